@@ -0,0 +1,386 @@
+use crate::{
+    alerts::{
+        rule::{Alert, AlertRule},
+        sink::{build_sink, AlertSink},
+    },
+    config::config::AlertsConfig,
+    data_processing::{
+        processor::{AccountData, TransactionData},
+        Processor,
+    },
+    data_retrieval::{
+        stream_hub::{StreamHub, Topic},
+        RpcSolanaClient,
+    },
+    data_storage::Storage,
+    webhooks::webhook::new_id,
+};
+use chrono::Utc;
+use solana_account_decoder::UiAccount;
+use solana_client::rpc_response::{Response, RpcLogsResponse};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc},
+    task::JoinHandle,
+};
+use tracing::{error, warn};
+
+/// Alerts waiting for a sink before new ones are dropped.
+const SINK_QUEUE_CAPACITY: usize = 1000;
+
+/// Evaluates the configured alert rules against processed transactions and account updates.
+///
+/// Rules watching the same address share one subscription: transactions mentioning it are
+/// fetched and processed once, and account updates are compared with the previous state of the
+/// account, needed to spot authority changes. Each sink of a rule sends the alerts from its own
+/// queue, so a slow sink never holds back the evaluation.
+pub struct AlertEngine {
+    rpc_solana_client: Arc<RpcSolanaClient>,
+    processor:         Arc<Processor>,
+    stream_hub:        Arc<StreamHub>,
+    /// Rules with the queues of their sinks.
+    rules:             Vec<(AlertRule, Vec<mpsc::Sender<Alert>>)>,
+    /// When each rule, by index, last raised an alert.
+    last_triggered:    Mutex<HashMap<usize, i64>>,
+    /// Last known state of the accounts watched by account rules.
+    accounts:          Mutex<HashMap<String, AccountData>>,
+}
+
+impl AlertEngine {
+    /// Builds the rules' sinks, each sending from a queue on its own task.
+    pub fn new(
+        config: AlertsConfig, rpc_solana_client: Arc<RpcSolanaClient>, processor: Arc<Processor>,
+        storage: Arc<Storage>, stream_hub: Arc<StreamHub>,
+    ) -> Self {
+        let rules = config
+            .rules
+            .into_iter()
+            .map(|rule| {
+                let sinks = rule
+                    .sinks
+                    .iter()
+                    .map(|sink| spawn_sink(build_sink(sink, &storage, config.history_size)))
+                    .collect();
+                (rule, sinks)
+            })
+            .collect();
+        AlertEngine {
+            rpc_solana_client,
+            processor,
+            stream_hub,
+            rules,
+            last_triggered: Mutex::new(HashMap::new()),
+            accounts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Starts one task per subscription the rules need.
+    pub fn start(self: &Arc<Self>) -> Vec<JoinHandle<()>> {
+        let mut topics: Vec<Topic> = vec![];
+        for (rule, _) in &self.rules {
+            let topic = rule.condition.topic();
+            if !topics.contains(&topic) {
+                topics.push(topic);
+            }
+        }
+        topics
+            .into_iter()
+            .map(|topic| {
+                let engine = Arc::clone(self);
+                tokio::spawn(async move { engine.watch(topic).await })
+            })
+            .collect()
+    }
+
+    async fn watch(&self, topic: Topic) {
+        let commitment = match topic {
+            Topic::Logs(_) => self.rpc_solana_client.transaction_commitment(),
+            _ => self.rpc_solana_client.commitment(None),
+        };
+        let mut receiver = match self
+            .stream_hub
+            .subscribe_internal(topic.clone(), commitment)
+        {
+            Ok(receiver) => receiver,
+            Err(e) => {
+                error!("Error subscribing to {:?} for alerts: {}", topic, e);
+                return;
+            }
+        };
+        if let Topic::Account(address) = &topic {
+            self.load_account(address).await;
+        }
+
+        loop {
+            match receiver.recv().await {
+                Ok(event) => match &topic {
+                    Topic::Logs(_) => {
+                        match serde_json::from_value::<Response<RpcLogsResponse>>(event.data) {
+                            Ok(response) => self.on_logs(&topic, response.value).await,
+                            Err(e) => warn!("Unexpected logs notification: {}", e),
+                        }
+                    }
+                    Topic::Account(address) => {
+                        match serde_json::from_value::<Response<UiAccount>>(event.data) {
+                            Ok(response) => {
+                                let account = response.value.decode().and_then(|account| {
+                                    self.processor.process_account(
+                                        account,
+                                        response.context.slot,
+                                        Utc::now().timestamp(),
+                                    )
+                                });
+                                if let Some(account_data) = account {
+                                    self.evaluate_account(&topic, address, account_data).await;
+                                }
+                            }
+                            Err(e) => warn!("Unexpected account notification: {}", e),
+                        }
+                    }
+                    _ => {}
+                },
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Skipped {} updates of {:?} for alerts", skipped, topic)
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Records the current state of the account, so the first update has something to compare to.
+    async fn load_account(&self, address: &str) {
+        match self
+            .rpc_solana_client
+            .get_account(address, self.rpc_solana_client.commitment(None), None)
+            .await
+        {
+            Ok(response) => {
+                if let Some(account_data) = self.processor.process_account(
+                    response.value,
+                    response.context.slot,
+                    response.fetched_at,
+                ) {
+                    self.accounts
+                        .lock()
+                        .unwrap()
+                        .entry(address.to_string())
+                        .or_insert(account_data);
+                }
+            }
+            Err(e) => warn!("Error fetching account {} for alerts: {}", address, e),
+        }
+    }
+
+    /// Fetches the transaction behind the logs if one of the rules of the topic wants it.
+    async fn on_logs(&self, topic: &Topic, logs: RpcLogsResponse) {
+        let failed = logs.err.is_some();
+        if !self
+            .rules_of(topic)
+            .any(|(_, rule)| rule.condition.wants_transaction(failed))
+        {
+            return;
+        }
+
+        let encoded_transaction = match self
+            .rpc_solana_client
+            .get_transaction(
+                &logs.signature,
+                self.rpc_solana_client.transaction_commitment(),
+            )
+            .await
+        {
+            Ok(encoded_transaction) => encoded_transaction,
+            Err(e) => {
+                error!(
+                    "Error fetching transaction {} for alerts: {}",
+                    logs.signature, e
+                );
+                return;
+            }
+        };
+        if let Some(transaction_data) = self.processor.process_transaction(encoded_transaction) {
+            self.evaluate_transaction(topic, &transaction_data).await;
+        }
+    }
+
+    async fn evaluate_transaction(&self, topic: &Topic, transaction_data: &TransactionData) {
+        for (index, rule) in self.rules_of(topic) {
+            if let Some(message) = rule.condition.check_transaction(transaction_data) {
+                self.trigger(
+                    index,
+                    message,
+                    transaction_data.signature().map(str::to_string),
+                    transaction_data.slot,
+                );
+            }
+        }
+    }
+
+    async fn evaluate_account(&self, topic: &Topic, address: &str, account_data: AccountData) {
+        let slot = account_data.context_slot;
+        let previous = self
+            .accounts
+            .lock()
+            .unwrap()
+            .insert(address.to_string(), account_data.clone());
+        for (index, rule) in self.rules_of(topic) {
+            if let Some(message) = rule
+                .condition
+                .check_account(&account_data, previous.as_ref())
+            {
+                self.trigger(index, message, None, Some(slot));
+            }
+        }
+    }
+
+    fn rules_of<'a>(&'a self, topic: &'a Topic) -> impl Iterator<Item = (usize, &'a AlertRule)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(move |(_, (rule, _))| &rule.condition.topic() == topic)
+            .map(|(index, (rule, _))| (index, rule))
+    }
+
+    /// Raises an alert of the rule unless it already did within its debounce window.
+    fn trigger(&self, index: usize, message: String, signature: Option<String>, slot: Option<u64>) {
+        let (rule, sinks) = &self.rules[index];
+        let now = Utc::now().timestamp();
+        {
+            let mut last_triggered = self.last_triggered.lock().unwrap();
+            if last_triggered
+                .get(&index)
+                .is_some_and(|last| now - last < rule.debounce_secs as i64)
+            {
+                return;
+            }
+            last_triggered.insert(index, now);
+        }
+
+        let alert = Alert {
+            id: new_id(),
+            rule: rule.name.clone(),
+            severity: rule.severity,
+            message,
+            address: rule.condition.address().to_string(),
+            signature,
+            slot,
+            triggered_at: now,
+        };
+        for sink in sinks {
+            if sink.try_send(alert.clone()).is_err() {
+                warn!(
+                    "Dropping alert {} of rule {}: sink queue full",
+                    alert.id, alert.rule
+                );
+            }
+        }
+    }
+}
+
+fn spawn_sink(sink: Arc<dyn AlertSink>) -> mpsc::Sender<Alert> {
+    let (sender, mut receiver) = mpsc::channel::<Alert>(SINK_QUEUE_CAPACITY);
+    tokio::spawn(async move {
+        while let Some(alert) = receiver.recv().await {
+            sink.send(&alert).await;
+        }
+    });
+    sender
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alerts::rule::{Condition, Severity, SinkConfig},
+        config::config::StreamConfig,
+        test_fixtures::{self, UNREACHABLE_RPC_URL},
+    };
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn engine(rules: Vec<AlertRule>, storage: Arc<Storage>) -> AlertEngine {
+        let rpc_solana_client = test_fixtures::rpc_client(UNREACHABLE_RPC_URL);
+        AlertEngine::new(
+            AlertsConfig {
+                history_size: 10,
+                rules,
+            },
+            Arc::clone(&rpc_solana_client),
+            Arc::new(Processor),
+            storage,
+            test_fixtures::stream_hub(rpc_solana_client, StreamConfig::default()),
+        )
+    }
+
+    fn low_balance_rule(debounce_secs: u64) -> AlertRule {
+        AlertRule {
+            name: "low balance".to_string(),
+            severity: Severity::Critical,
+            condition: Condition::LowBalance {
+                account: "Account".to_string(),
+                min_sol: 1.0,
+            },
+            debounce_secs,
+            sinks: vec![SinkConfig::Store],
+        }
+    }
+
+    fn account(lamports: u64) -> AccountData {
+        serde_json::from_value(json!({
+            "lamports": lamports,
+            "data": [],
+            "owner": "11111111111111111111111111111111",
+            "executable": false,
+            "rent_epoch": 0,
+            "token_account": null,
+            "mint": null,
+            "context_slot": 7,
+            "fetched_at": 0
+        }))
+        .unwrap()
+    }
+
+    /// Alerts stored once the sinks caught up with the `expected` ones, and any extra.
+    async fn stored_alerts(storage: &Storage, expected: usize) -> Vec<Alert> {
+        while storage.get_alerts().len() < expected {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        storage.get_alerts()
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_account_debounces() {
+        let storage = Arc::new(Storage::default());
+        let engine = engine(vec![low_balance_rule(60)], Arc::clone(&storage));
+        let topic = Topic::Account("Account".to_string());
+
+        engine.evaluate_account(&topic, "Account", account(1)).await;
+        engine.evaluate_account(&topic, "Account", account(2)).await;
+
+        let alerts = stored_alerts(&storage, 1).await;
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].rule, "low balance");
+        assert_eq!(alerts[0].severity, Severity::Critical);
+        assert_eq!(alerts[0].address, "Account");
+        assert_eq!(alerts[0].slot, Some(7));
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_account_without_debounce() {
+        let storage = Arc::new(Storage::default());
+        let engine = engine(vec![low_balance_rule(0)], Arc::clone(&storage));
+        let topic = Topic::Account("Account".to_string());
+
+        engine.evaluate_account(&topic, "Account", account(1)).await;
+        engine
+            .evaluate_account(&topic, "Account", account(2_000_000_000))
+            .await;
+        engine.evaluate_account(&topic, "Account", account(2)).await;
+
+        assert_eq!(stored_alerts(&storage, 2).await.len(), 2);
+    }
+}
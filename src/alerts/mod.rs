@@ -0,0 +1,5 @@
+pub mod engine;
+pub mod rule;
+pub mod sink;
+
+pub use engine::AlertEngine;
@@ -0,0 +1,348 @@
+use crate::{
+    data_processing::{
+        processor::{AccountData, TransactionData},
+        token::parse_mint,
+    },
+    data_retrieval::stream_hub::Topic,
+};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    #[default]
+    Warning,
+    Critical,
+}
+
+/// Rule raising an alert whenever its condition holds, at most once per `debounce_secs`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlertRule {
+    pub name:          String,
+    #[serde(default)]
+    pub severity:      Severity,
+    pub condition:     Condition,
+    #[serde(default)]
+    pub debounce_secs: u64,
+    #[serde(default = "default_sinks")]
+    pub sinks:         Vec<SinkConfig>,
+}
+
+fn default_sinks() -> Vec<SinkConfig> {
+    vec![SinkConfig::Log, SinkConfig::Store]
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// A transaction takes at least `min_sol` out of the wallet.
+    SolOutflow { wallet: String, min_sol: f64 },
+    /// A transaction invoking the program fails.
+    FailedTransaction { program_id: String },
+    /// An update leaves the account with less than `min_sol`.
+    LowBalance { account: String, min_sol: f64 },
+    /// The mint or freeze authority of the mint changes.
+    MintAuthorityChanged { mint: String },
+}
+
+/// Where the alerts of a rule are sent.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    Log,
+    /// The alert table served by `/api/alerts`.
+    Store,
+    /// POSTs the alert as JSON.
+    Webhook {
+        url: String,
+    },
+}
+
+/// Alert raised by a rule.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Alert {
+    pub id:           String,
+    pub rule:         String,
+    pub severity:     Severity,
+    pub message:      String,
+    /// Address the condition is about.
+    pub address:      String,
+    pub signature:    Option<String>,
+    pub slot:         Option<u64>,
+    pub triggered_at: i64,
+}
+
+impl AlertRule {
+    /// Checks the address of the condition is a valid pubkey.
+    pub fn validate(&self) -> Result<(), String> {
+        let address = self.condition.address();
+        Pubkey::from_str(address).map(|_| ()).map_err(|e| {
+            format!(
+                "alert rule {}: invalid address {}: {}",
+                self.name, address, e
+            )
+        })
+    }
+}
+
+impl Condition {
+    pub fn address(&self) -> &str {
+        match self {
+            Condition::SolOutflow { wallet, .. } => wallet,
+            Condition::FailedTransaction { program_id } => program_id,
+            Condition::LowBalance { account, .. } => account,
+            Condition::MintAuthorityChanged { mint } => mint,
+        }
+    }
+
+    /// Subscription whose updates the condition is evaluated against.
+    pub fn topic(&self) -> Topic {
+        let address = self.address().to_string();
+        match self {
+            Condition::SolOutflow { .. } | Condition::FailedTransaction { .. } => {
+                Topic::Logs(Some(address))
+            }
+            Condition::LowBalance { .. } | Condition::MintAuthorityChanged { .. } => {
+                Topic::Account(address)
+            }
+        }
+    }
+
+    /// Whether evaluating the condition needs a transaction that failed or succeeded.
+    pub fn wants_transaction(&self, failed: bool) -> bool {
+        match self {
+            Condition::SolOutflow { .. } => !failed,
+            Condition::FailedTransaction { .. } => failed,
+            Condition::LowBalance { .. } | Condition::MintAuthorityChanged { .. } => false,
+        }
+    }
+
+    /// Describes why the transaction triggers the condition, `None` if it does not.
+    pub fn check_transaction(&self, transaction_data: &TransactionData) -> Option<String> {
+        match self {
+            Condition::SolOutflow { wallet, min_sol } => {
+                let outflow = transaction_data
+                    .lamport_change(wallet)
+                    .min(0)
+                    .unsigned_abs();
+                (transaction_data.error.is_none()
+                    && outflow > 0
+                    && outflow >= sol_to_lamports(*min_sol))
+                .then(|| format!("{} SOL left wallet {}", lamports_to_sol(outflow), wallet))
+            }
+            Condition::FailedTransaction { program_id } => transaction_data
+                .error
+                .as_ref()
+                .filter(|_| transaction_data.involves(program_id))
+                .map(|error| {
+                    format!(
+                        "transaction invoking program {} failed: {}",
+                        program_id, error
+                    )
+                }),
+            Condition::LowBalance { .. } | Condition::MintAuthorityChanged { .. } => None,
+        }
+    }
+
+    /// Describes why the account update triggers the condition, `None` if it does not.
+    ///
+    /// `previous` is the state of the account before the update, if known.
+    pub fn check_account(
+        &self, account_data: &AccountData, previous: Option<&AccountData>,
+    ) -> Option<String> {
+        match self {
+            Condition::LowBalance { account, min_sol } => {
+                (account_data.lamports < sol_to_lamports(*min_sol)).then(|| {
+                    format!(
+                        "account {} holds {} SOL, below {} SOL",
+                        account,
+                        lamports_to_sol(account_data.lamports),
+                        min_sol
+                    )
+                })
+            }
+            Condition::MintAuthorityChanged { mint } => {
+                let authorities = |account_data: &AccountData| {
+                    let owner = Pubkey::from_str(&account_data.owner).ok()?;
+                    let mint_info = parse_mint(mint, &owner, &account_data.data)?;
+                    Some((mint_info.mint_authority, mint_info.freeze_authority))
+                };
+                let (mint_authority, freeze_authority) = authorities(account_data)?;
+                let (previous_mint_authority, previous_freeze_authority) = authorities(previous?)?;
+                let mut changes = vec![];
+                if mint_authority != previous_mint_authority {
+                    changes.push(format!(
+                        "mint authority changed from {} to {}",
+                        previous_mint_authority.as_deref().unwrap_or("none"),
+                        mint_authority.as_deref().unwrap_or("none")
+                    ));
+                }
+                if freeze_authority != previous_freeze_authority {
+                    changes.push(format!(
+                        "freeze authority changed from {} to {}",
+                        previous_freeze_authority.as_deref().unwrap_or("none"),
+                        freeze_authority.as_deref().unwrap_or("none")
+                    ));
+                }
+                (!changes.is_empty()).then(|| format!("mint {}: {}", mint, changes.join(", ")))
+            }
+            Condition::SolOutflow { .. } | Condition::FailedTransaction { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+    use serde_json::json;
+    use solana_sdk::pubkey;
+
+    fn transaction(error: Option<&str>) -> TransactionData {
+        test_fixtures::transaction(json!({
+            "accounts": [
+                {"pubkey": "Wallet", "writable": true, "signer": true, "lamport_change": -150000000000i64},
+                {"pubkey": "Program", "writable": false, "signer": false}
+            ],
+            "error": error
+        }))
+    }
+
+    fn account(lamports: u64, owner: &Pubkey, data: Vec<u8>) -> AccountData {
+        serde_json::from_value(json!({
+            "lamports": lamports,
+            "data": data,
+            "owner": owner.to_string(),
+            "executable": false,
+            "rent_epoch": 0,
+            "token_account": null,
+            "mint": null,
+            "context_slot": 1,
+            "fetched_at": 0
+        }))
+        .unwrap()
+    }
+
+    fn mint(mint_authority: Option<Pubkey>) -> AccountData {
+        let mut data = vec![];
+        match mint_authority {
+            Some(authority) => {
+                data.extend_from_slice(&1u32.to_le_bytes());
+                data.extend_from_slice(authority.as_ref());
+            }
+            None => data.extend_from_slice(&[0; 36]),
+        }
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(6);
+        data.push(1);
+        data.extend_from_slice(&[0; 36]);
+        account(
+            1,
+            &pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+            data,
+        )
+    }
+
+    #[test]
+    fn test_sol_outflow() {
+        let condition = |min_sol| Condition::SolOutflow {
+            wallet: "Wallet".to_string(),
+            min_sol,
+        };
+
+        assert_eq!(
+            condition(100.0).check_transaction(&transaction(None)),
+            Some("150 SOL left wallet Wallet".to_string())
+        );
+        assert!(condition(200.0)
+            .check_transaction(&transaction(None))
+            .is_none());
+        assert!(condition(100.0)
+            .check_transaction(&transaction(Some("InstructionError")))
+            .is_none());
+    }
+
+    #[test]
+    fn test_failed_transaction() {
+        let condition = Condition::FailedTransaction {
+            program_id: "Program".to_string(),
+        };
+        let other = Condition::FailedTransaction {
+            program_id: "Other".to_string(),
+        };
+
+        assert!(condition
+            .check_transaction(&transaction(Some("InstructionError")))
+            .unwrap()
+            .contains("InstructionError"));
+        assert!(condition.check_transaction(&transaction(None)).is_none());
+        assert!(other
+            .check_transaction(&transaction(Some("InstructionError")))
+            .is_none());
+    }
+
+    #[test]
+    fn test_low_balance() {
+        let condition = Condition::LowBalance {
+            account: "Account".to_string(),
+            min_sol: 1.0,
+        };
+        let owner = Pubkey::new_unique();
+
+        assert!(condition
+            .check_account(&account(999_999_999, &owner, vec![]), None)
+            .is_some());
+        assert!(condition
+            .check_account(&account(1_000_000_000, &owner, vec![]), None)
+            .is_none());
+    }
+
+    #[test]
+    fn test_mint_authority_changed() {
+        let address = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let condition = Condition::MintAuthorityChanged {
+            mint: address.to_string(),
+        };
+
+        let message = condition
+            .check_account(&mint(None), Some(&mint(Some(authority))))
+            .unwrap();
+
+        assert!(message.contains(&format!(
+            "mint authority changed from {} to none",
+            authority
+        )));
+        assert!(condition
+            .check_account(&mint(Some(authority)), Some(&mint(Some(authority))))
+            .is_none());
+        // Nothing to compare against before the first update.
+        assert!(condition.check_account(&mint(None), None).is_none());
+    }
+
+    #[test]
+    fn test_rule_from_toml() {
+        let rule: AlertRule = toml::from_str(
+            r#"
+            name = "treasury outflow"
+            condition = { type = "sol_outflow", wallet = "Wallet", min_sol = 100.0 }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(rule.severity, Severity::Warning);
+        assert_eq!(rule.debounce_secs, 0);
+        assert_eq!(rule.sinks, vec![SinkConfig::Log, SinkConfig::Store]);
+        assert_eq!(
+            rule.condition.topic(),
+            Topic::Logs(Some("Wallet".to_string()))
+        );
+    }
+}
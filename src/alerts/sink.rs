@@ -0,0 +1,84 @@
+use crate::{
+    alerts::rule::{Alert, Severity, SinkConfig},
+    data_storage::Storage,
+};
+use async_trait::async_trait;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info, warn};
+
+/// Time a webhook sink has to accept an alert.
+const WEBHOOK_SINK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Destination of the alerts raised by a rule.
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    async fn send(&self, alert: &Alert);
+}
+
+/// Logs the alert at a level matching its severity.
+pub struct LogSink;
+
+#[async_trait]
+impl AlertSink for LogSink {
+    async fn send(&self, alert: &Alert) {
+        match alert.severity {
+            Severity::Info => info!("Alert {}: {}", alert.rule, alert.message),
+            Severity::Warning => warn!("Alert {}: {}", alert.rule, alert.message),
+            Severity::Critical => error!("Alert {}: {}", alert.rule, alert.message),
+        }
+    }
+}
+
+/// Stores the alert in the table served by `/api/alerts`.
+pub struct StorageSink {
+    storage:      Arc<Storage>,
+    history_size: usize,
+}
+
+#[async_trait]
+impl AlertSink for StorageSink {
+    async fn send(&self, alert: &Alert) {
+        self.storage.insert_alert(alert.clone(), self.history_size);
+    }
+}
+
+/// POSTs the alert as JSON, once, logging failures.
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url:    String,
+}
+
+#[async_trait]
+impl AlertSink for WebhookSink {
+    async fn send(&self, alert: &Alert) {
+        let result = self
+            .client
+            .post(&self.url)
+            .json(alert)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            warn!("Failed to send alert {} to {}: {}", alert.id, self.url, e);
+        }
+    }
+}
+
+pub fn build_sink(
+    config: &SinkConfig, storage: &Arc<Storage>, history_size: usize,
+) -> Arc<dyn AlertSink> {
+    match config {
+        SinkConfig::Log => Arc::new(LogSink),
+        SinkConfig::Store => Arc::new(StorageSink {
+            storage: Arc::clone(storage),
+            history_size,
+        }),
+        SinkConfig::Webhook { url } => Arc::new(WebhookSink {
+            client: reqwest::Client::builder()
+                .timeout(WEBHOOK_SINK_TIMEOUT)
+                .build()
+                .expect("default TLS backend is available"),
+            url:    url.clone(),
+        }),
+    }
+}
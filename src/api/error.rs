@@ -1,33 +1,174 @@
+use crate::data_retrieval::{error::RetrievalErrorKind, RetrievalError};
 use axum::{http::StatusCode, response::IntoResponse, Json};
 use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
 #[derive(Debug, Error)]
 pub enum ApiError {
-    #[error("Not found")]
-    NotFound,
-    #[error("Internal server error")]
-    InternalError,
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Rate limited")]
+    RateLimited,
+    #[error("Upstream timeout")]
+    UpstreamTimeout,
+    #[error("Upstream error")]
+    UpstreamError { retryable: bool },
+    #[error("Decode failure: {0}")]
+    DecodeFailure(String),
+    /// Failure of the service itself, its cause logged rather than answered.
+    #[error("Internal error")]
+    Internal,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ErrorMessage {
-    pub code:    u16,
-    pub message: String,
+    pub code:       u16,
+    /// Stable, machine-readable identifier of the error kind.
+    pub error_code: &'static str,
+    pub message:    String,
+    /// Whether the same request may succeed if retried later.
+    pub retryable:  bool,
+}
+
+impl ApiError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::UpstreamTimeout => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::UpstreamError { .. } | ApiError::DecodeFailure(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn retryable(&self) -> bool {
+        match self {
+            ApiError::RateLimited | ApiError::UpstreamTimeout => true,
+            ApiError::UpstreamError { retryable } => *retryable,
+            _ => false,
+        }
+    }
+
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "invalid_input",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::RateLimited => "rate_limited",
+            ApiError::UpstreamTimeout => "upstream_timeout",
+            ApiError::UpstreamError { .. } => "upstream_error",
+            ApiError::DecodeFailure(_) => "decode_failure",
+            ApiError::Internal => "internal_error",
+        }
+    }
+}
+
+impl From<RetrievalError> for ApiError {
+    fn from(e: RetrievalError) -> Self {
+        // Upstream messages may leak provider URLs or keys, only client mistakes are echoed back.
+        match e.kind() {
+            RetrievalErrorKind::InvalidInput => ApiError::BadRequest(e.message),
+            RetrievalErrorKind::NotFound => ApiError::NotFound(e.message),
+            RetrievalErrorKind::RateLimited => ApiError::RateLimited,
+            RetrievalErrorKind::UpstreamTimeout => ApiError::UpstreamTimeout,
+            RetrievalErrorKind::UpstreamError => ApiError::UpstreamError {
+                retryable: e.is_retryable(),
+            },
+            RetrievalErrorKind::DecodeFailure => ApiError::DecodeFailure(e.message),
+        }
+    }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
-        let (status, error_message) = match self {
-            ApiError::NotFound => (StatusCode::NOT_FOUND, "Resource not found"),
-            ApiError::InternalError => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
-        };
-
+        let status = self.status_code();
         let body = Json(ErrorMessage {
-            code:    status.as_u16(),
-            message: error_message.to_string(),
+            code:       status.as_u16(),
+            error_code: self.error_code(),
+            message:    self.to_string(),
+            retryable:  self.retryable(),
         });
 
         (status, body).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retrieval_error_status_codes() {
+        let cases = [
+            (
+                RetrievalErrorKind::InvalidInput,
+                StatusCode::BAD_REQUEST,
+                "invalid_input",
+            ),
+            (
+                RetrievalErrorKind::NotFound,
+                StatusCode::NOT_FOUND,
+                "not_found",
+            ),
+            (
+                RetrievalErrorKind::RateLimited,
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+            ),
+            (
+                RetrievalErrorKind::UpstreamTimeout,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "upstream_timeout",
+            ),
+            (
+                RetrievalErrorKind::UpstreamError,
+                StatusCode::BAD_GATEWAY,
+                "upstream_error",
+            ),
+            (
+                RetrievalErrorKind::DecodeFailure,
+                StatusCode::BAD_GATEWAY,
+                "decode_failure",
+            ),
+        ];
+
+        for (kind, status, error_code) in cases {
+            let api_error = ApiError::from(RetrievalError::new(kind, "message"));
+            assert_eq!(api_error.status_code(), status);
+            assert_eq!(api_error.error_code(), error_code);
+            assert_eq!(api_error.error_code(), kind.as_str());
+        }
+    }
+
+    #[test]
+    fn test_upstream_error_message_is_not_echoed() {
+        let api_error = ApiError::from(RetrievalError::new(
+            RetrievalErrorKind::UpstreamError,
+            "https://provider.example/?api-key=secret",
+        ));
+
+        assert!(!api_error.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn test_upstream_error_keeps_retryable() {
+        let mut retrieval_error = RetrievalError::new(RetrievalErrorKind::UpstreamError, "503");
+        retrieval_error.retryable = true;
+
+        assert!(ApiError::from(retrieval_error).retryable());
+        assert!(!ApiError::NotFound("missing".to_string()).retryable());
+    }
+
+    #[test]
+    fn test_internal_error() {
+        assert_eq!(
+            ApiError::Internal.status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(ApiError::Internal.error_code(), "internal_error");
+        assert!(!ApiError::Internal.retryable());
+    }
+}
@@ -0,0 +1,827 @@
+use crate::{
+    api::{
+        error::ApiError,
+        handlers::{context_headers, CommitmentQuery, MAX_BATCH_ACCOUNTS, MAX_PAGE_LIMIT},
+    },
+    data_processing::{
+        processor::{self, AccountData, BlockData, InstructionData, TokenBalanceChange},
+        token::{MintInfo, TokenAccountData},
+        Processor, TokenRegistry,
+    },
+    data_retrieval::{error::RetrievalErrorKind, RetrievalError, RpcSolanaClient},
+    data_storage::{storage::TransactionFilter, Storage},
+};
+use async_graphql::{
+    dataloader::{DataLoader, Loader},
+    http::GraphiQLSource,
+    Context, EmptyMutation, EmptySubscription, ErrorExtensions, Object, Schema,
+};
+use axum::{
+    extract::Query,
+    http::HeaderMap,
+    response::{Html, IntoResponse},
+    Extension, Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::future::join_all;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use std::{cmp::Reverse, collections::HashMap, str::FromStr, sync::Arc};
+use tracing::error;
+
+/// Deepest selection a query may nest.
+const MAX_QUERY_DEPTH: usize = 10;
+
+/// Most fields a query may select, counting list fields once per requested item, bounding the
+/// RPC calls a single request fans out to, aliased root fields included.
+const MAX_QUERY_COMPLEXITY: usize = 500;
+
+pub type GraphQlSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn build_schema() -> GraphQlSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .finish()
+}
+
+/// State the resolvers of one request share.
+///
+/// The loaders live as long as the request, so every account or transaction it mentions is
+/// fetched once, and the accounts resolved by sibling fields in one `getMultipleAccounts` call.
+struct RequestData {
+    rpc_solana_client: Arc<RpcSolanaClient>,
+    processor:         Arc<Processor>,
+    token_registry:    Arc<TokenRegistry>,
+    storage:           Arc<Storage>,
+    commitment:        CommitmentConfig,
+    accounts:          DataLoader<AccountLoader>,
+    transactions:      DataLoader<TransactionLoader>,
+}
+
+impl RequestData {
+    fn new(
+        rpc_solana_client: Arc<RpcSolanaClient>, processor: Arc<Processor>,
+        token_registry: Arc<TokenRegistry>, storage: Arc<Storage>, commitment: CommitmentConfig,
+    ) -> Self {
+        let accounts = DataLoader::new(
+            AccountLoader {
+                rpc_solana_client: Arc::clone(&rpc_solana_client),
+                processor: Arc::clone(&processor),
+                token_registry: Arc::clone(&token_registry),
+                commitment,
+            },
+            tokio::spawn,
+        );
+        let transactions = DataLoader::new(
+            TransactionLoader {
+                rpc_solana_client: Arc::clone(&rpc_solana_client),
+                processor: Arc::clone(&processor),
+                token_registry: Arc::clone(&token_registry),
+                storage: Arc::clone(&storage),
+                commitment,
+            },
+            tokio::spawn,
+        );
+        RequestData {
+            rpc_solana_client,
+            processor,
+            token_registry,
+            storage,
+            commitment,
+            accounts,
+            transactions,
+        }
+    }
+}
+
+/// Executes a GraphQL query, at the commitment given in the query string.
+#[utoipa::path(
+    post,
+    path = "/api/graphql",
+    tag = "graphql",
+    params(CommitmentQuery),
+    request_body(
+        content = Object,
+        description = "GraphQL request with a `query`, and optional `variables` and `operationName`"
+    ),
+    responses(
+        (status = 200, description = "GraphQL response with `data` and `errors`", body = Object)
+    )
+)]
+pub async fn graphql(
+    Extension(schema): Extension<GraphQlSchema>,
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(processor): Extension<Arc<Processor>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>,
+    Extension(storage): Extension<Arc<Storage>>, Query(query): Query<CommitmentQuery>,
+    Json(request): Json<async_graphql::Request>,
+) -> (HeaderMap, Json<async_graphql::Response>) {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    let request = request.data(RequestData::new(
+        rpc_solana_client,
+        processor,
+        token_registry,
+        storage,
+        commitment,
+    ));
+    (
+        context_headers(commitment, None),
+        Json(schema.execute(request).await),
+    )
+}
+
+/// Serves GraphiQL, to explore the schema from a browser.
+#[utoipa::path(
+    get,
+    path = "/api/graphql",
+    tag = "graphql",
+    responses(
+        (status = 200, description = "GraphiQL page", content_type = "text/html")
+    )
+)]
+pub async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/api/graphql").finish())
+}
+
+/// Converts the error like the REST API does, adding its code and whether to retry as extensions.
+fn graphql_error(e: impl Into<ApiError>) -> async_graphql::Error {
+    let e = e.into();
+    async_graphql::Error::new(e.to_string()).extend_with(|_, extensions| {
+        extensions.set("code", e.error_code());
+        extensions.set("retryable", e.retryable());
+    })
+}
+
+/// Batches the account lookups of a request into `getMultipleAccounts` calls.
+struct AccountLoader {
+    rpc_solana_client: Arc<RpcSolanaClient>,
+    processor:         Arc<Processor>,
+    token_registry:    Arc<TokenRegistry>,
+    commitment:        CommitmentConfig,
+}
+
+impl Loader<String> for AccountLoader {
+    type Value = AccountData;
+    type Error = RetrievalError;
+
+    async fn load(&self, pubkeys: &[String]) -> Result<HashMap<String, AccountData>, Self::Error> {
+        let response = self
+            .rpc_solana_client
+            .get_multiple_accounts(pubkeys, self.commitment, None)
+            .await?;
+        let context_slot = response.context.slot;
+        let fetched_at = response.fetched_at;
+
+        let mut accounts = HashMap::new();
+        for (pubkey, account) in pubkeys.iter().zip(response.value) {
+            let Some(mut account_data) = account.and_then(|account| {
+                self.processor
+                    .process_account(account, context_slot, fetched_at)
+            }) else {
+                continue;
+            };
+            self.token_registry
+                .enrich_account(pubkey, &mut account_data)
+                .await;
+            accounts.insert(pubkey.clone(), account_data);
+        }
+        Ok(accounts)
+    }
+}
+
+/// Resolves transactions from storage, fetching the missing ones concurrently.
+struct TransactionLoader {
+    rpc_solana_client: Arc<RpcSolanaClient>,
+    processor:         Arc<Processor>,
+    token_registry:    Arc<TokenRegistry>,
+    storage:           Arc<Storage>,
+    commitment:        CommitmentConfig,
+}
+
+impl TransactionLoader {
+    async fn fetch(
+        &self, signature: &str,
+    ) -> Result<Option<processor::TransactionData>, RetrievalError> {
+        if let Some(transaction_data) = self.storage.get_transaction(signature) {
+            return Ok(Some(transaction_data));
+        }
+        let encoded_transaction = match self
+            .rpc_solana_client
+            .get_transaction(signature, self.commitment)
+            .await
+        {
+            Ok(encoded_transaction) => encoded_transaction,
+            Err(e) if e.kind() == RetrievalErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let Some(mut transaction_data) = self.processor.process_transaction(encoded_transaction)
+        else {
+            return Err(RetrievalError::decode_failure(format!(
+                "unsupported format of transaction {}",
+                signature
+            )));
+        };
+        self.token_registry
+            .enrich_transaction(&mut transaction_data)
+            .await;
+        self.storage.insert_transaction(transaction_data.clone());
+        Ok(Some(transaction_data))
+    }
+}
+
+impl Loader<String> for TransactionLoader {
+    type Value = processor::TransactionData;
+    type Error = RetrievalError;
+
+    async fn load(
+        &self, signatures: &[String],
+    ) -> Result<HashMap<String, processor::TransactionData>, Self::Error> {
+        let results = join_all(signatures.iter().map(|signature| self.fetch(signature))).await;
+        let mut transactions = HashMap::new();
+        for (signature, result) in signatures.iter().zip(results) {
+            if let Some(transaction_data) = result? {
+                transactions.insert(signature.clone(), transaction_data);
+            }
+        }
+        Ok(transactions)
+    }
+}
+
+async fn load_account(ctx: &Context<'_>, pubkey: &str) -> async_graphql::Result<Option<Account>> {
+    let data = ctx.data::<RequestData>()?;
+    match data.accounts.load_one(pubkey.to_string()).await {
+        Ok(account_data) => Ok(account_data.map(|account_data| Account {
+            pubkey: pubkey.to_string(),
+            account_data,
+        })),
+        Err(e) => {
+            error!("Error fetching account {}: {:?}", pubkey, e);
+            Err(graphql_error(e))
+        }
+    }
+}
+
+fn check_pubkey(pubkey: &str) -> async_graphql::Result<()> {
+    Pubkey::from_str(pubkey)
+        .map(|_| ())
+        .map_err(|e| graphql_error(RetrievalError::invalid_pubkey(pubkey, e)))
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Block at the slot, with its processed transactions.
+    async fn block(&self, ctx: &Context<'_>, slot: u64) -> async_graphql::Result<Block> {
+        let data = ctx.data::<RequestData>()?;
+        let encoded_block = match data
+            .rpc_solana_client
+            .get_transaction_by_slot(slot, data.commitment)
+            .await
+        {
+            Ok(encoded_block) => encoded_block,
+            Err(e) => {
+                error!("Error fetching block by slot {}: {:?}", slot, e);
+                return Err(graphql_error(e));
+            }
+        };
+        let Some(mut block_data) = data.processor.process_block(slot, encoded_block) else {
+            error!("Failed to process block for slot {}", slot);
+            return Err(graphql_error(ApiError::DecodeFailure(format!(
+                "unsupported format of block {}",
+                slot
+            ))));
+        };
+        for transaction_data in block_data.transactions.iter_mut().flatten() {
+            data.token_registry
+                .enrich_transaction(transaction_data)
+                .await;
+            data.storage.insert_transaction(transaction_data.clone());
+        }
+        data.storage.insert_block(slot, &block_data);
+        Ok(Block { slot, block_data })
+    }
+
+    /// Transaction with the signature, `null` if the node does not know it.
+    async fn transaction(
+        &self, ctx: &Context<'_>, signature: String,
+    ) -> async_graphql::Result<Option<Transaction>> {
+        Signature::from_str(&signature)
+            .map_err(|e| graphql_error(RetrievalError::invalid_signature(&signature, e)))?;
+        let data = ctx.data::<RequestData>()?;
+        match data.transactions.load_one(signature.clone()).await {
+            Ok(transaction_data) => Ok(transaction_data.map(Transaction)),
+            Err(e) => {
+                error!("Error fetching transaction {}: {:?}", signature, e);
+                Err(graphql_error(e))
+            }
+        }
+    }
+
+    /// Account at the pubkey, `null` if it does not exist.
+    async fn account(
+        &self, ctx: &Context<'_>, pubkey: String,
+    ) -> async_graphql::Result<Option<Account>> {
+        check_pubkey(&pubkey)?;
+        load_account(ctx, &pubkey).await
+    }
+
+    /// Accounts in the order of `pubkeys`, `null` for those that do not exist.
+    #[graphql(complexity = "pubkeys.len().max(1) * child_complexity")]
+    async fn accounts(
+        &self, ctx: &Context<'_>, pubkeys: Vec<String>,
+    ) -> async_graphql::Result<Vec<Option<Account>>> {
+        if pubkeys.len() > MAX_BATCH_ACCOUNTS {
+            return Err(graphql_error(ApiError::BadRequest(format!(
+                "at most {} pubkeys can be requested at once",
+                MAX_BATCH_ACCOUNTS
+            ))));
+        }
+        for pubkey in &pubkeys {
+            check_pubkey(pubkey)?;
+        }
+        let data = ctx.data::<RequestData>()?;
+        let mut accounts = match data.accounts.load_many(pubkeys.iter().cloned()).await {
+            Ok(accounts) => accounts,
+            Err(e) => {
+                error!("Error fetching {} accounts: {:?}", pubkeys.len(), e);
+                return Err(graphql_error(e));
+            }
+        };
+        Ok(pubkeys
+            .into_iter()
+            .map(|pubkey| {
+                let account_data = accounts.remove(&pubkey)?;
+                Some(Account {
+                    pubkey,
+                    account_data,
+                })
+            })
+            .collect())
+    }
+}
+
+pub struct Block {
+    slot:       u64,
+    block_data: BlockData,
+}
+
+#[Object]
+impl Block {
+    async fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    async fn blockhash(&self) -> &str {
+        &self.block_data.blockhash
+    }
+
+    async fn previous_blockhash(&self) -> &str {
+        &self.block_data.previous_blockhash
+    }
+
+    async fn parent_slot(&self) -> u64 {
+        self.block_data.parent_slot
+    }
+
+    async fn block_time(&self) -> Option<i64> {
+        self.block_data.block_time
+    }
+
+    async fn block_height(&self) -> Option<u64> {
+        self.block_data.block_height
+    }
+
+    /// Transactions of the block in block order, those in unsupported formats left out.
+    async fn transactions(&self) -> Vec<Transaction> {
+        self.block_data
+            .transactions
+            .iter()
+            .flatten()
+            .cloned()
+            .map(Transaction)
+            .collect()
+    }
+}
+
+pub struct Transaction(processor::TransactionData);
+
+#[Object]
+impl Transaction {
+    async fn signature(&self) -> Option<&str> {
+        self.0.signature()
+    }
+
+    async fn slot(&self) -> Option<u64> {
+        self.0.slot
+    }
+
+    async fn block_time(&self) -> Option<i64> {
+        self.0.block_time
+    }
+
+    async fn blockhash(&self) -> &str {
+        self.0.blockhash()
+    }
+
+    /// Fee in lamports.
+    async fn fee(&self) -> Option<u64> {
+        self.0.fee
+    }
+
+    async fn error(&self) -> Option<&str> {
+        self.0.error.as_deref()
+    }
+
+    async fn success(&self) -> bool {
+        self.0.error.is_none()
+    }
+
+    async fn accounts(&self) -> Vec<TransactionAccount> {
+        self.0
+            .accounts()
+            .iter()
+            .cloned()
+            .map(TransactionAccount)
+            .collect()
+    }
+
+    async fn instructions(&self) -> Vec<Instruction> {
+        self.0
+            .instructions()
+            .iter()
+            .cloned()
+            .map(Instruction)
+            .collect()
+    }
+
+    async fn token_transfers(&self) -> Vec<TokenTransfer> {
+        self.0
+            .token_balances
+            .iter()
+            .cloned()
+            .map(TokenTransfer)
+            .collect()
+    }
+}
+
+/// Account as referenced by a transaction.
+pub struct TransactionAccount(processor::Account);
+
+#[Object]
+impl TransactionAccount {
+    async fn pubkey(&self) -> &str {
+        self.0.pubkey()
+    }
+
+    async fn writable(&self) -> bool {
+        self.0.writable()
+    }
+
+    async fn signer(&self) -> bool {
+        self.0.signer()
+    }
+
+    async fn lamport_change(&self) -> i64 {
+        self.0.lamport_change()
+    }
+
+    /// Current state of the account.
+    async fn account(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Account>> {
+        load_account(ctx, self.0.pubkey()).await
+    }
+}
+
+pub struct Instruction(InstructionData);
+
+#[Object]
+impl Instruction {
+    async fn program_id(&self) -> &str {
+        self.0.program_id()
+    }
+
+    /// Base58 encoded data, empty for instructions parsed by the node.
+    async fn data(&self) -> &str {
+        self.0.data()
+    }
+
+    /// Type of instructions parsed by the node, e.g. `transfer`.
+    async fn instruction_type(&self) -> Option<&str> {
+        self.0.instruction_type()
+    }
+
+    async fn program(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Account>> {
+        load_account(ctx, self.0.program_id()).await
+    }
+}
+
+/// Change of a token balance made by a transaction, amounts in base units.
+pub struct TokenTransfer(TokenBalanceChange);
+
+#[Object]
+impl TokenTransfer {
+    /// The token account.
+    async fn account(&self) -> &str {
+        &self.0.account
+    }
+
+    async fn mint(&self) -> &str {
+        &self.0.mint
+    }
+
+    async fn owner(&self) -> Option<&str> {
+        self.0.owner.as_deref()
+    }
+
+    async fn program_id(&self) -> Option<&str> {
+        self.0.program_id.as_deref()
+    }
+
+    async fn decimals(&self) -> u8 {
+        self.0.decimals
+    }
+
+    async fn pre_amount(&self) -> Option<&str> {
+        self.0.pre_amount.as_deref()
+    }
+
+    async fn post_amount(&self) -> Option<&str> {
+        self.0.post_amount.as_deref()
+    }
+
+    /// Signed difference of the amounts, as a string since it may exceed 53 bits.
+    async fn change(&self) -> String {
+        let amount = |amount: &Option<String>| {
+            amount
+                .as_deref()
+                .and_then(|amount| amount.parse::<i128>().ok())
+                .unwrap_or_default()
+        };
+        (amount(&self.0.post_amount) - amount(&self.0.pre_amount)).to_string()
+    }
+
+    async fn name(&self) -> Option<&str> {
+        self.0.name.as_deref()
+    }
+
+    async fn symbol(&self) -> Option<&str> {
+        self.0.symbol.as_deref()
+    }
+}
+
+pub struct Account {
+    pubkey:       String,
+    account_data: AccountData,
+}
+
+#[Object]
+impl Account {
+    async fn pubkey(&self) -> &str {
+        &self.pubkey
+    }
+
+    async fn lamports(&self) -> u64 {
+        self.account_data.lamports
+    }
+
+    async fn owner(&self) -> &str {
+        &self.account_data.owner
+    }
+
+    async fn executable(&self) -> bool {
+        self.account_data.executable
+    }
+
+    async fn rent_epoch(&self) -> u64 {
+        self.account_data.rent_epoch
+    }
+
+    /// Base64 encoded data.
+    async fn data(&self) -> String {
+        STANDARD.encode(&self.account_data.data)
+    }
+
+    /// Slot of the RPC response the account was read from.
+    async fn context_slot(&self) -> u64 {
+        self.account_data.context_slot
+    }
+
+    /// Decoded state if the account is a token account.
+    async fn token_account(&self) -> Option<TokenAccount> {
+        self.account_data.token_account.clone().map(TokenAccount)
+    }
+
+    /// Decoded state if the account is a mint.
+    async fn mint(&self) -> Option<Mint> {
+        self.account_data.mint.clone().map(Mint)
+    }
+
+    /// Stored transactions involving the account, newest first, those without a slot leading.
+    #[graphql(complexity = "limit.clamp(1, MAX_PAGE_LIMIT) * child_complexity")]
+    async fn transactions(
+        &self, ctx: &Context<'_>, #[graphql(default = 20)] limit: usize,
+    ) -> async_graphql::Result<Vec<Transaction>> {
+        let data = ctx.data::<RequestData>()?;
+        let filter = TransactionFilter {
+            account: Some(self.pubkey.clone()),
+            ..TransactionFilter::default()
+        };
+        let mut transactions = data.storage.search_transactions(&filter);
+        transactions
+            .sort_by_key(|transaction_data| Reverse(transaction_data.slot.unwrap_or(u64::MAX)));
+        Ok(transactions
+            .into_iter()
+            .take(limit.min(MAX_PAGE_LIMIT))
+            .map(Transaction)
+            .collect())
+    }
+}
+
+pub struct TokenAccount(TokenAccountData);
+
+#[Object]
+impl TokenAccount {
+    async fn mint(&self) -> &str {
+        &self.0.mint
+    }
+
+    async fn owner(&self) -> &str {
+        &self.0.owner
+    }
+
+    /// Amount in base units.
+    async fn amount(&self) -> &str {
+        &self.0.amount
+    }
+
+    async fn decimals(&self) -> Option<u8> {
+        self.0.decimals
+    }
+
+    async fn ui_amount_string(&self) -> Option<&str> {
+        self.0.ui_amount_string.as_deref()
+    }
+
+    async fn name(&self) -> Option<&str> {
+        self.0.name.as_deref()
+    }
+
+    async fn symbol(&self) -> Option<&str> {
+        self.0.symbol.as_deref()
+    }
+}
+
+pub struct Mint(MintInfo);
+
+#[Object]
+impl Mint {
+    async fn address(&self) -> &str {
+        &self.0.mint
+    }
+
+    async fn program_id(&self) -> &str {
+        &self.0.program_id
+    }
+
+    async fn decimals(&self) -> u8 {
+        self.0.decimals
+    }
+
+    async fn supply(&self) -> &str {
+        &self.0.supply
+    }
+
+    async fn mint_authority(&self) -> Option<&str> {
+        self.0.mint_authority.as_deref()
+    }
+
+    async fn freeze_authority(&self) -> Option<&str> {
+        self.0.freeze_authority.as_deref()
+    }
+
+    async fn name(&self) -> Option<&str> {
+        self.0.name.as_deref()
+    }
+
+    async fn symbol(&self) -> Option<&str> {
+        self.0.symbol.as_deref()
+    }
+
+    async fn uri(&self) -> Option<&str> {
+        self.0.uri.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{self, UNREACHABLE_RPC_URL};
+    use serde_json::json;
+
+    async fn execute(storage: Arc<Storage>, query: &str) -> serde_json::Value {
+        let rpc_solana_client = test_fixtures::rpc_client(UNREACHABLE_RPC_URL);
+        let token_registry = Arc::new(TokenRegistry::new(
+            Arc::clone(&rpc_solana_client),
+            Arc::clone(&storage),
+        ));
+        let commitment = rpc_solana_client.commitment(None);
+        let request = async_graphql::Request::new(query).data(RequestData::new(
+            rpc_solana_client,
+            Arc::new(Processor),
+            token_registry,
+            storage,
+            commitment,
+        ));
+        serde_json::to_value(build_schema().execute(request).await).unwrap()
+    }
+
+    fn transaction(signature: &str) -> processor::TransactionData {
+        test_fixtures::transaction(json!({
+            "signatures": [signature],
+            "accounts": [
+                {"pubkey": "Payer", "writable": true, "signer": true, "lamport_change": -5000}
+            ],
+            "instructions": [{
+                "program_id": "11111111111111111111111111111111",
+                "data": "",
+                "instruction_type": "transfer"
+            }],
+            "token_balances": [{
+                "account": "TokenAccount",
+                "mint": "Mint",
+                "owner": "Payer",
+                "program_id": null,
+                "decimals": 6,
+                "pre_amount": "1500000",
+                "post_amount": "500000",
+                "name": null,
+                "symbol": "USDC"
+            }],
+            "slot": 7,
+            "fee": 5000
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_transaction_from_storage() {
+        let storage = Arc::new(Storage::default());
+        let signature = Signature::new_unique().to_string();
+        storage.insert_transaction(transaction(&signature));
+
+        let response = execute(
+            storage,
+            &format!(
+                r#"{{ transaction(signature: "{}") {{
+                    slot fee success
+                    accounts {{ pubkey signer lamportChange }}
+                    instructions {{ programId instructionType }}
+                    tokenTransfers {{ mint symbol change }}
+                }} }}"#,
+                signature
+            ),
+        )
+        .await;
+
+        assert_eq!(
+            response["data"]["transaction"],
+            json!({
+                "slot": 7,
+                "fee": 5000,
+                "success": true,
+                "accounts": [{"pubkey": "Payer", "signer": true, "lamportChange": -5000}],
+                "instructions": [{
+                    "programId": "11111111111111111111111111111111",
+                    "instructionType": "transfer"
+                }],
+                "tokenTransfers": [{"mint": "Mint", "symbol": "USDC", "change": "-1000000"}]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_input() {
+        let response = execute(
+            Arc::new(Storage::default()),
+            r#"{ account(pubkey: "invalid") { lamports } }"#,
+        )
+        .await;
+
+        assert_eq!(response["data"], serde_json::Value::Null);
+        assert_eq!(response["errors"][0]["extensions"]["code"], "invalid_input");
+        assert_eq!(response["errors"][0]["extensions"]["retryable"], false);
+    }
+
+    #[tokio::test]
+    async fn test_query_complexity_limit() {
+        let blocks: String = (0 .. 200)
+            .map(|slot| format!("b{}: block(slot: {}) {{ slot blockhash }} ", slot, slot))
+            .collect();
+        let response = execute(Arc::new(Storage::default()), &format!("{{ {} }}", blocks)).await;
+
+        assert_eq!(response["data"], serde_json::Value::Null);
+        assert!(response["errors"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("complex"));
+    }
+}
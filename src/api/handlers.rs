@@ -1,71 +1,2186 @@
 use crate::{
-    api::error::ApiError,
+    alerts::rule::{Alert, Severity},
+    api::{
+        error::{ApiError, ErrorMessage},
+        openapi::commitment_schema,
+    },
     data_processing::{
         processor::{AccountData, BlockData, TransactionData},
-        Processor,
+        token::{MintInfo, Portfolio},
+        Processor, TokenRegistry,
+    },
+    data_retrieval::{
+        cache::CacheMetrics,
+        retry::CallMetrics,
+        rpc_pool::EndpointStatus,
+        stream_hub::{StreamEvent, StreamHub, Topic},
+        RpcSolanaClient,
+    },
+    data_storage::{
+        storage::{AccountSnapshot, TransactionFilter},
+        Storage,
+    },
+    export::{Dataset, ExportQuery, ExportWriter, Exporter, Format},
+    webhooks::{
+        webhook::{DeadLetter, Delivery, DeliveryStatus, Webhook, WebhookFilter},
+        WebhookDispatcher,
+    },
+};
+use axum::{
+    body::Body,
+    extract::{Path, Query},
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
     },
-    data_retrieval::RpcSolanaClient,
+    Extension, Json,
 };
-use axum::{extract::Path, Extension, Json};
-use std::sync::Arc;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use futures::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::{parse_token::spl_token_ids, UiDataSliceConfig};
+use solana_client::{
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+};
+use std::{collections::BTreeMap, convert::Infallible, str::FromStr, sync::Arc, time::Duration};
+use tokio::sync::broadcast;
 use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+/// Largest page served by list endpoints.
+pub const MAX_PAGE_LIMIT: usize = 100;
+const DEFAULT_PAGE_LIMIT: usize = 20;
+/// Signatures account history inspects per request before returning a partial page.
+const MAX_SCANNED_SIGNATURES: usize = 1000;
+
+/// Paging, ordering and filters shared by the list endpoints.
+///
+/// Items missing the attribute a filter looks at, e.g. the slot of an undelivered webhook payload,
+/// do not match it.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    pub limit:     Option<usize>,
+    /// `next_cursor` of the previous page.
+    pub cursor:    Option<String>,
+    pub sort:      Option<SortOrder>,
+    pub from_slot: Option<u64>,
+    pub to_slot:   Option<u64>,
+    /// Unix timestamps, inclusive like the slot range.
+    pub from_time: Option<i64>,
+    pub to_time:   Option<i64>,
+    pub status:    Option<StatusFilter>,
+    /// Only transactions invoking or mentioning this address.
+    pub program:   Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// Oldest first.
+    Asc,
+    /// Newest first.
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusFilter {
+    Success,
+    Failed,
+}
+
+/// One page of a list endpoint.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Page<T> {
+    pub items:       Vec<T>,
+    /// Pass as `cursor` to fetch the next page, absent on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Page of account history, also positioned for clients paginating with `before`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountHistory {
+    #[serde(flatten)]
+    pub page:        Page<TransactionData>,
+    /// Pass as `before` to fetch the next page, absent on the last page.
+    pub next_before: Option<String>,
+}
+
+/// Attributes of a listed item the filters of `ListQuery` look at.
+pub trait Listed {
+    fn slot(&self) -> Option<u64> {
+        None
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        None
+    }
+
+    fn succeeded(&self) -> Option<bool> {
+        None
+    }
+
+    fn involves(&self, _address: &str) -> bool {
+        false
+    }
+}
+
+impl ListQuery {
+    fn limit(&self) -> usize {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_LIMIT)
+            .clamp(1, MAX_PAGE_LIMIT)
+    }
+
+    fn sort(&self, default: SortOrder) -> SortOrder {
+        self.sort.unwrap_or(default)
+    }
+
+    fn has_filters(&self) -> bool {
+        self.from_slot.is_some()
+            || self.to_slot.is_some()
+            || self.from_time.is_some()
+            || self.to_time.is_some()
+            || self.status.is_some()
+            || self.program.is_some()
+    }
+
+    fn matches(&self, item: &impl Listed) -> bool {
+        within(item.slot(), self.from_slot, self.to_slot)
+            && within(item.timestamp(), self.from_time, self.to_time)
+            && self
+                .status
+                .is_none_or(|status| item.succeeded() == Some(status == StatusFilter::Success))
+            && self
+                .program
+                .as_deref()
+                .is_none_or(|program| item.involves(program))
+    }
+
+    /// Whether the item is older than the start of the slot or time range.
+    fn precedes_range(&self, item: &impl Listed) -> bool {
+        self.from_slot
+            .is_some_and(|from| item.slot().is_some_and(|slot| slot < from))
+            || self
+                .from_time
+                .is_some_and(|from| item.timestamp().is_some_and(|time| time < from))
+    }
+
+    /// Key of the last item of the previous page.
+    fn cursor_key(&self) -> Result<Option<String>, ApiError> {
+        self.cursor
+            .as_deref()
+            .map(|cursor| {
+                URL_SAFE_NO_PAD
+                    .decode(cursor)
+                    .ok()
+                    .and_then(|key| String::from_utf8(key).ok())
+                    .ok_or_else(|| ApiError::BadRequest(format!("invalid cursor {}", cursor)))
+            })
+            .transpose()
+    }
+}
+
+fn within<N: PartialOrd + Copy>(value: Option<N>, from: Option<N>, to: Option<N>) -> bool {
+    if from.is_none() && to.is_none() {
+        return true;
+    }
+    value.is_some_and(|value| {
+        from.is_none_or(|from| value >= from) && to.is_none_or(|to| value <= to)
+    })
+}
+
+fn encode_cursor(key: &str) -> String {
+    URL_SAFE_NO_PAD.encode(key)
+}
+
+/// Filters, orders and pages items listed oldest first, each with a key identifying it.
+fn paginate<T: Listed>(
+    items: Vec<(String, T)>, query: &ListQuery, default_sort: SortOrder,
+) -> Result<Page<T>, ApiError> {
+    paginate_resuming(items, query, default_sort, |_, _, _| None)
+}
 
+/// Like `paginate`, but when the cursor's item is gone asks `resume` where the page starts, given
+/// the sort, the listed items in page order and the cursor key.
+fn paginate_resuming<T: Listed>(
+    items: Vec<(String, T)>, query: &ListQuery, default_sort: SortOrder,
+    resume: impl Fn(SortOrder, &[(String, T)], &str) -> Option<usize>,
+) -> Result<Page<T>, ApiError> {
+    let sort = query.sort(default_sort);
+    let mut items: Vec<_> = items
+        .into_iter()
+        .filter(|(_, item)| query.matches(item))
+        .collect();
+    if sort == SortOrder::Desc {
+        items.reverse();
+    }
+
+    let start = match query.cursor_key()? {
+        Some(key) => match items.iter().position(|(item_key, _)| *item_key == key) {
+            Some(position) => position + 1,
+            None => resume(sort, &items, &key).ok_or_else(|| {
+                ApiError::BadRequest("cursor no longer points into the list".to_string())
+            })?,
+        },
+        None => 0,
+    };
+    let limit = query.limit();
+    let mut page: Vec<_> = items.into_iter().skip(start).take(limit + 1).collect();
+    let next_cursor = if page.len() > limit {
+        page.truncate(limit);
+        page.last().map(|(key, _)| encode_cursor(key))
+    } else {
+        None
+    };
+
+    Ok(Page {
+        items: page.into_iter().map(|(_, item)| item).collect(),
+        next_cursor,
+    })
+}
+
+impl Listed for TransactionData {
+    fn slot(&self) -> Option<u64> {
+        self.slot
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        self.block_time
+    }
+
+    fn succeeded(&self) -> Option<bool> {
+        Some(self.error.is_none())
+    }
+
+    fn involves(&self, address: &str) -> bool {
+        TransactionData::involves(self, address)
+    }
+}
+
+/// Only carries what is known before the transaction is fetched, the program is checked after.
+impl Listed for RpcConfirmedTransactionStatusWithSignature {
+    fn slot(&self) -> Option<u64> {
+        Some(self.slot)
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        self.block_time
+    }
+
+    fn succeeded(&self) -> Option<bool> {
+        Some(self.err.is_none())
+    }
+
+    fn involves(&self, _address: &str) -> bool {
+        true
+    }
+}
+
+impl Listed for AccountSnapshot {
+    fn slot(&self) -> Option<u64> {
+        Some(self.account.context_slot)
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        Some(self.captured_at)
+    }
+}
+
+impl Listed for Alert {
+    fn slot(&self) -> Option<u64> {
+        self.slot
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        Some(self.triggered_at)
+    }
+}
+
+impl Listed for Webhook {
+    fn timestamp(&self) -> Option<i64> {
+        Some(self.created_at)
+    }
+}
+
+impl Listed for Delivery {
+    fn timestamp(&self) -> Option<i64> {
+        Some(self.created_at)
+    }
+
+    fn succeeded(&self) -> Option<bool> {
+        match self.status {
+            DeliveryStatus::Pending => None,
+            DeliveryStatus::Delivered => Some(true),
+            DeliveryStatus::Failed => Some(false),
+        }
+    }
+}
+
+impl Listed for DeadLetter {
+    fn slot(&self) -> Option<u64> {
+        self.payload.slot
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        Some(self.failed_at)
+    }
+
+    fn involves(&self, address: &str) -> bool {
+        self.payload.involves(address)
+    }
+}
+
+/// Commitment requested by the caller, the configured default when absent.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CommitmentQuery {
+    #[param(schema_with = commitment_schema)]
+    pub commitment: Option<CommitmentLevel>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AccountQuery {
+    #[param(schema_with = commitment_schema)]
+    pub commitment:       Option<CommitmentLevel>,
+    /// Reject responses from nodes that have not reached this slot yet.
+    pub min_context_slot: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LogsStreamQuery {
+    /// Only stream logs of transactions mentioning this address.
+    pub mentions:   Option<String>,
+    #[param(schema_with = commitment_schema)]
+    pub commitment: Option<CommitmentLevel>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    /// Start after this signature, exclusive; `cursor` is preferred.
+    pub before:     Option<String>,
+    /// Stop at this signature, exclusive.
+    pub until:      Option<String>,
+    #[param(schema_with = commitment_schema)]
+    pub commitment: Option<CommitmentLevel>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AlertQuery {
+    pub rule:     Option<String>,
+    pub severity: Option<Severity>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WebhookRequest {
+    pub url:    String,
+    #[serde(flatten)]
+    pub filter: WebhookFilter,
+}
+
+/// Webhook as registered, the only response including its signing secret.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RegisteredWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret:  String,
+}
+
+/// Largest number of pubkeys accepted by the batch account lookup.
+pub const MAX_BATCH_ACCOUNTS: usize = 1000;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AccountsRequest {
+    pub pubkeys: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountLookupStatus {
+    Found,
+    NotFound,
+    InvalidPubkey,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountLookup {
+    pub pubkey:  String,
+    pub status:  AccountLookupStatus,
+    pub account: Option<AccountData>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProgramAccountsQuery {
+    /// Only return accounts whose data is exactly this many bytes.
+    pub data_size:         Option<u64>,
+    /// Comma separated `offset:bytes` pairs, with `bytes` base58 encoded.
+    pub memcmp:            Option<String>,
+    pub data_slice_offset: Option<usize>,
+    pub data_slice_length: Option<usize>,
+    /// Store each returned account as a snapshot, up to `max_snapshots` being kept.
+    #[serde(default)]
+    pub persist:           bool,
+    #[param(schema_with = commitment_schema)]
+    pub commitment:        Option<CommitmentLevel>,
+    pub min_context_slot:  Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProgramAccount {
+    pub pubkey:  String,
+    pub account: AccountData,
+}
+
+impl ProgramAccountsQuery {
+    fn filters(&self) -> Option<Vec<RpcFilterType>> {
+        let mut filters = vec![];
+        if let Some(data_size) = self.data_size {
+            filters.push(RpcFilterType::DataSize(data_size));
+        }
+        for memcmp in self.memcmp.iter().flat_map(|memcmp| memcmp.split(',')) {
+            let (offset, bytes) = memcmp.split_once(':')?;
+            filters.push(RpcFilterType::Memcmp(Memcmp::new(
+                offset.trim().parse().ok()?,
+                MemcmpEncodedBytes::Base58(bytes.trim().to_string()),
+            )));
+        }
+        filters
+            .iter()
+            .all(|filter| filter.verify().is_ok())
+            .then_some(filters)
+    }
+
+    fn data_slice(&self) -> Option<UiDataSliceConfig> {
+        match (self.data_slice_offset, self.data_slice_length) {
+            (None, None) => None,
+            (offset, length) => Some(UiDataSliceConfig {
+                offset: offset.unwrap_or(0),
+                length: length.unwrap_or(usize::MAX),
+            }),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/transaction/signature/{signature}",
+    tag = "transactions",
+    params(
+        ("signature" = String, Path, description = "Base58 transaction signature"),
+        CommitmentQuery
+    ),
+    responses(
+        (status = 200, description = "Processed transaction", body = TransactionData, headers(("x-commitment" = String), ("x-slot" = u64))),
+        (status = 400, description = "Invalid signature", body = ErrorMessage),
+        (status = 404, description = "Transaction not found", body = ErrorMessage),
+        (status = 429, description = "Rate limited by the RPC provider", body = ErrorMessage),
+        (status = 502, description = "Upstream error or undecodable response", body = ErrorMessage),
+        (status = 503, description = "Upstream timeout", body = ErrorMessage)
+    )
+)]
 pub async fn get_transaction_by_signature(
     Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
-    Extension(processor): Extension<Arc<Processor>>, Path(signature): Path<String>,
-) -> Result<Json<TransactionData>, ApiError> {
-    match rpc_solana_client.get_transaction(&signature).await {
-        Ok(encoded_transaction) => match processor.process_transaction(encoded_transaction) {
-            Some(transaction_data) => Ok(Json(transaction_data)),
-            None => {
-                error!("Transaction not found for signature {}", &signature);
-                Err(ApiError::NotFound)
-            }
-        },
+    Extension(processor): Extension<Arc<Processor>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>,
+    Extension(storage): Extension<Arc<Storage>>, Path(signature): Path<String>,
+    Query(query): Query<CommitmentQuery>,
+) -> Result<(HeaderMap, Json<TransactionData>), ApiError> {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    let encoded_transaction = match rpc_solana_client
+        .get_transaction(&signature, commitment)
+        .await
+    {
+        Ok(encoded_transaction) => encoded_transaction,
         Err(e) => {
             error!(
                 "Error fetching transaction by signature {}: {:?}",
                 &signature, e
             );
-            Err(ApiError::InternalError)
+            return Err(e.into());
+        }
+    };
+
+    let slot = encoded_transaction.slot;
+    match processor.process_transaction(encoded_transaction) {
+        Some(mut transaction_data) => {
+            token_registry
+                .enrich_transaction(&mut transaction_data)
+                .await;
+            storage.insert_transaction(transaction_data.clone());
+            Ok((slot_headers(commitment, slot), Json(transaction_data)))
+        }
+        None => {
+            error!(
+                "Unsupported transaction format for signature {}",
+                &signature
+            );
+            Err(ApiError::DecodeFailure(format!(
+                "unsupported format of transaction {}",
+                signature
+            )))
         }
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/transaction/slot/{slot}",
+    tag = "transactions",
+    params(
+        ("slot" = u64, Path),
+        CommitmentQuery
+    ),
+    responses(
+        (status = 200, description = "Processed block", body = BlockData, headers(("x-commitment" = String), ("x-slot" = u64))),
+        (status = 400, description = "Invalid slot", body = ErrorMessage),
+        (status = 404, description = "Block not found", body = ErrorMessage),
+        (status = 429, description = "Rate limited by the RPC provider", body = ErrorMessage),
+        (status = 502, description = "Upstream error or undecodable response", body = ErrorMessage),
+        (status = 503, description = "Upstream timeout", body = ErrorMessage)
+    )
+)]
 pub async fn get_transaction_by_slot(
     Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
-    Extension(processor): Extension<Arc<Processor>>, Path(slot): Path<u64>,
-) -> Result<Json<BlockData>, ApiError> {
-    match rpc_solana_client.get_transaction_by_slot(slot).await {
-        Ok(encoded_block) => match processor.process_block(encoded_block) {
-            Some(block_data) => Ok(Json(block_data)),
-            None => {
-                error!("block not found for slot {}", &slot);
-                Err(ApiError::NotFound)
+    Extension(processor): Extension<Arc<Processor>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>,
+    Extension(storage): Extension<Arc<Storage>>, Path(slot): Path<u64>,
+    Query(query): Query<CommitmentQuery>,
+) -> Result<(HeaderMap, Json<BlockData>), ApiError> {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    let encoded_block = match rpc_solana_client
+        .get_transaction_by_slot(slot, commitment)
+        .await
+    {
+        Ok(encoded_block) => encoded_block,
+        Err(e) => {
+            error!("Error fetching block by slot {}: {:?}", &slot, e);
+            return Err(e.into());
+        }
+    };
+
+    match processor.process_block(slot, encoded_block) {
+        Some(mut block_data) => {
+            for transaction_data in block_data.transactions.iter_mut().flatten() {
+                token_registry.enrich_transaction(transaction_data).await;
+                storage.insert_transaction(transaction_data.clone());
             }
-        },
+            storage.insert_block(slot, &block_data);
+            Ok((slot_headers(commitment, slot), Json(block_data)))
+        }
+        None => {
+            error!("Failed to process block for slot {}", &slot);
+            Err(ApiError::DecodeFailure(format!(
+                "unsupported format of block {}",
+                slot
+            )))
+        }
+    }
+}
+
+/// Returns the processed transactions of the block, in block order unless sorted otherwise.
+#[utoipa::path(
+    get,
+    path = "/api/block/{slot}/transactions",
+    tag = "transactions",
+    params(
+        ("slot" = u64, Path),
+        CommitmentQuery,
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "Processed transactions of the block", body = Page<TransactionData>, headers(("x-commitment" = String), ("x-slot" = u64))),
+        (status = 400, description = "Invalid slot or query", body = ErrorMessage),
+        (status = 404, description = "Block not found", body = ErrorMessage),
+        (status = 429, description = "Rate limited by the RPC provider", body = ErrorMessage),
+        (status = 502, description = "Upstream error or undecodable response", body = ErrorMessage),
+        (status = 503, description = "Upstream timeout", body = ErrorMessage)
+    )
+)]
+pub async fn get_block_transactions(
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(processor): Extension<Arc<Processor>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>,
+    Extension(storage): Extension<Arc<Storage>>, Path(slot): Path<u64>,
+    Query(query): Query<CommitmentQuery>, Query(list_query): Query<ListQuery>,
+) -> Result<(HeaderMap, Json<Page<TransactionData>>), ApiError> {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    let encoded_block = match rpc_solana_client
+        .get_transaction_by_slot(slot, commitment)
+        .await
+    {
+        Ok(encoded_block) => encoded_block,
         Err(e) => {
             error!("Error fetching block by slot {}: {:?}", &slot, e);
-            Err(ApiError::InternalError)
+            return Err(e.into());
         }
+    };
+    let Some(block_data) = processor.process_block(slot, encoded_block) else {
+        error!("Failed to process block for slot {}", &slot);
+        return Err(ApiError::DecodeFailure(format!(
+            "unsupported format of block {}",
+            slot
+        )));
+    };
+    storage.insert_block(slot, &block_data);
+
+    // The position in the block identifies a transaction, unprocessed ones included.
+    let transactions = block_data
+        .transactions
+        .into_iter()
+        .enumerate()
+        .filter_map(|(position, transaction_data)| {
+            transaction_data.map(|transaction_data| (position.to_string(), transaction_data))
+        })
+        .collect();
+    let mut page = paginate(transactions, &list_query, SortOrder::Asc)?;
+    for transaction_data in page.items.iter_mut() {
+        token_registry.enrich_transaction(transaction_data).await;
+        storage.insert_transaction(transaction_data.clone());
     }
+    Ok((slot_headers(commitment, slot), Json(page)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/account/{pubkey}",
+    tag = "accounts",
+    params(
+        ("pubkey" = String, Path),
+        AccountQuery
+    ),
+    responses(
+        (status = 200, description = "Processed account", body = AccountData, headers(("x-commitment" = String), ("x-context-slot" = u64))),
+        (status = 400, description = "Invalid pubkey", body = ErrorMessage),
+        (status = 404, description = "Account not found", body = ErrorMessage),
+        (status = 429, description = "Rate limited by the RPC provider", body = ErrorMessage),
+        (status = 502, description = "Upstream error or undecodable response", body = ErrorMessage),
+        (status = 503, description = "Upstream timeout", body = ErrorMessage)
+    )
+)]
 pub async fn get_account_by_pubkey(
     Path(pubkey): Path<String>, Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
     Extension(processor): Extension<Arc<Processor>>,
-) -> Result<Json<AccountData>, ApiError> {
-    match rpc_solana_client.get_account(&pubkey).await {
-        Ok(account) => match processor.process_account(account) {
-            Some(account_data) => Ok(Json(account_data)),
-            None => {
-                error!("Account not found {}", &pubkey);
-                Err(ApiError::NotFound)
+    Extension(token_registry): Extension<Arc<TokenRegistry>>, Query(query): Query<AccountQuery>,
+) -> Result<(HeaderMap, Json<AccountData>), ApiError> {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    let response = match rpc_solana_client
+        .get_account(&pubkey, commitment, query.min_context_slot)
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Error fetching Account {}: {:?}", &pubkey, e);
+            return Err(e.into());
+        }
+    };
+
+    match processor.process_account(response.value, response.context.slot, response.fetched_at) {
+        Some(mut account_data) => {
+            token_registry
+                .enrich_account(&pubkey, &mut account_data)
+                .await;
+            Ok((
+                context_headers(commitment, Some(response.context.slot)),
+                Json(account_data),
+            ))
+        }
+        None => {
+            error!("Failed to process Account {}", &pubkey);
+            Err(ApiError::DecodeFailure(format!(
+                "unsupported account {}",
+                pubkey
+            )))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/token/{mint}",
+    tag = "tokens",
+    params(
+        ("mint" = String, Path),
+        AccountQuery
+    ),
+    responses(
+        (status = 200, description = "Mint with its metadata", body = MintInfo, headers(("x-commitment" = String))),
+        (status = 400, description = "Invalid mint", body = ErrorMessage),
+        (status = 404, description = "Mint not found", body = ErrorMessage),
+        (status = 429, description = "Rate limited by the RPC provider", body = ErrorMessage),
+        (status = 502, description = "Upstream error or undecodable response", body = ErrorMessage),
+        (status = 503, description = "Upstream timeout", body = ErrorMessage)
+    )
+)]
+pub async fn get_mint(
+    Path(mint): Path<String>, Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>, Query(query): Query<AccountQuery>,
+) -> Result<(HeaderMap, Json<MintInfo>), ApiError> {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    match token_registry
+        .get_mint(&mint, commitment, query.min_context_slot)
+        .await
+    {
+        Ok(Some(mint_info)) => Ok((context_headers(commitment, None), Json(mint_info))),
+        Ok(None) => {
+            error!("Account {} is not a token mint", &mint);
+            Err(ApiError::NotFound(format!(
+                "account {} is not a token mint",
+                mint
+            )))
+        }
+        Err(e) => {
+            error!("Error fetching mint {}: {:?}", &mint, e);
+            Err(e.into())
+        }
+    }
+}
+
+/// Returns call, retry and failure counters of every RPC method used so far.
+#[utoipa::path(
+    get,
+    path = "/api/metrics/rpc",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Counters by RPC method", body = BTreeMap<String, CallMetrics>)
+    )
+)]
+pub async fn get_rpc_metrics(
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+) -> Json<BTreeMap<&'static str, CallMetrics>> {
+    Json(rpc_solana_client.metrics())
+}
+
+/// Returns hit, miss and eviction counters of the RPC response cache.
+#[utoipa::path(
+    get,
+    path = "/api/metrics/cache",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Counters of the RPC response cache", body = CacheMetrics)
+    )
+)]
+pub async fn get_cache_metrics(
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+) -> Json<CacheMetrics> {
+    Json(rpc_solana_client.cache_metrics())
+}
+
+/// Returns the health, slot and lag of every RPC endpoint in the pool.
+#[utoipa::path(
+    get,
+    path = "/api/rpc/endpoints",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Health of every RPC endpoint", body = Vec<EndpointStatus>)
+    )
+)]
+pub async fn get_rpc_endpoints(
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+) -> Json<Vec<EndpointStatus>> {
+    Json(rpc_solana_client.endpoint_status())
+}
+
+/// Searches the stored transactions, oldest slot first unless sorted otherwise.
+///
+/// Only transactions the aggregator has processed are found, the RPC node is not queried.
+#[utoipa::path(
+    get,
+    path = "/api/transactions/search",
+    tag = "transactions",
+    params(
+        TransactionFilter,
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "Matching stored transactions", body = Page<TransactionData>),
+        (status = 400, description = "Invalid query", body = ErrorMessage)
+    )
+)]
+pub async fn search_transactions(
+    Query(filter): Query<TransactionFilter>, Query(list_query): Query<ListQuery>,
+    Extension(storage): Extension<Arc<Storage>>,
+) -> Result<Json<Page<TransactionData>>, ApiError> {
+    let transactions = storage
+        .search_transactions(&filter)
+        .into_iter()
+        .filter_map(|transaction_data| {
+            let signature = transaction_data.signature()?.to_string();
+            Some((signature, transaction_data))
+        })
+        .collect();
+    paginate(transactions, &list_query, SortOrder::Asc).map(Json)
+}
+
+/// Returns processed transactions of the account, newest first.
+///
+/// Signatures are filtered by slot, time and status before their transactions are fetched. At
+/// most `MAX_SCANNED_SIGNATURES` are inspected per request, so a page may hold fewer items than
+/// `limit` and still have a `next_cursor`.
+#[utoipa::path(
+    get,
+    path = "/api/account/{pubkey}/transactions",
+    tag = "accounts",
+    params(
+        ("pubkey" = String, Path),
+        HistoryQuery,
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "Processed transactions of the account", body = AccountHistory, headers(("x-commitment" = String), ("x-context-slot" = u64))),
+        (status = 400, description = "Invalid pubkey or query", body = ErrorMessage),
+        (status = 429, description = "Rate limited by the RPC provider", body = ErrorMessage),
+        (status = 502, description = "Upstream error or undecodable response", body = ErrorMessage),
+        (status = 503, description = "Upstream timeout", body = ErrorMessage)
+    )
+)]
+pub async fn get_account_transactions(
+    Path(pubkey): Path<String>, Query(query): Query<HistoryQuery>,
+    Query(list_query): Query<ListQuery>,
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(processor): Extension<Arc<Processor>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>,
+    Extension(storage): Extension<Arc<Storage>>,
+) -> Result<(HeaderMap, Json<AccountHistory>), ApiError> {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    if list_query.cursor.is_some() && query.before.is_some() {
+        return Err(ApiError::BadRequest(
+            "cursor and before cannot be combined".to_string(),
+        ));
+    }
+    if list_query.sort(SortOrder::Desc) == SortOrder::Asc {
+        return Err(ApiError::BadRequest(
+            "account history is only sorted newest first".to_string(),
+        ));
+    }
+    let limit = list_query.limit();
+    // Without filters every signature ends up in the page, so there is no need to fetch more.
+    let batch_size = if list_query.has_filters() {
+        MAX_PAGE_LIMIT
+    } else {
+        limit
+    };
+
+    let mut before = list_query.cursor_key()?.or(query.before);
+    let mut transactions = Vec::with_capacity(limit);
+    let mut scanned = 0;
+    let mut next_before = None;
+    'batches: loop {
+        let statuses = match rpc_solana_client
+            .get_signatures_for_address(
+                &pubkey,
+                before.as_deref(),
+                query.until.as_deref(),
+                Some(batch_size),
+                commitment,
+            )
+            .await
+        {
+            Ok(statuses) => statuses,
+            Err(e) => {
+                error!("Error fetching signatures for account {}: {:?}", &pubkey, e);
+                return Err(e.into());
+            }
+        };
+        let exhausted = statuses.len() < batch_size;
+
+        for status in statuses {
+            scanned += 1;
+            before = Some(status.signature.clone());
+            if list_query.precedes_range(&status) {
+                break 'batches;
+            }
+            if list_query.matches(&status) {
+                // A failed fetch fails the page, as the cursor would otherwise skip its signature.
+                if let Some(transaction_data) = indexed_transaction(
+                    &pubkey,
+                    &status.signature,
+                    commitment,
+                    &rpc_solana_client,
+                    &processor,
+                    &token_registry,
+                    &storage,
+                )
+                .await?
+                .filter(|transaction_data| list_query.matches(transaction_data))
+                {
+                    transactions.push(transaction_data);
+                }
+            }
+            if transactions.len() == limit || scanned >= MAX_SCANNED_SIGNATURES {
+                next_before = before;
+                break 'batches;
+            }
+        }
+        if exhausted {
+            break;
+        }
+    }
+
+    Ok((
+        context_headers(commitment, None),
+        Json(AccountHistory {
+            page: Page {
+                items:       transactions,
+                next_cursor: next_before.as_deref().map(encode_cursor),
+            },
+            next_before,
+        }),
+    ))
+}
+
+/// Returns the stored transaction, or fetches, processes and stores it if not indexed yet. A
+/// transaction the processor skips is `None`.
+async fn indexed_transaction(
+    pubkey: &str, signature: &str, commitment: CommitmentConfig,
+    rpc_solana_client: &RpcSolanaClient, processor: &Processor, token_registry: &TokenRegistry,
+    storage: &Storage,
+) -> Result<Option<TransactionData>, ApiError> {
+    if let Some(transaction_data) = storage.get_transaction(signature) {
+        return Ok(Some(transaction_data));
+    }
+
+    let encoded_transaction = match rpc_solana_client
+        .get_transaction(signature, commitment)
+        .await
+    {
+        Ok(encoded_transaction) => encoded_transaction,
+        Err(e) => {
+            error!(
+                "Error backfilling transaction {} for account {}: {:?}",
+                signature, pubkey, e
+            );
+            return Err(e.into());
+        }
+    };
+
+    let Some(mut transaction_data) = processor.process_transaction(encoded_transaction) else {
+        return Ok(None);
+    };
+    token_registry
+        .enrich_transaction(&mut transaction_data)
+        .await;
+    storage.insert_transaction(transaction_data.clone());
+    Ok(Some(transaction_data))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/accounts",
+    tag = "accounts",
+    params(
+        AccountQuery
+    ),
+    request_body = AccountsRequest,
+    responses(
+        (status = 200, description = "Lookup of every pubkey, in request order", body = Vec<AccountLookup>, headers(("x-commitment" = String), ("x-context-slot" = u64))),
+        (status = 400, description = "Invalid request", body = ErrorMessage),
+        (status = 429, description = "Rate limited by the RPC provider", body = ErrorMessage),
+        (status = 502, description = "Upstream error or undecodable response", body = ErrorMessage),
+        (status = 503, description = "Upstream timeout", body = ErrorMessage)
+    )
+)]
+pub async fn get_accounts(
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(processor): Extension<Arc<Processor>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>, Query(query): Query<AccountQuery>,
+    Json(request): Json<AccountsRequest>,
+) -> Result<(HeaderMap, Json<Vec<AccountLookup>>), ApiError> {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    if request.pubkeys.len() > MAX_BATCH_ACCOUNTS {
+        error!(
+            "Batch of {} accounts exceeds the limit of {}",
+            request.pubkeys.len(),
+            MAX_BATCH_ACCOUNTS
+        );
+        return Err(ApiError::BadRequest(format!(
+            "at most {} pubkeys can be requested at once",
+            MAX_BATCH_ACCOUNTS
+        )));
+    }
+
+    let valid_pubkeys: Vec<String> = request
+        .pubkeys
+        .iter()
+        .filter(|pubkey| Pubkey::from_str(pubkey).is_ok())
+        .cloned()
+        .collect();
+
+    // Without a valid pubkey there is nothing to fetch, nor a context slot to report.
+    let (context_slot, fetched_at, accounts) = match valid_pubkeys.is_empty() {
+        true => (None, 0, Vec::new()),
+        false => match rpc_solana_client
+            .get_multiple_accounts(&valid_pubkeys, commitment, query.min_context_slot)
+            .await
+        {
+            Ok(response) => (
+                Some(response.context.slot),
+                response.fetched_at,
+                response.value,
+            ),
+            Err(e) => {
+                error!("Error fetching {} accounts: {:?}", valid_pubkeys.len(), e);
+                return Err(e.into());
             }
         },
+    };
+    let mut accounts = accounts.into_iter();
+
+    let mut lookups = Vec::with_capacity(request.pubkeys.len());
+    for pubkey in request.pubkeys {
+        if Pubkey::from_str(&pubkey).is_err() {
+            lookups.push(AccountLookup {
+                pubkey,
+                status: AccountLookupStatus::InvalidPubkey,
+                account: None,
+            });
+            continue;
+        }
+
+        let account_data = match (accounts.next(), context_slot) {
+            (Some(Some(account)), Some(context_slot)) => {
+                processor.process_account(account, context_slot, fetched_at)
+            }
+            _ => None,
+        };
+
+        match account_data {
+            Some(mut account_data) => {
+                token_registry
+                    .enrich_account(&pubkey, &mut account_data)
+                    .await;
+                lookups.push(AccountLookup {
+                    pubkey,
+                    status: AccountLookupStatus::Found,
+                    account: Some(account_data),
+                });
+            }
+            None => lookups.push(AccountLookup {
+                pubkey,
+                status: AccountLookupStatus::NotFound,
+                account: None,
+            }),
+        }
+    }
+
+    Ok((context_headers(commitment, context_slot), Json(lookups)))
+}
+
+/// Writes the processed accounts as newline-delimited JSON once the node has returned all of them.
+#[utoipa::path(
+    get,
+    path = "/api/program/{program_id}/accounts",
+    tag = "accounts",
+    params(
+        ("program_id" = String, Path),
+        ProgramAccountsQuery
+    ),
+    responses(
+        (status = 200, description = "Accounts owned by the program, one JSON object per line, sent once the node has returned all of them", body = ProgramAccount, content_type = "application/x-ndjson", headers(("x-commitment" = String), ("x-context-slot" = u64))),
+        (status = 400, description = "Invalid program id or filter", body = ErrorMessage),
+        (status = 429, description = "Rate limited by the RPC provider", body = ErrorMessage),
+        (status = 502, description = "Upstream error or undecodable response", body = ErrorMessage),
+        (status = 503, description = "Upstream timeout", body = ErrorMessage)
+    )
+)]
+pub async fn get_program_accounts(
+    Path(program_id): Path<String>, Query(query): Query<ProgramAccountsQuery>,
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(processor): Extension<Arc<Processor>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>,
+    Extension(storage): Extension<Arc<Storage>>,
+) -> Result<Response, ApiError> {
+    stream_program_accounts(
+        program_id,
+        query,
+        rpc_solana_client,
+        processor,
+        token_registry,
+        storage,
+        |pubkey, account| ProgramAccount { pubkey, account },
+    )
+    .await
+}
+
+/// Writes the accounts of the program as newline-delimited JSON, each as `to_line` maps it. The
+/// whole `getProgramAccounts` response is awaited first, only its processing being streamed.
+pub async fn stream_program_accounts<T: Serialize + 'static>(
+    program_id: String, query: ProgramAccountsQuery, rpc_solana_client: Arc<RpcSolanaClient>,
+    processor: Arc<Processor>, token_registry: Arc<TokenRegistry>, storage: Arc<Storage>,
+    to_line: fn(String, AccountData) -> T,
+) -> Result<Response, ApiError> {
+    let Some(filters) = query.filters() else {
+        error!("Invalid program account filters: {:?}", &query);
+        return Err(ApiError::BadRequest(
+            "invalid program account filters".to_string(),
+        ));
+    };
+
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    let response = match rpc_solana_client
+        .get_program_accounts(
+            &program_id,
+            filters,
+            query.data_slice(),
+            commitment,
+            query.min_context_slot,
+        )
+        .await
+    {
+        Ok(response) => response,
         Err(e) => {
-            error!("Error fetching Account {}: {:?}", &pubkey, e);
-            Err(ApiError::InternalError)
+            error!(
+                "Error fetching accounts of program {}: {:?}",
+                &program_id, e
+            );
+            return Err(e.into());
+        }
+    };
+
+    let persist = query.persist;
+    let context_slot = response.context.slot;
+    let fetched_at = response.fetched_at;
+    let mut headers = context_headers(commitment, Some(context_slot));
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/x-ndjson"),
+    );
+    let lines = stream::iter(response.value).filter_map(move |(pubkey, account)| {
+        let processor = Arc::clone(&processor);
+        let token_registry = Arc::clone(&token_registry);
+        let storage = Arc::clone(&storage);
+        async move {
+            let pubkey = pubkey.to_string();
+            let mut account_data = processor.process_account(account, context_slot, fetched_at)?;
+            token_registry
+                .enrich_account(&pubkey, &mut account_data)
+                .await;
+            if persist {
+                storage.insert_account_snapshot(&pubkey, account_data.clone());
+            }
+
+            let mut line = serde_json::to_vec(&to_line(pubkey, account_data)).ok()?;
+            line.push(b'\n');
+            Some(Ok::<_, Infallible>(line))
+        }
+    });
+
+    Ok((headers, Body::from_stream(lines)).into_response())
+}
+
+/// Returns stored snapshots of the account, oldest first unless sorted otherwise.
+#[utoipa::path(
+    get,
+    path = "/api/account/{pubkey}/snapshots",
+    tag = "accounts",
+    params(
+        ("pubkey" = String, Path),
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "Stored snapshots of the account", body = Page<AccountSnapshot>),
+        (status = 400, description = "Invalid query", body = ErrorMessage)
+    )
+)]
+pub async fn get_account_snapshots(
+    Path(pubkey): Path<String>, Query(list_query): Query<ListQuery>,
+    Extension(storage): Extension<Arc<Storage>>,
+) -> Result<Json<Page<AccountSnapshot>>, ApiError> {
+    // Snapshots are only ever appended, so their position identifies them.
+    let snapshots = storage
+        .get_account_snapshots(&pubkey)
+        .into_iter()
+        .enumerate()
+        .map(|(position, snapshot)| (position.to_string(), snapshot))
+        .collect();
+    paginate(snapshots, &list_query, SortOrder::Asc).map(Json)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/account/{pubkey}/tokens",
+    tag = "tokens",
+    params(
+        ("pubkey" = String, Path, description = "Wallet owning the token accounts"),
+        AccountQuery
+    ),
+    responses(
+        (status = 200, description = "SOL and token balances of the wallet", body = Portfolio, headers(("x-commitment" = String), ("x-context-slot" = u64))),
+        (status = 400, description = "Invalid pubkey", body = ErrorMessage),
+        (status = 429, description = "Rate limited by the RPC provider", body = ErrorMessage),
+        (status = 502, description = "Upstream error or undecodable response", body = ErrorMessage),
+        (status = 503, description = "Upstream timeout", body = ErrorMessage)
+    )
+)]
+pub async fn get_token_portfolio(
+    Path(owner): Path<String>, Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(processor): Extension<Arc<Processor>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>, Query(query): Query<AccountQuery>,
+) -> Result<(HeaderMap, Json<Portfolio>), ApiError> {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    let (lamports, mut context_slot) = match rpc_solana_client
+        .get_optional_account(&owner, commitment, query.min_context_slot)
+        .await
+    {
+        Ok(response) => (
+            response
+                .value
+                .map(|account| account.lamports)
+                .unwrap_or_default(),
+            response.context.slot,
+        ),
+        Err(e) => {
+            error!("Error fetching Account {}: {:?}", &owner, e);
+            return Err(e.into());
         }
+    };
+
+    let mut token_accounts = vec![];
+    for program_id in spl_token_ids() {
+        let response = match rpc_solana_client
+            .get_token_accounts_by_owner(&owner, &program_id, commitment, query.min_context_slot)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                error!(
+                    "Error fetching {} token accounts of {}: {:?}",
+                    &program_id, &owner, e
+                );
+                return Err(e.into());
+            }
+        };
+
+        context_slot = context_slot.min(response.context.slot);
+        for (pubkey, account) in response.value {
+            let pubkey = pubkey.to_string();
+            let Some(mut account_data) =
+                processor.process_account(account, response.context.slot, response.fetched_at)
+            else {
+                continue;
+            };
+            token_registry
+                .enrich_account(&pubkey, &mut account_data)
+                .await;
+            if let Some(token_account) = account_data.token_account {
+                token_accounts.push((pubkey, token_account));
+            }
+        }
+    }
+
+    Ok((
+        context_headers(commitment, Some(context_slot)),
+        Json(Portfolio::new(&owner, lamports, token_accounts)),
+    ))
+}
+
+/// Streams updates of the account as server-sent events.
+#[utoipa::path(
+    get,
+    path = "/api/stream/account/{pubkey}",
+    tag = "streams",
+    params(
+        ("pubkey" = String, Path),
+        CommitmentQuery,
+        ("Last-Event-ID" = Option<u64>, Header, description = "Id of the last event received, to replay the missed ones")
+    ),
+    responses(
+        (status = 200, description = "Server-sent `account` events carrying AccountData", content_type = "text/event-stream"),
+        (status = 400, description = "Invalid pubkey", body = ErrorMessage),
+        (status = 429, description = "Too many subscriptions open", body = ErrorMessage)
+    )
+)]
+pub async fn stream_account(
+    Path(pubkey): Path<String>, Query(query): Query<CommitmentQuery>, headers: HeaderMap,
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(stream_hub): Extension<Arc<StreamHub>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    match stream_hub.subscribe(
+        Topic::Account(pubkey.clone()),
+        commitment,
+        last_event_id(&headers),
+    ) {
+        Ok((backlog, receiver)) => Ok(event_stream(
+            "account",
+            backlog,
+            receiver,
+            stream_hub.heartbeat_interval(),
+        )),
+        Err(e) => {
+            error!("Error streaming account {}: {:?}", &pubkey, e);
+            Err(e.into())
+        }
+    }
+}
+
+/// Streams transaction logs as server-sent events, optionally only those mentioning an address.
+#[utoipa::path(
+    get,
+    path = "/api/stream/logs",
+    tag = "streams",
+    params(
+        LogsStreamQuery,
+        ("Last-Event-ID" = Option<u64>, Header, description = "Id of the last event received, to replay the missed ones")
+    ),
+    responses(
+        (status = 200, description = "Server-sent `logs` events", content_type = "text/event-stream"),
+        (status = 400, description = "Invalid address", body = ErrorMessage),
+        (status = 429, description = "Too many subscriptions open", body = ErrorMessage)
+    )
+)]
+pub async fn stream_logs(
+    Query(query): Query<LogsStreamQuery>, headers: HeaderMap,
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(stream_hub): Extension<Arc<StreamHub>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let commitment = rpc_solana_client.commitment(query.commitment);
+    match stream_hub.subscribe(
+        Topic::Logs(query.mentions.clone()),
+        commitment,
+        last_event_id(&headers),
+    ) {
+        Ok((backlog, receiver)) => Ok(event_stream(
+            "logs",
+            backlog,
+            receiver,
+            stream_hub.heartbeat_interval(),
+        )),
+        Err(e) => {
+            error!("Error streaming logs of {:?}: {:?}", &query.mentions, e);
+            Err(e.into())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    request_body = WebhookRequest,
+    responses(
+        (status = 201, description = "Registered webhook with its signing secret", body = RegisteredWebhook),
+        (status = 400, description = "Invalid URL or filter", body = ErrorMessage)
+    )
+)]
+pub async fn create_webhook(
+    Extension(webhook_dispatcher): Extension<Arc<WebhookDispatcher>>,
+    Json(request): Json<WebhookRequest>,
+) -> Result<(StatusCode, Json<RegisteredWebhook>), ApiError> {
+    match webhook_dispatcher.register(&request.url, request.filter) {
+        Ok(webhook) => Ok((
+            StatusCode::CREATED,
+            Json(RegisteredWebhook {
+                secret: webhook.secret.clone(),
+                webhook,
+            }),
+        )),
+        Err(e) => {
+            error!("Error registering webhook {}: {:?}", &request.url, e);
+            Err(e.into())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    tag = "webhooks",
+    params(
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "Registered webhooks", body = Page<Webhook>),
+        (status = 400, description = "Invalid query", body = ErrorMessage)
+    )
+)]
+pub async fn get_webhooks(
+    Query(list_query): Query<ListQuery>, Extension(storage): Extension<Arc<Storage>>,
+) -> Result<Json<Page<Webhook>>, ApiError> {
+    let webhooks = storage
+        .get_webhooks()
+        .into_iter()
+        .map(|webhook| (webhook.id.clone(), webhook))
+        .collect();
+    paginate(webhooks, &list_query, SortOrder::Asc).map(Json)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path)
+    ),
+    responses(
+        (status = 204, description = "Webhook removed"),
+        (status = 404, description = "Webhook not found", body = ErrorMessage)
+    )
+)]
+pub async fn delete_webhook(
+    Path(id): Path<String>, Extension(webhook_dispatcher): Extension<Arc<WebhookDispatcher>>,
+) -> Result<StatusCode, ApiError> {
+    match webhook_dispatcher.delete(&id) {
+        Some(_) => Ok(StatusCode::NO_CONTENT),
+        None => Err(webhook_not_found(&id)),
+    }
+}
+
+/// Returns the most recent deliveries of the webhook, oldest first unless sorted otherwise.
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path),
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "Recent deliveries of the webhook", body = Page<Delivery>),
+        (status = 400, description = "Invalid query", body = ErrorMessage),
+        (status = 404, description = "Webhook not found", body = ErrorMessage)
+    )
+)]
+pub async fn get_webhook_deliveries(
+    Path(id): Path<String>, Query(list_query): Query<ListQuery>,
+    Extension(storage): Extension<Arc<Storage>>,
+) -> Result<Json<Page<Delivery>>, ApiError> {
+    if storage.get_webhook(&id).is_none() {
+        return Err(webhook_not_found(&id));
+    }
+    let deliveries = storage
+        .get_deliveries(&id)
+        .into_iter()
+        .map(|delivery| (delivery.id.clone(), delivery))
+        .collect();
+    paginate(deliveries, &list_query, SortOrder::Asc).map(Json)
+}
+
+/// Returns the payloads the webhook never accepted, oldest first unless sorted otherwise.
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}/dead-letters",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path),
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "Payloads the webhook never accepted", body = Page<DeadLetter>),
+        (status = 400, description = "Invalid query", body = ErrorMessage),
+        (status = 404, description = "Webhook not found", body = ErrorMessage)
+    )
+)]
+pub async fn get_webhook_dead_letters(
+    Path(id): Path<String>, Query(list_query): Query<ListQuery>,
+    Extension(storage): Extension<Arc<Storage>>,
+) -> Result<Json<Page<DeadLetter>>, ApiError> {
+    if storage.get_webhook(&id).is_none() {
+        return Err(webhook_not_found(&id));
+    }
+    let dead_letters = storage
+        .get_dead_letters(&id)
+        .into_iter()
+        .map(|dead_letter| (dead_letter.delivery_id.clone(), dead_letter))
+        .collect();
+    paginate(dead_letters, &list_query, SortOrder::Asc).map(Json)
+}
+
+/// Returns the stored alerts, newest first unless sorted otherwise, optionally only those of a
+/// rule or severity.
+#[utoipa::path(
+    get,
+    path = "/api/alerts",
+    tag = "alerts",
+    params(
+        AlertQuery,
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "Stored alerts", body = Page<Alert>),
+        (status = 400, description = "Invalid query", body = ErrorMessage)
+    )
+)]
+pub async fn get_alerts(
+    Query(query): Query<AlertQuery>, Query(list_query): Query<ListQuery>,
+    Extension(storage): Extension<Arc<Storage>>,
+) -> Result<Json<Page<Alert>>, ApiError> {
+    let alerts = storage
+        .get_alerts()
+        .into_iter()
+        .filter(|alert| query.rule.as_ref().is_none_or(|rule| &alert.rule == rule))
+        .filter(|alert| {
+            query
+                .severity
+                .is_none_or(|severity| alert.severity == severity)
+        })
+        .map(|alert| (alert_key(&alert), alert))
+        .collect();
+    paginate_resuming(alerts, &list_query, SortOrder::Desc, resume_alerts).map(Json)
+}
+
+/// Cursor key of an alert, carrying when it was raised so that a page can resume after the alert
+/// is evicted.
+fn alert_key(alert: &Alert) -> String {
+    format!("{}:{}", alert.triggered_at, alert.id)
+}
+
+/// Start of the page after an evicted alert. The oldest alerts are evicted first, so the stored
+/// ones raised at the same time as the cursor's are newer than it.
+fn resume_alerts(sort: SortOrder, alerts: &[(String, Alert)], key: &str) -> Option<usize> {
+    let triggered_at: i64 = key.split_once(':')?.0.parse().ok()?;
+    let position = alerts.iter().position(|(_, alert)| match sort {
+        SortOrder::Asc => alert.triggered_at >= triggered_at,
+        SortOrder::Desc => alert.triggered_at < triggered_at,
+    });
+    Some(position.unwrap_or(alerts.len()))
+}
+
+fn webhook_not_found(id: &str) -> ApiError {
+    error!("Webhook {} does not exist", id);
+    ApiError::NotFound(format!("webhook {} does not exist", id))
+}
+
+fn last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Replays `backlog` then forwards live events, with heartbeats while none arrive.
+///
+/// A listener falling behind the live events is disconnected, so it resumes from the buffered ones
+/// with `Last-Event-ID`.
+fn event_stream(
+    name: &'static str, backlog: Vec<StreamEvent>, receiver: broadcast::Receiver<StreamEvent>,
+    heartbeat_interval: Duration,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let live = stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await.ok()?;
+        Some((event, receiver))
+    });
+    let events = stream::iter(backlog).chain(live).map(move |event| {
+        Ok(Event::default()
+            .id(event.id.to_string())
+            .event(name)
+            .data(event.data.to_string()))
+    });
+
+    Sse::new(events).keep_alive(
+        KeepAlive::new()
+            .interval(heartbeat_interval)
+            .event(Event::default().event("heartbeat").data("")),
+    )
+}
+
+/// Echoes the commitment used and, when known, the slot the response reflects.
+pub fn context_headers(commitment: CommitmentConfig, context_slot: Option<u64>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-commitment",
+        HeaderValue::from_str(&commitment.commitment.to_string()).unwrap(),
+    );
+    if let Some(context_slot) = context_slot {
+        headers.insert("x-context-slot", HeaderValue::from(context_slot));
+    }
+    headers
+}
+
+/// Echoes the commitment used and the slot of the returned transaction or block, which unlike a
+/// context slot says nothing of how recent the node's view is.
+pub fn slot_headers(commitment: CommitmentConfig, slot: u64) -> HeaderMap {
+    let mut headers = context_headers(commitment, None);
+    headers.insert("x-slot", HeaderValue::from(slot));
+    headers
+}
+
+/// Streams the stored rows of a dataset in a slot or time range, read and encoded in batches.
+#[utoipa::path(
+    get,
+    path = "/api/export/{dataset}",
+    tag = "export",
+    params(
+        ("dataset" = Dataset, Path),
+        ExportQuery
+    ),
+    responses(
+        (status = 200, description = "Rows of the dataset as CSV, newline-delimited JSON or Parquet", content((String = "text/csv"), (String = "application/x-ndjson"), (String = "application/vnd.apache.parquet"))),
+        (status = 400, description = "Invalid dataset or query", body = ErrorMessage),
+        (status = 500, description = "Export writer could not be created", body = ErrorMessage)
+    )
+)]
+pub async fn export_dataset(
+    Path(dataset): Path<Dataset>, Query(query): Query<ExportQuery>,
+    Extension(storage): Extension<Arc<Storage>>,
+) -> Result<Response, ApiError> {
+    let format = query.format.unwrap_or(Format::Ndjson);
+    let writer = match ExportWriter::new(format, dataset) {
+        Ok(writer) => writer,
+        Err(e) => {
+            error!("Error creating {:?} export writer: {:?}", format, e);
+            return Err(ApiError::Internal);
+        }
+    };
+    let exporter = Exporter::new(storage, dataset, &query);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    if let Ok(disposition) = HeaderValue::from_str(&format!(
+        "attachment; filename=\"{}.{}\"",
+        dataset.as_str(),
+        format.extension()
+    )) {
+        headers.insert(CONTENT_DISPOSITION, disposition);
+    }
+    // Each step reads and encodes one batch, the writer's trailing bytes ending the stream.
+    let chunks = stream::unfold(Some((exporter, writer)), move |state| async move {
+        let (mut exporter, mut writer) = state?;
+        let chunk = match exporter.next_batch() {
+            Some(rows) => writer
+                .write_batch(&rows)
+                .map(|chunk| (chunk, Some((exporter, writer)))),
+            None => writer.finish().map(|chunk| (chunk, None)),
+        };
+        match chunk {
+            Ok((chunk, state)) => Some((Ok(chunk), state)),
+            Err(e) => {
+                error!("Error exporting {}: {:?}", dataset.as_str(), e);
+                Some((Err(e), None))
+            }
+        }
+    });
+
+    Ok((headers, Body::from_stream(chunks)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+    use solana_sdk::signature::Signature;
+
+    fn program_accounts_query(
+        data_size: Option<u64>, memcmp: Option<&str>,
+    ) -> ProgramAccountsQuery {
+        ProgramAccountsQuery {
+            data_size,
+            memcmp: memcmp.map(str::to_string),
+            data_slice_offset: None,
+            data_slice_length: None,
+            persist: false,
+            commitment: None,
+            min_context_slot: None,
+        }
+    }
+
+    #[test]
+    fn test_program_accounts_filters() {
+        let query = program_accounts_query(Some(165), Some("0:3Mc6vR,32:2UzHM"));
+
+        let filters = query.filters().unwrap();
+
+        assert_eq!(filters.len(), 3);
+        assert_eq!(filters[0], RpcFilterType::DataSize(165));
+        assert_eq!(
+            filters[2],
+            RpcFilterType::Memcmp(Memcmp::new(
+                32,
+                MemcmpEncodedBytes::Base58("2UzHM".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_context_headers() {
+        let headers = context_headers(CommitmentConfig::finalized(), Some(42));
+
+        assert_eq!(headers["x-commitment"], "finalized");
+        assert_eq!(headers["x-context-slot"], "42");
+        assert!(
+            !context_headers(CommitmentConfig::processed(), None).contains_key("x-context-slot")
+        );
+    }
+
+    #[test]
+    fn test_slot_headers() {
+        let headers = slot_headers(CommitmentConfig::finalized(), 42);
+
+        assert_eq!(headers["x-commitment"], "finalized");
+        assert_eq!(headers["x-slot"], "42");
+        assert!(!headers.contains_key("x-context-slot"));
+    }
+
+    #[test]
+    fn test_last_event_id() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(last_event_id(&headers), None);
+
+        headers.insert("last-event-id", HeaderValue::from_static(" 17"));
+        assert_eq!(last_event_id(&headers), Some(17));
+
+        headers.insert("last-event-id", HeaderValue::from_static("abc"));
+        assert_eq!(last_event_id(&headers), None);
+    }
+
+    #[test]
+    fn test_program_accounts_filters_invalid() {
+        assert!(program_accounts_query(None, Some("0")).filters().is_none());
+        assert!(program_accounts_query(None, Some("x:3Mc6vR"))
+            .filters()
+            .is_none());
+        assert!(program_accounts_query(None, Some("0:not-base58!"))
+            .filters()
+            .is_none());
+    }
+
+    fn transaction(signature: &str, slot: u64, error: Option<&str>) -> TransactionData {
+        test_fixtures::transaction(serde_json::json!({
+            "signatures": [signature],
+            "accounts": [{"pubkey": "Program", "writable": false, "signer": false}],
+            "slot": slot,
+            "error": error
+        }))
+    }
+
+    fn keyed(transactions: Vec<TransactionData>) -> Vec<(String, TransactionData)> {
+        transactions
+            .into_iter()
+            .map(|transaction_data| {
+                (
+                    transaction_data.signature().unwrap().to_string(),
+                    transaction_data,
+                )
+            })
+            .collect()
+    }
+
+    fn list_query(query: &str) -> ListQuery {
+        Query::try_from_uri(&format!("http://localhost/?{}", query).parse().unwrap())
+            .unwrap()
+            .0
+    }
+
+    fn signatures(page: &Page<TransactionData>) -> Vec<&str> {
+        page.items
+            .iter()
+            .map(|transaction_data| transaction_data.signature().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_paginate_follows_cursor() {
+        let transactions = || {
+            keyed(
+                (1 ..= 5)
+                    .map(|slot| transaction(&format!("S{}", slot), slot, None))
+                    .collect(),
+            )
+        };
+
+        let first = paginate(
+            transactions(),
+            &list_query("limit=2&sort=desc"),
+            SortOrder::Asc,
+        )
+        .unwrap();
+        let cursor = first.next_cursor.clone().unwrap();
+        let second = paginate(
+            transactions(),
+            &list_query(&format!("limit=2&sort=desc&cursor={}", cursor)),
+            SortOrder::Asc,
+        )
+        .unwrap();
+        let last = paginate(
+            transactions(),
+            &list_query(&format!(
+                "limit=2&sort=desc&cursor={}",
+                second.next_cursor.clone().unwrap()
+            )),
+            SortOrder::Asc,
+        )
+        .unwrap();
+
+        assert_eq!(signatures(&first), vec!["S5", "S4"]);
+        assert_ne!(cursor, "S4");
+        assert_eq!(signatures(&second), vec!["S3", "S2"]);
+        assert_eq!(signatures(&last), vec!["S1"]);
+        assert!(last.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_paginate_filters() {
+        let transactions = keyed(vec![
+            transaction("S1", 1, None),
+            transaction("S2", 2, Some("InstructionError")),
+            transaction("S3", 3, None),
+            transaction("S4", 4, None),
+        ]);
+
+        let page = paginate(
+            transactions.clone(),
+            &list_query("from_slot=2&to_slot=4&status=success"),
+            SortOrder::Asc,
+        )
+        .unwrap();
+        let by_program = paginate(
+            transactions,
+            &list_query("program=Other&status=failed"),
+            SortOrder::Asc,
+        )
+        .unwrap();
+
+        assert_eq!(signatures(&page), vec!["S3", "S4"]);
+        assert!(page.next_cursor.is_none());
+        assert!(by_program.items.is_empty());
+    }
+
+    #[test]
+    fn test_paginate_invalid_cursor() {
+        let transactions = || keyed(vec![transaction("S1", 1, None)]);
+        let unknown = encode_cursor("S9");
+
+        assert!(matches!(
+            paginate(transactions(), &list_query("cursor=!!"), SortOrder::Asc),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            paginate(
+                transactions(),
+                &list_query(&format!("cursor={}", unknown)),
+                SortOrder::Asc
+            ),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_list_query_precedes_range() {
+        let query = list_query("from_slot=10&limit=500");
+
+        assert_eq!(query.limit(), MAX_PAGE_LIMIT);
+        assert!(query.precedes_range(&transaction("S1", 9, None)));
+        assert!(!query.precedes_range(&transaction("S1", 10, None)));
+        assert!(!list_query("").precedes_range(&transaction("S1", 9, None)));
+    }
+
+    fn alert(id: &str, triggered_at: i64) -> Alert {
+        Alert {
+            id: id.to_string(),
+            rule: "rule".to_string(),
+            severity: Severity::Warning,
+            message: "message".to_string(),
+            address: "Address".to_string(),
+            signature: None,
+            slot: None,
+            triggered_at,
+        }
+    }
+
+    async fn alert_ids(storage: &Arc<Storage>, query: &str) -> (Vec<String>, Option<String>) {
+        let Json(page) = get_alerts(
+            Query(AlertQuery {
+                rule:     None,
+                severity: None,
+            }),
+            Query(list_query(query)),
+            Extension(Arc::clone(storage)),
+        )
+        .await
+        .unwrap();
+        let ids = page.items.into_iter().map(|alert| alert.id).collect();
+        (ids, page.next_cursor)
+    }
+
+    #[tokio::test]
+    async fn test_get_alerts_resumes_after_eviction() {
+        let storage = Arc::new(Storage::default());
+        for (id, triggered_at) in [("A1", 1), ("A2", 2), ("A3", 2)] {
+            storage.insert_alert(alert(id, triggered_at), 3);
+        }
+        let (oldest, asc_cursor) = alert_ids(&storage, "sort=asc&limit=1").await;
+        let (newest, desc_cursor) = alert_ids(&storage, "limit=2").await;
+
+        for (id, triggered_at) in [("A4", 3), ("A5", 4), ("A6", 4)] {
+            storage.insert_alert(alert(id, triggered_at), 3);
+        }
+        let (after_oldest, _) = alert_ids(
+            &storage,
+            &format!("sort=asc&cursor={}", asc_cursor.unwrap()),
+        )
+        .await;
+        let (after_newest, next_cursor) =
+            alert_ids(&storage, &format!("cursor={}", desc_cursor.unwrap())).await;
+
+        assert_eq!(oldest, vec!["A1"]);
+        assert_eq!(newest, vec!["A3", "A2"]);
+        assert_eq!(after_oldest, vec!["A4", "A5", "A6"]);
+        assert!(after_newest.is_empty());
+        assert!(next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_account_transactions_before() {
+        let before = Signature::new_unique().to_string();
+        let signatures = [Signature::new_unique(), Signature::new_unique()].map(|s| s.to_string());
+        let storage = Arc::new(Storage::default());
+        for signature in &signatures {
+            storage.insert_transaction(transaction(signature, 5, None));
+        }
+        let (expected_before, statuses) = (before.clone(), signatures.clone());
+        let answer = move |Json(request): Json<serde_json::Value>| async move {
+            assert_eq!(request["params"][1]["before"], expected_before);
+            let statuses: Vec<_> = statuses
+                .iter()
+                .map(|signature| {
+                    serde_json::json!({"signature": signature, "slot": 5, "err": null, "memo": null})
+                })
+                .collect();
+            Json(serde_json::json!({"jsonrpc": "2.0", "id": request["id"], "result": statuses}))
+        };
+        let rpc_solana_client = test_fixtures::rpc_client(&test_fixtures::serve_rpc(answer).await);
+        let history = |query: String| {
+            let rpc_solana_client = Arc::clone(&rpc_solana_client);
+            let storage = Arc::clone(&storage);
+            async move {
+                get_account_transactions(
+                    Path(Pubkey::new_unique().to_string()),
+                    Query::try_from_uri(&format!("http://localhost/?{}", query).parse().unwrap())
+                        .unwrap(),
+                    Query(list_query(&query)),
+                    Extension(Arc::clone(&rpc_solana_client)),
+                    Extension(Arc::new(Processor)),
+                    Extension(Arc::new(TokenRegistry::new(
+                        rpc_solana_client,
+                        Arc::clone(&storage),
+                    ))),
+                    Extension(storage),
+                )
+                .await
+            }
+        };
+
+        let (_, Json(page)) = history(format!("before={}&limit=2", before)).await.unwrap();
+        let combined = history(format!(
+            "before={}&cursor={}",
+            before,
+            encode_cursor(&before)
+        ))
+        .await;
+
+        assert_eq!(page.page.items.len(), 2);
+        assert_eq!(page.next_before.as_ref(), Some(&signatures[1]));
+        assert_eq!(page.page.next_cursor, Some(encode_cursor(&signatures[1])));
+        assert!(matches!(combined, Err(ApiError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_get_account_transactions_failed_fetch() {
+        let (stored, failed) = (
+            Signature::new_unique().to_string(),
+            Signature::new_unique().to_string(),
+        );
+        let storage = Arc::new(Storage::default());
+        storage.insert_transaction(transaction(&stored, 5, None));
+        let statuses = [stored.clone(), failed.clone()];
+        let answer = move |Json(request): Json<serde_json::Value>| async move {
+            let body = match request["method"].as_str() {
+                Some("getSignaturesForAddress") => {
+                    let statuses: Vec<_> = statuses
+                        .iter()
+                        .map(|signature| {
+                            serde_json::json!({"signature": signature, "slot": 5, "err": null, "memo": null})
+                        })
+                        .collect();
+                    serde_json::json!({"jsonrpc": "2.0", "id": request["id"], "result": statuses})
+                }
+                _ => serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": {"code": -32000, "message": "failed"}
+                }),
+            };
+            Json(body)
+        };
+        let rpc_solana_client = test_fixtures::rpc_client(&test_fixtures::serve_rpc(answer).await);
+
+        let history = get_account_transactions(
+            Path(Pubkey::new_unique().to_string()),
+            Query::try_from_uri(&"http://localhost/?limit=2".parse().unwrap()).unwrap(),
+            Query(list_query("limit=2")),
+            Extension(Arc::clone(&rpc_solana_client)),
+            Extension(Arc::new(Processor)),
+            Extension(Arc::new(TokenRegistry::new(
+                rpc_solana_client,
+                Arc::clone(&storage),
+            ))),
+            Extension(Arc::clone(&storage)),
+        )
+        .await;
+
+        assert!(matches!(
+            history,
+            Err(ApiError::UpstreamError { retryable: false })
+        ));
+        assert!(storage.get_transaction(&failed).is_none());
+    }
+
+    /// Client of a local node answering `getMultipleAccounts` with a system account for every
+    /// pubkey but `missing`, at slot 42.
+    async fn accounts_client(missing: String) -> Arc<RpcSolanaClient> {
+        let answer = move |Json(request): Json<serde_json::Value>| async move {
+            let accounts: Vec<serde_json::Value> = request["params"][0]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|pubkey| match pubkey.as_str() == Some(missing.as_str()) {
+                    true => serde_json::Value::Null,
+                    false => serde_json::json!({
+                        "lamports": 1000,
+                        "data": ["", "base64"],
+                        "owner": "11111111111111111111111111111111",
+                        "executable": false,
+                        "rentEpoch": 0,
+                        "space": 0
+                    }),
+                })
+                .collect();
+            Json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": {"context": {"slot": 42}, "value": accounts}
+            }))
+        };
+        test_fixtures::rpc_client(&test_fixtures::serve_rpc(answer).await)
+    }
+
+    async fn lookup_accounts(
+        rpc_solana_client: Arc<RpcSolanaClient>, pubkeys: Vec<String>,
+    ) -> Result<(HeaderMap, Json<Vec<AccountLookup>>), ApiError> {
+        let token_registry = Arc::new(TokenRegistry::new(
+            Arc::clone(&rpc_solana_client),
+            Arc::new(Storage::default()),
+        ));
+        get_accounts(
+            Extension(rpc_solana_client),
+            Extension(Arc::new(Processor)),
+            Extension(token_registry),
+            Query::try_from_uri(&"http://localhost/".parse().unwrap()).unwrap(),
+            Json(AccountsRequest { pubkeys }),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_get_accounts() {
+        let found = Pubkey::new_unique().to_string();
+        let missing = Pubkey::new_unique().to_string();
+        let rpc_solana_client = accounts_client(missing.clone()).await;
+
+        let (headers, Json(lookups)) = lookup_accounts(
+            rpc_solana_client,
+            vec![missing.clone(), "invalid".to_string(), found.clone()],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(headers["x-context-slot"], "42");
+        let pubkeys: Vec<&str> = lookups
+            .iter()
+            .map(|lookup| lookup.pubkey.as_str())
+            .collect();
+        assert_eq!(pubkeys, vec![missing.as_str(), "invalid", found.as_str()]);
+        assert!(matches!(lookups[0].status, AccountLookupStatus::NotFound));
+        assert!(lookups[0].account.is_none());
+        assert!(matches!(
+            lookups[1].status,
+            AccountLookupStatus::InvalidPubkey
+        ));
+        assert!(lookups[1].account.is_none());
+        assert!(matches!(lookups[2].status, AccountLookupStatus::Found));
+        let account = lookups[2].account.as_ref().unwrap();
+        assert_eq!(account.lamports, 1000);
+        assert_eq!(account.context_slot, 42);
+    }
+
+    #[tokio::test]
+    async fn test_get_accounts_without_valid_pubkey() {
+        // Nothing listens on the port, any RPC call would fail.
+        let rpc_solana_client = test_fixtures::rpc_client("http://127.0.0.1:9");
+
+        let (headers, Json(lookups)) =
+            lookup_accounts(rpc_solana_client, vec!["invalid".to_string()])
+                .await
+                .unwrap();
+
+        assert!(!headers.contains_key("x-context-slot"));
+        assert!(matches!(
+            lookups[0].status,
+            AccountLookupStatus::InvalidPubkey
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_accounts_limit() {
+        let rpc_solana_client = accounts_client(String::new()).await;
+        let pubkeys = |count: usize| {
+            (0 .. count)
+                .map(|_| Pubkey::new_unique().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let (_, Json(lookups)) =
+            lookup_accounts(Arc::clone(&rpc_solana_client), pubkeys(MAX_BATCH_ACCOUNTS))
+                .await
+                .unwrap();
+        let rejected = lookup_accounts(rpc_solana_client, pubkeys(MAX_BATCH_ACCOUNTS + 1)).await;
+
+        assert_eq!(lookups.len(), MAX_BATCH_ACCOUNTS);
+        assert!(matches!(rejected, Err(ApiError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_export_dataset() {
+        let storage = Arc::new(Storage::default());
+        storage.insert_transaction(transaction("S1", 1, None));
+        storage.insert_transaction(transaction("S2", 2, Some("InstructionError")));
+        let query =
+            Query::try_from_uri(&"http://localhost/?format=csv&from_slot=2".parse().unwrap())
+                .unwrap();
+
+        let response = export_dataset(Path(Dataset::Transactions), query, Extension(storage))
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/csv");
+        assert_eq!(
+            response.headers()[CONTENT_DISPOSITION],
+            "attachment; filename=\"transactions.csv\""
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("slot,block_time,signature,"));
+        assert!(lines[1].starts_with("2,,S2,Blockhash,Program,,false,InstructionError,"));
     }
 }
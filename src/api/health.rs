@@ -0,0 +1,358 @@
+use crate::{
+    config::{config::HealthConfig, Config},
+    data_retrieval::{
+        rpc_pool::EndpointStatus,
+        stream_hub::{StreamHub, SubscriptionStatus, RECONNECT_DELAY},
+        RpcSolanaClient,
+    },
+    data_storage::Storage,
+};
+use axum::{http::StatusCode, Extension, Json};
+use chrono::Utc;
+use serde::Serialize;
+use solana_sdk::commitment_config::CommitmentLevel;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// Facts about the running service reported by `/api/status`, fixed at startup.
+pub struct ServiceInfo {
+    pub started_at: i64,
+    pub config:     ConfigSummary,
+    pub health:     HealthConfig,
+}
+
+impl ServiceInfo {
+    pub fn new(config: ConfigSummary, health: HealthConfig) -> Self {
+        ServiceInfo {
+            started_at: Utc::now().timestamp(),
+            config,
+            health,
+        }
+    }
+}
+
+/// Settings of the service worth knowing when troubleshooting, without URLs or secrets.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ConfigSummary {
+    #[schema(value_type = String)]
+    pub commitment:                 CommitmentLevel,
+    pub rpc_endpoints:              usize,
+    pub health_check_interval_secs: u64,
+    pub max_slot_lag:               u64,
+    pub cache_max_entries:          usize,
+    pub cache_max_bytes:            usize,
+    pub stream_buffer_size:         usize,
+    pub webhook_max_attempts:       u32,
+    pub alert_rules:                usize,
+    pub max_ingestion_lag:          Option<u64>,
+}
+
+impl From<&Config> for ConfigSummary {
+    fn from(config: &Config) -> Self {
+        ConfigSummary {
+            commitment:                 config.commitment,
+            rpc_endpoints:              config.endpoints().len(),
+            health_check_interval_secs: config.pool.health_check_interval_secs,
+            max_slot_lag:               config.pool.max_slot_lag,
+            cache_max_entries:          config.cache.max_entries,
+            cache_max_bytes:            config.cache.max_bytes,
+            stream_buffer_size:         config.stream.buffer_size,
+            webhook_max_attempts:       config.webhooks.max_attempts,
+            alert_rules:                config.alerts.rules.len(),
+            max_ingestion_lag:          config.health.max_ingestion_lag,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Liveness {
+    pub status: &'static str,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Readiness {
+    pub ready:  bool,
+    pub checks: Vec<Check>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Check {
+    /// `rpc`, `storage`, `subscriptions` or `ingestion`.
+    pub name:   &'static str,
+    pub ok:     bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ServiceStatus {
+    pub version:             &'static str,
+    /// Unix timestamp at which the service started.
+    pub started_at:          i64,
+    pub uptime_secs:         i64,
+    pub config:              ConfigSummary,
+    pub endpoints:           Vec<EndpointStatus>,
+    /// Most advanced slot reported by the RPC endpoints.
+    pub chain_slot:          Option<u64>,
+    /// Highest slot of a processed transaction or block.
+    pub last_processed_slot: Option<u64>,
+    pub ingestion_lag:       Option<u64>,
+    pub subscriptions:       Vec<SubscriptionStatus>,
+}
+
+/// Answers as long as the process serves requests.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "The process is alive", body = Liveness)
+    )
+)]
+pub async fn health() -> Json<Liveness> {
+    Json(Liveness { status: "ok" })
+}
+
+/// Reports whether the service can serve traffic, for load balancers to route around it when not.
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every check passed", body = Readiness),
+        (status = 503, description = "A check failed", body = Readiness)
+    )
+)]
+pub async fn ready(
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(storage): Extension<Arc<Storage>>, Extension(stream_hub): Extension<Arc<StreamHub>>,
+    Extension(service_info): Extension<Arc<ServiceInfo>>,
+) -> (StatusCode, Json<Readiness>) {
+    let readiness = readiness(
+        &rpc_solana_client.endpoint_status(),
+        storage.is_healthy(),
+        &stream_hub.status(),
+        storage.last_processed_slot(),
+        service_info.health.max_ingestion_lag,
+        Utc::now().timestamp(),
+    );
+    let status = match readiness.ready {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(readiness))
+}
+
+/// Returns the version, configuration and state of the upstream connections.
+#[utoipa::path(
+    get,
+    path = "/api/status",
+    tag = "health",
+    responses(
+        (status = 200, description = "State of the service", body = ServiceStatus)
+    )
+)]
+pub async fn get_status(
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(storage): Extension<Arc<Storage>>, Extension(stream_hub): Extension<Arc<StreamHub>>,
+    Extension(service_info): Extension<Arc<ServiceInfo>>,
+) -> Json<ServiceStatus> {
+    let endpoints = rpc_solana_client.endpoint_status();
+    let chain_slot = chain_slot(&endpoints);
+    let last_processed_slot = storage.last_processed_slot();
+    Json(ServiceStatus {
+        version: env!("CARGO_PKG_VERSION"),
+        started_at: service_info.started_at,
+        uptime_secs: Utc::now().timestamp() - service_info.started_at,
+        config: service_info.config.clone(),
+        endpoints,
+        chain_slot,
+        last_processed_slot,
+        ingestion_lag: chain_slot
+            .zip(last_processed_slot)
+            .map(|(chain_slot, last)| chain_slot.saturating_sub(last)),
+        subscriptions: stream_hub.status(),
+    })
+}
+
+fn chain_slot(endpoints: &[EndpointStatus]) -> Option<u64> {
+    endpoints.iter().filter_map(|endpoint| endpoint.slot).max()
+}
+
+fn readiness(
+    endpoints: &[EndpointStatus], storage_healthy: bool, subscriptions: &[SubscriptionStatus],
+    last_processed_slot: Option<u64>, max_ingestion_lag: Option<u64>, now: i64,
+) -> Readiness {
+    // Endpoints count as healthy until checked, only a completed check proves one reachable.
+    let reachable = endpoints
+        .iter()
+        .filter(|endpoint| endpoint.healthy && endpoint.last_checked.is_some())
+        .count();
+    let rpc = Check {
+        name:   "rpc",
+        ok:     reachable > 0,
+        detail: format!("{} of {} endpoints healthy", reachable, endpoints.len()),
+    };
+
+    let storage = Check {
+        name:   "storage",
+        ok:     storage_healthy,
+        detail: match storage_healthy {
+            true => "in-memory storage available".to_string(),
+            false => "in-memory storage poisoned by a panic".to_string(),
+        },
+    };
+
+    // Only the service's own subscriptions count, once they had a reconnect cycle to connect.
+    let checked: Vec<&SubscriptionStatus> = subscriptions
+        .iter()
+        .filter(|subscription| {
+            subscription.internal && now - subscription.opened_at > RECONNECT_DELAY.as_secs() as i64
+        })
+        .collect();
+    let disconnected: Vec<&str> = checked
+        .iter()
+        .filter(|subscription| !subscription.connected)
+        .map(|subscription| subscription.topic.as_str())
+        .collect();
+    let subscriptions = Check {
+        name:   "subscriptions",
+        ok:     disconnected.is_empty(),
+        detail: match disconnected.is_empty() {
+            true => format!("{} service subscriptions connected", checked.len()),
+            false => format!("disconnected: {}", disconnected.join(", ")),
+        },
+    };
+
+    let (ok, detail) = match (
+        max_ingestion_lag,
+        chain_slot(endpoints),
+        last_processed_slot,
+    ) {
+        (None, ..) => (true, "not checked".to_string()),
+        (Some(_), None, _) => (false, "chain slot unknown".to_string()),
+        // Nothing to lag behind until the first transaction or block is processed.
+        (Some(_), _, None) => (true, "nothing processed yet".to_string()),
+        (Some(max_lag), Some(chain_slot), Some(last)) => {
+            let lag = chain_slot.saturating_sub(last);
+            (
+                lag <= max_lag,
+                format!("{} slots behind, at most {} allowed", lag, max_lag),
+            )
+        }
+    };
+    let ingestion = Check {
+        name: "ingestion",
+        ok,
+        detail,
+    };
+
+    let checks = vec![rpc, storage, subscriptions, ingestion];
+    Readiness {
+        ready: checks.iter().all(|check| check.ok),
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_retrieval::rate_limiter::BudgetStatus;
+
+    fn endpoint(healthy: bool, slot: Option<u64>, last_checked: Option<i64>) -> EndpointStatus {
+        EndpointStatus {
+            rpc_url: "http://127.0.0.1:8899".to_string(),
+            ws_url: None,
+            weight: 1,
+            healthy,
+            slot,
+            slot_lag: None,
+            last_error: None,
+            last_checked,
+            budget: BudgetStatus {
+                requests_per_second: None,
+                monthly_credits:     None,
+                credits_used:        0,
+                throttled:           0,
+                rejected:            0,
+            },
+        }
+    }
+
+    /// Time of the readiness checks in the tests.
+    const NOW: i64 = 1000;
+
+    /// Subscription of the service opened long before `NOW`.
+    fn subscription(topic: &str, connected: bool) -> SubscriptionStatus {
+        SubscriptionStatus {
+            topic: topic.to_string(),
+            commitment: CommitmentLevel::Confirmed,
+            connected,
+            listeners: 1,
+            last_event_at: None,
+            last_error: None,
+            internal: true,
+            opened_at: 0,
+        }
+    }
+
+    fn failed(readiness: &Readiness) -> Vec<&str> {
+        readiness
+            .checks
+            .iter()
+            .filter(|check| !check.ok)
+            .map(|check| check.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_readiness() {
+        let endpoints = [
+            endpoint(true, Some(100), Some(1)),
+            endpoint(false, Some(10), Some(1)),
+        ];
+        let subscriptions = [subscription("logs", true)];
+
+        let report = readiness(&endpoints, true, &subscriptions, None, None, NOW);
+        assert!(report.ready);
+        assert_eq!(report.checks[0].detail, "1 of 2 endpoints healthy");
+
+        let report = readiness(&endpoints, true, &subscriptions, Some(90), Some(20), NOW);
+        assert!(report.ready);
+        let report = readiness(&endpoints, true, &subscriptions, Some(70), Some(20), NOW);
+        assert_eq!(failed(&report), vec!["ingestion"]);
+        let report = readiness(&endpoints, true, &subscriptions, None, Some(20), NOW);
+        assert!(report.ready);
+        assert_eq!(report.checks[3].detail, "nothing processed yet");
+    }
+
+    #[test]
+    fn test_readiness_failures() {
+        // Endpoints are healthy until the first check completes.
+        let endpoints = [endpoint(true, None, None)];
+        let subscriptions = [subscription("logs", true), subscription("slot", false)];
+
+        let report = readiness(&endpoints, false, &subscriptions, None, None, NOW);
+
+        assert!(!report.ready);
+        assert_eq!(failed(&report), vec!["rpc", "storage", "subscriptions"]);
+        assert_eq!(report.checks[2].detail, "disconnected: slot");
+    }
+
+    #[test]
+    fn test_readiness_ignores_client_and_new_subscriptions() {
+        let endpoints = [endpoint(true, Some(100), Some(1))];
+        let client = SubscriptionStatus {
+            internal: false,
+            ..subscription("account:Client", false)
+        };
+        let new = SubscriptionStatus {
+            opened_at: NOW,
+            ..subscription("logs:Webhook", false)
+        };
+
+        let report = readiness(&endpoints, true, &[client, new], None, None, NOW);
+
+        assert!(report.ready);
+        assert_eq!(report.checks[2].detail, "0 service subscriptions connected");
+    }
+}
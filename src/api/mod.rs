@@ -1,3 +1,8 @@
 pub mod error;
+mod graphql;
 mod handlers;
+pub mod health;
+mod openapi;
 pub mod routes;
+mod v1;
+mod websocket;
@@ -0,0 +1,315 @@
+use crate::{
+    api::{error::ErrorMessage, graphql, handlers, health, v1, websocket},
+    config::config::DocsConfig,
+};
+use axum::{
+    extract::Path,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{Html, IntoResponse, Response},
+    Extension, Json,
+};
+use std::sync::Arc;
+use tracing::warn;
+use utoipa::{
+    openapi::{Object, ObjectBuilder, Type},
+    OpenApi,
+};
+
+/// OpenAPI document of the REST API, generated from the handler annotations and the serde types.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Solana Data Aggregator",
+        description = "Processed Solana transactions, blocks and accounts."
+    ),
+    paths(
+        handlers::get_transaction_by_signature,
+        handlers::get_transaction_by_slot,
+        handlers::get_block_transactions,
+        handlers::search_transactions,
+        handlers::get_account_by_pubkey,
+        handlers::get_accounts,
+        handlers::get_account_transactions,
+        handlers::get_token_portfolio,
+        handlers::get_account_snapshots,
+        handlers::get_program_accounts,
+        handlers::get_mint,
+        handlers::get_rpc_metrics,
+        handlers::get_cache_metrics,
+        handlers::get_rpc_endpoints,
+        handlers::stream_account,
+        handlers::stream_logs,
+        handlers::create_webhook,
+        handlers::get_webhooks,
+        handlers::delete_webhook,
+        handlers::get_webhook_deliveries,
+        handlers::get_webhook_dead_letters,
+        handlers::get_alerts,
+        handlers::export_dataset,
+        websocket::websocket,
+        graphql::graphql,
+        graphql::graphiql,
+        health::health,
+        health::ready,
+        health::get_status,
+        v1::handlers::get_transaction,
+        v1::handlers::search_transactions,
+        v1::handlers::get_block,
+        v1::handlers::get_block_transactions,
+        v1::handlers::get_account,
+        v1::handlers::get_accounts,
+        v1::handlers::get_account_transactions,
+        v1::handlers::get_token_portfolio,
+        v1::handlers::get_account_snapshots,
+        v1::handlers::get_program_accounts,
+        v1::handlers::get_mint,
+        v1::handlers::get_rpc_metrics,
+        v1::handlers::get_cache_metrics,
+        v1::handlers::get_rpc_endpoints,
+        v1::handlers::create_webhook,
+        v1::handlers::get_webhooks,
+        v1::handlers::delete_webhook,
+        v1::handlers::get_webhook_deliveries,
+        v1::handlers::get_webhook_dead_letters,
+        v1::handlers::get_alerts,
+    ),
+    components(schemas(ErrorMessage)),
+    tags(
+        (name = "transactions", description = "Transactions and blocks"),
+        (name = "accounts", description = "Accounts, their history and snapshots"),
+        (name = "tokens", description = "Mints and wallet portfolios"),
+        (name = "streams", description = "Server-sent events and WebSocket subscriptions"),
+        (name = "webhooks", description = "Webhooks and their deliveries"),
+        (name = "alerts", description = "Alerts raised by the configured rules"),
+        (name = "metrics", description = "RPC and cache metrics"),
+        (name = "export", description = "Bulk export of stored data"),
+        (name = "health", description = "Liveness, readiness and status of the service"),
+        (name = "graphql", description = "GraphQL API"),
+        (name = "v1", description = "Stable API with camelCase bodies, decoupled from the internal model"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Schema of the `commitment` query parameter, `CommitmentLevel` being a foreign type.
+pub fn commitment_schema() -> Object {
+    ObjectBuilder::new()
+        .schema_type(Type::String)
+        .enum_values(Some(["processed", "confirmed", "finalized"]))
+        .description(Some(
+            "Commitment of the chain data read, the configured default when absent.",
+        ))
+        .build()
+}
+
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Serves Swagger UI over `/api/openapi.json`, its assets served by [`swagger_asset`].
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI)
+}
+
+/// Serves the Swagger UI assets from the configured directory.
+pub async fn swagger_asset(
+    Extension(docs_config): Extension<Arc<DocsConfig>>, Path(file): Path<String>,
+) -> Response {
+    let content_type = match file.as_str() {
+        "swagger-ui.css" => "text/css",
+        "swagger-ui-bundle.js" => "text/javascript",
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+    match tokio::fs::read(docs_config.swagger_ui_dir.join(&file)).await {
+        Ok(bytes) => ([(CONTENT_TYPE, content_type)], bytes).into_response(),
+        Err(e) => {
+            warn!("Error reading Swagger UI asset {}: {}", file, e);
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+}
+
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>Solana Data Aggregator API</title>
+  <link rel="stylesheet" href="/api/docs/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="/api/docs/swagger-ui-bundle.js"></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::routes, data_storage::Storage, test_fixtures::Services};
+    use axum::http::StatusCode;
+    use std::{collections::BTreeSet, sync::Arc};
+
+    /// Routes serving the documentation itself.
+    const UNDOCUMENTED: [(&str, &str); 3] = [
+        ("GET", "/api/openapi.json"),
+        ("GET", "/api/docs"),
+        ("GET", "/api/docs/{file}"),
+    ];
+
+    const METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+    fn documented_routes() -> BTreeSet<(String, String)> {
+        let mut routes = BTreeSet::new();
+        for (path, item) in ApiDoc::openapi().paths.paths {
+            for (method, operation) in METHODS.into_iter().zip([
+                &item.get,
+                &item.post,
+                &item.put,
+                &item.patch,
+                &item.delete,
+            ]) {
+                if operation.is_some() {
+                    routes.insert((method.to_string(), path.clone()));
+                }
+            }
+        }
+        routes
+    }
+
+    /// Paths of the route tables `create_router` registers, in OpenAPI syntax.
+    fn registered_paths() -> BTreeSet<String> {
+        let v1_paths = v1::routes::routes()
+            .into_iter()
+            .map(|(path, _)| format!("/api/v1{}", path));
+        routes::routes()
+            .into_iter()
+            .map(|(path, _)| path.to_string())
+            .chain(v1_paths)
+            .map(|path| {
+                path.split('/')
+                    .map(|segment| match segment.strip_prefix(':') {
+                        Some(param) => format!("{{{}}}", param),
+                        None => segment.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect()
+    }
+
+    /// Serves the whole API, answering requests no route or method matches with a status the
+    /// handlers never answer with.
+    async fn serve_api() -> String {
+        let app = Services::new(Arc::new(Storage::default()))
+            .router()
+            .fallback(|| async { StatusCode::IM_A_TEAPOT });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    /// Whether the router serves the method on the path, its parameters being invalid values
+    /// rejected before any RPC call.
+    async fn is_served(client: &reqwest::Client, url: &str, method: &str, path: &str) -> bool {
+        let path = path
+            .split('/')
+            .map(|segment| match segment.starts_with('{') {
+                true => "invalid",
+                false => segment,
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let status = client
+            .request(method.parse().unwrap(), format!("{}{}", url, path))
+            .send()
+            .await
+            .unwrap()
+            .status()
+            .as_u16();
+        status != StatusCode::IM_A_TEAPOT.as_u16()
+            && status != StatusCode::METHOD_NOT_ALLOWED.as_u16()
+    }
+
+    #[tokio::test]
+    async fn test_spec_matches_routes() {
+        let url = serve_api().await;
+        let client = reqwest::Client::new();
+        let mut served = BTreeSet::new();
+        for path in registered_paths() {
+            for method in METHODS {
+                if is_served(&client, &url, method, &path).await {
+                    served.insert((method.to_string(), path.clone()));
+                }
+            }
+        }
+        for (method, path) in UNDOCUMENTED {
+            assert!(served.remove(&(method.to_string(), path.to_string())));
+        }
+        let documented = documented_routes();
+
+        assert_eq!(
+            served.difference(&documented).collect::<Vec<_>>(),
+            Vec::<&(String, String)>::new(),
+            "routes missing from the OpenAPI document"
+        );
+        assert_eq!(
+            documented.difference(&served).collect::<Vec<_>>(),
+            Vec::<&(String, String)>::new(),
+            "documented operations without a route"
+        );
+    }
+
+    #[test]
+    fn test_spec_schemas() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = &spec["components"]["schemas"];
+
+        for schema in [
+            "TransactionData",
+            "AccountData",
+            "BlockData",
+            "ErrorMessage",
+            "v1.Transaction",
+            "v1.Account",
+            "v1.Error",
+        ] {
+            assert!(schemas[schema].is_object(), "missing schema {}", schema);
+        }
+        assert!(schemas["TransactionData"]["properties"]["fee"].is_object());
+        assert!(schemas["v1.Transaction"]["properties"]["blockTime"].is_object());
+
+        let v1_params: Vec<&str> = spec["paths"]["/api/v1/transactions/search"]["get"]
+            ["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|param| param["name"].as_str())
+            .collect();
+        assert!(v1_params.contains(&"fromSlot"));
+        assert!(v1_params.contains(&"minFee"));
+    }
+
+    #[tokio::test]
+    async fn test_swagger_asset() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("swagger-ui.css"), "body {}").unwrap();
+        let docs_config = Extension(Arc::new(DocsConfig {
+            swagger_ui_dir: dir.path().to_path_buf(),
+        }));
+
+        let response = swagger_asset(docs_config.clone(), Path("swagger-ui.css".to_string())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/css");
+
+        for file in ["swagger-ui-bundle.js", "../config.toml"] {
+            let response = swagger_asset(docs_config.clone(), Path(file.to_string())).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+    }
+}
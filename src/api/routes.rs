@@ -1,20 +1,112 @@
-use axum::{routing::get, Extension, Router};
+use axum::{
+    routing::{delete, get, post, MethodRouter},
+    Extension, Router,
+};
 use std::sync::Arc;
 
 use crate::{
-    api::handlers::{get_account_by_pubkey, get_transaction_by_signature, get_transaction_by_slot},
-    data_processing::Processor,
-    data_retrieval::RpcSolanaClient,
+    api::{
+        graphql::{build_schema, graphiql, graphql},
+        handlers::{
+            create_webhook, delete_webhook, export_dataset, get_account_by_pubkey,
+            get_account_snapshots, get_account_transactions, get_accounts, get_alerts,
+            get_block_transactions, get_cache_metrics, get_mint, get_program_accounts,
+            get_rpc_endpoints, get_rpc_metrics, get_token_portfolio, get_transaction_by_signature,
+            get_transaction_by_slot, get_webhook_dead_letters, get_webhook_deliveries,
+            get_webhooks, search_transactions, stream_account, stream_logs,
+        },
+        health::{get_status, health, ready, ServiceInfo},
+        openapi::{openapi_json, swagger_asset, swagger_ui},
+        v1,
+        websocket::websocket,
+    },
+    config::config::DocsConfig,
+    data_processing::{Processor, TokenRegistry},
+    data_retrieval::{stream_hub::StreamHub, RpcSolanaClient},
+    data_storage::Storage,
+    webhooks::WebhookDispatcher,
 };
 
-pub fn create_router(rpc_solana_client: Arc<RpcSolanaClient>, processor: Arc<Processor>) -> Router {
-    Router::new()
-        .route(
+/// Paths of the unversioned API with their handlers, shared with the OpenAPI tests.
+pub fn routes() -> Vec<(&'static str, MethodRouter)> {
+    vec![
+        ("/health", get(health)),
+        ("/ready", get(ready)),
+        ("/api/status", get(get_status)),
+        (
             "/api/transaction/signature/:signature",
             get(get_transaction_by_signature),
-        )
-        .route("/api/transaction/slot/:slot", get(get_transaction_by_slot))
-        .route("/api/account/:pubkey", get(get_account_by_pubkey))
+        ),
+        ("/api/transaction/slot/:slot", get(get_transaction_by_slot)),
+        ("/api/transactions/search", get(search_transactions)),
+        ("/api/block/:slot/transactions", get(get_block_transactions)),
+        ("/api/account/:pubkey", get(get_account_by_pubkey)),
+        ("/api/accounts", post(get_accounts)),
+        (
+            "/api/account/:pubkey/transactions",
+            get(get_account_transactions),
+        ),
+        ("/api/account/:pubkey/tokens", get(get_token_portfolio)),
+        ("/api/account/:pubkey/snapshots", get(get_account_snapshots)),
+        (
+            "/api/program/:program_id/accounts",
+            get(get_program_accounts),
+        ),
+        ("/api/token/:mint", get(get_mint)),
+        ("/api/metrics/rpc", get(get_rpc_metrics)),
+        ("/api/metrics/cache", get(get_cache_metrics)),
+        ("/api/rpc/endpoints", get(get_rpc_endpoints)),
+        ("/api/stream/account/:pubkey", get(stream_account)),
+        ("/api/stream/logs", get(stream_logs)),
+        ("/api/export/:dataset", get(export_dataset)),
+        ("/api/ws", get(websocket)),
+        ("/api/graphql", get(graphiql).post(graphql)),
+        ("/api/openapi.json", get(openapi_json)),
+        ("/api/docs", get(swagger_ui)),
+        ("/api/docs/:file", get(swagger_asset)),
+        ("/api/alerts", get(get_alerts)),
+        ("/api/webhooks", get(get_webhooks).post(create_webhook)),
+        ("/api/webhooks/:id", delete(delete_webhook)),
+        ("/api/webhooks/:id/deliveries", get(get_webhook_deliveries)),
+        (
+            "/api/webhooks/:id/dead-letters",
+            get(get_webhook_dead_letters),
+        ),
+    ]
+}
+
+#[allow(clippy::too_many_arguments)] // One extension per shared service.
+pub fn create_router(
+    rpc_solana_client: Arc<RpcSolanaClient>, processor: Arc<Processor>,
+    token_registry: Arc<TokenRegistry>, storage: Arc<Storage>, stream_hub: Arc<StreamHub>,
+    webhook_dispatcher: Arc<WebhookDispatcher>, service_info: Arc<ServiceInfo>,
+    docs_config: DocsConfig,
+) -> Router {
+    routes()
+        .into_iter()
+        .fold(Router::new(), |router, (path, method_router)| {
+            router.route(path, method_router)
+        })
+        .nest("/api/v1", v1::routes::create_router())
         .layer(Extension(rpc_solana_client))
         .layer(Extension(processor))
+        .layer(Extension(token_registry))
+        .layer(Extension(storage))
+        .layer(Extension(stream_hub))
+        .layer(Extension(webhook_dispatcher))
+        .layer(Extension(service_info))
+        .layer(Extension(Arc::new(docs_config)))
+        .layer(Extension(build_schema()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{data_storage::Storage, test_fixtures::Services};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_create_router() {
+        // Route conflicts only surface when the router is built.
+        let _router = Services::new(Arc::new(Storage::default())).router();
+    }
 }
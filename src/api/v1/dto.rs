@@ -0,0 +1,1098 @@
+use crate::{
+    alerts::rule,
+    api::{error::ApiError, handlers, openapi::commitment_schema},
+    data_processing::{processor, token},
+    data_retrieval::{
+        cache::CacheMetrics as InternalCacheMetrics, error::RetrievalErrorKind,
+        rate_limiter::BudgetStatus, retry::CallMetrics as InternalCallMetrics,
+        rpc_pool::EndpointStatus as InternalEndpointStatus,
+    },
+    data_storage::storage::{self, AccountSnapshot as InternalAccountSnapshot},
+    webhooks::webhook,
+};
+use axum::{
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use solana_sdk::commitment_config::CommitmentLevel;
+use utoipa::{IntoParams, ToSchema};
+
+/// Body of failed `/api/v1` requests.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Error)]
+pub struct Error {
+    pub code:       u16,
+    /// Stable, machine-readable identifier of the error kind.
+    pub error_code: &'static str,
+    pub message:    String,
+    /// Whether the same request may succeed if retried later.
+    pub retryable:  bool,
+}
+
+/// `ApiError` answered with the `/api/v1` error body.
+#[derive(Debug)]
+pub struct V1Error(pub ApiError);
+
+impl From<ApiError> for V1Error {
+    fn from(e: ApiError) -> Self {
+        V1Error(e)
+    }
+}
+
+impl IntoResponse for V1Error {
+    fn into_response(self) -> Response {
+        let status = self.0.status_code();
+        let body = Json(Error {
+            code:       status.as_u16(),
+            error_code: self.0.error_code(),
+            message:    self.0.to_string(),
+            retryable:  self.0.retryable(),
+        });
+
+        (status, body).into_response()
+    }
+}
+
+/// One page of a list endpoint.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Page)]
+pub struct Page<T> {
+    pub items:       Vec<T>,
+    /// Pass as `cursor` to fetch the next page, absent on the last page.
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn from_page<U: Into<T>>(page: handlers::Page<U>) -> Self {
+        Page {
+            items:       page.items.into_iter().map(Into::into).collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = v1::SortOrder)]
+pub enum SortOrder {
+    /// Oldest first.
+    Asc,
+    /// Newest first.
+    Desc,
+}
+
+impl From<SortOrder> for handlers::SortOrder {
+    fn from(sort: SortOrder) -> Self {
+        match sort {
+            SortOrder::Asc => handlers::SortOrder::Asc,
+            SortOrder::Desc => handlers::SortOrder::Desc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = v1::StatusFilter)]
+pub enum StatusFilter {
+    Success,
+    Failed,
+}
+
+impl From<StatusFilter> for handlers::StatusFilter {
+    fn from(status: StatusFilter) -> Self {
+        match status {
+            StatusFilter::Success => handlers::StatusFilter::Success,
+            StatusFilter::Failed => handlers::StatusFilter::Failed,
+        }
+    }
+}
+
+/// Paging, ordering and filters shared by the list endpoints.
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    pub limit:     Option<usize>,
+    /// `nextCursor` of the previous page.
+    pub cursor:    Option<String>,
+    pub sort:      Option<SortOrder>,
+    pub from_slot: Option<u64>,
+    pub to_slot:   Option<u64>,
+    /// Unix timestamps, inclusive like the slot range.
+    pub from_time: Option<i64>,
+    pub to_time:   Option<i64>,
+    pub status:    Option<StatusFilter>,
+    /// Only transactions invoking or mentioning this address.
+    pub program:   Option<String>,
+}
+
+impl From<ListQuery> for handlers::ListQuery {
+    fn from(query: ListQuery) -> Self {
+        handlers::ListQuery {
+            limit:     query.limit,
+            cursor:    query.cursor,
+            sort:      query.sort.map(Into::into),
+            from_slot: query.from_slot,
+            to_slot:   query.to_slot,
+            from_time: query.from_time,
+            to_time:   query.to_time,
+            status:    query.status.map(Into::into),
+            program:   query.program,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct AccountQuery {
+    #[param(schema_with = commitment_schema)]
+    pub commitment:       Option<CommitmentLevel>,
+    /// Reject responses from nodes that have not reached this slot yet.
+    pub min_context_slot: Option<u64>,
+}
+
+impl From<AccountQuery> for handlers::AccountQuery {
+    fn from(query: AccountQuery) -> Self {
+        handlers::AccountQuery {
+            commitment:       query.commitment,
+            min_context_slot: query.min_context_slot,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ProgramAccountsQuery {
+    /// Only return accounts whose data is exactly this many bytes.
+    pub data_size:         Option<u64>,
+    /// Comma separated `offset:bytes` pairs, with `bytes` base58 encoded.
+    pub memcmp:            Option<String>,
+    pub data_slice_offset: Option<usize>,
+    pub data_slice_length: Option<usize>,
+    /// Store each returned account as a snapshot, up to `max_snapshots` being kept.
+    #[serde(default)]
+    pub persist:           bool,
+    #[param(schema_with = commitment_schema)]
+    pub commitment:        Option<CommitmentLevel>,
+    pub min_context_slot:  Option<u64>,
+}
+
+impl From<ProgramAccountsQuery> for handlers::ProgramAccountsQuery {
+    fn from(query: ProgramAccountsQuery) -> Self {
+        handlers::ProgramAccountsQuery {
+            data_size:         query.data_size,
+            memcmp:            query.memcmp,
+            data_slice_offset: query.data_slice_offset,
+            data_slice_length: query.data_slice_length,
+            persist:           query.persist,
+            commitment:        query.commitment,
+            min_context_slot:  query.min_context_slot,
+        }
+    }
+}
+
+/// Criteria of a stored transaction search, all of the given ones must hold.
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct TransactionFilter {
+    /// Any account of the transaction, signers and programs included.
+    pub account:          Option<String>,
+    pub signer:           Option<String>,
+    /// Program invoked by a top-level instruction.
+    pub program_id:       Option<String>,
+    /// Type of an instruction parsed by the node, e.g. `transfer`.
+    pub instruction_type: Option<String>,
+    /// Inclusive fee range in lamports.
+    pub min_fee:          Option<u64>,
+    pub max_fee:          Option<u64>,
+}
+
+impl From<TransactionFilter> for storage::TransactionFilter {
+    fn from(filter: TransactionFilter) -> Self {
+        storage::TransactionFilter {
+            account:          filter.account,
+            signer:           filter.signer,
+            program_id:       filter.program_id,
+            instruction_type: filter.instruction_type,
+            min_fee:          filter.min_fee,
+            max_fee:          filter.max_fee,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AlertQuery {
+    pub rule:     Option<String>,
+    pub severity: Option<Severity>,
+}
+
+impl From<AlertQuery> for handlers::AlertQuery {
+    fn from(query: AlertQuery) -> Self {
+        handlers::AlertQuery {
+            rule:     query.rule,
+            severity: query.severity.map(Into::into),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Transaction)]
+pub struct Transaction {
+    /// The first signature, which identifies the transaction.
+    pub signature:       Option<String>,
+    pub signatures:      Vec<String>,
+    pub slot:            Option<u64>,
+    pub block_time:      Option<i64>,
+    pub blockhash:       String,
+    /// Lamports paid, absent when the status meta is missing.
+    pub fee:             Option<u64>,
+    /// Why the transaction failed, absent when it succeeded.
+    pub error:           Option<String>,
+    pub success:         bool,
+    pub accounts:        Vec<TransactionAccount>,
+    pub instructions:    Vec<Instruction>,
+    pub token_transfers: Vec<TokenTransfer>,
+}
+
+/// Account as referenced by a transaction.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::TransactionAccount)]
+pub struct TransactionAccount {
+    pub pubkey:         String,
+    pub writable:       bool,
+    pub signer:         bool,
+    /// Lamports gained, negative when lost.
+    pub lamport_change: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Instruction)]
+pub struct Instruction {
+    pub program_id:       String,
+    /// Base58 encoded data, empty for instructions parsed by the node.
+    pub data:             String,
+    /// Type of instructions parsed by the node, e.g. `transfer`.
+    pub instruction_type: Option<String>,
+}
+
+/// Change of a token balance made by a transaction, amounts in base units.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::TokenTransfer)]
+pub struct TokenTransfer {
+    /// The token account.
+    pub account:     String,
+    pub mint:        String,
+    pub owner:       Option<String>,
+    pub program_id:  Option<String>,
+    pub decimals:    u8,
+    pub pre_amount:  Option<String>,
+    pub post_amount: Option<String>,
+    pub name:        Option<String>,
+    pub symbol:      Option<String>,
+}
+
+impl From<processor::TransactionData> for Transaction {
+    fn from(transaction_data: processor::TransactionData) -> Self {
+        Transaction {
+            signature:       transaction_data.signature().map(str::to_string),
+            signatures:      transaction_data.signatures().to_vec(),
+            slot:            transaction_data.slot,
+            block_time:      transaction_data.block_time,
+            blockhash:       transaction_data.blockhash().to_string(),
+            fee:             transaction_data.fee,
+            success:         transaction_data.error.is_none(),
+            accounts:        transaction_data
+                .accounts()
+                .iter()
+                .map(|account| TransactionAccount {
+                    pubkey:         account.pubkey().to_string(),
+                    writable:       account.writable(),
+                    signer:         account.signer(),
+                    lamport_change: account.lamport_change(),
+                })
+                .collect(),
+            instructions:    transaction_data
+                .instructions()
+                .iter()
+                .map(|instruction| Instruction {
+                    program_id:       instruction.program_id().to_string(),
+                    data:             instruction.data().to_string(),
+                    instruction_type: instruction.instruction_type().map(str::to_string),
+                })
+                .collect(),
+            token_transfers: transaction_data
+                .token_balances
+                .into_iter()
+                .map(|balance| TokenTransfer {
+                    account:     balance.account,
+                    mint:        balance.mint,
+                    owner:       balance.owner,
+                    program_id:  balance.program_id,
+                    decimals:    balance.decimals,
+                    pre_amount:  balance.pre_amount,
+                    post_amount: balance.post_amount,
+                    name:        balance.name,
+                    symbol:      balance.symbol,
+                })
+                .collect(),
+            error:           transaction_data.error,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Block)]
+pub struct Block {
+    pub blockhash:          String,
+    pub previous_blockhash: String,
+    pub parent_slot:        u64,
+    pub block_time:         Option<i64>,
+    pub block_height:       Option<u64>,
+    /// Transactions in block order, `null` for those in unsupported formats.
+    pub transactions:       Vec<Option<Transaction>>,
+}
+
+impl From<processor::BlockData> for Block {
+    fn from(block_data: processor::BlockData) -> Self {
+        Block {
+            blockhash:          block_data.blockhash,
+            previous_blockhash: block_data.previous_blockhash,
+            parent_slot:        block_data.parent_slot,
+            block_time:         block_data.block_time,
+            block_height:       block_data.block_height,
+            transactions:       block_data
+                .transactions
+                .into_iter()
+                .map(|transaction_data| transaction_data.map(Into::into))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Account)]
+pub struct Account {
+    pub pubkey:        String,
+    pub lamports:      u64,
+    pub owner:         String,
+    pub executable:    bool,
+    pub rent_epoch:    u64,
+    /// Base64 encoded data.
+    pub data:          String,
+    /// Length of the data in bytes.
+    pub space:         usize,
+    /// Decoded state if the account is a token account.
+    pub token_account: Option<TokenAccount>,
+    /// Decoded state if the account is a mint.
+    pub mint:          Option<Mint>,
+    /// Slot of the RPC response the account was read from.
+    pub context_slot:  u64,
+    /// Unix timestamp at which the account was fetched.
+    pub fetched_at:    i64,
+}
+
+impl Account {
+    pub fn new(pubkey: String, account_data: processor::AccountData) -> Self {
+        Account {
+            pubkey,
+            lamports: account_data.lamports,
+            owner: account_data.owner,
+            executable: account_data.executable,
+            rent_epoch: account_data.rent_epoch,
+            data: STANDARD.encode(&account_data.data),
+            space: account_data.data.len(),
+            token_account: account_data.token_account.map(Into::into),
+            mint: account_data.mint.map(Into::into),
+            context_slot: account_data.context_slot,
+            fetched_at: account_data.fetched_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::TokenAccount)]
+pub struct TokenAccount {
+    pub mint:             String,
+    pub owner:            String,
+    /// Amount in base units.
+    pub amount:           String,
+    pub decimals:         Option<u8>,
+    pub ui_amount_string: Option<String>,
+    pub name:             Option<String>,
+    pub symbol:           Option<String>,
+}
+
+impl From<token::TokenAccountData> for TokenAccount {
+    fn from(token_account: token::TokenAccountData) -> Self {
+        TokenAccount {
+            mint:             token_account.mint,
+            owner:            token_account.owner,
+            amount:           token_account.amount,
+            decimals:         token_account.decimals,
+            ui_amount_string: token_account.ui_amount_string,
+            name:             token_account.name,
+            symbol:           token_account.symbol,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Mint)]
+pub struct Mint {
+    pub address:          String,
+    pub program_id:       String,
+    pub decimals:         u8,
+    /// Supply in base units.
+    pub supply:           String,
+    pub mint_authority:   Option<String>,
+    pub freeze_authority: Option<String>,
+    /// Token-2022 extensions as parsed by the node.
+    #[schema(value_type = Vec<Object>)]
+    pub extensions:       Vec<serde_json::Value>,
+    pub name:             Option<String>,
+    pub symbol:           Option<String>,
+    pub uri:              Option<String>,
+}
+
+impl From<token::MintInfo> for Mint {
+    fn from(mint_info: token::MintInfo) -> Self {
+        Mint {
+            address:          mint_info.mint,
+            program_id:       mint_info.program_id,
+            decimals:         mint_info.decimals,
+            supply:           mint_info.supply,
+            mint_authority:   mint_info.mint_authority,
+            freeze_authority: mint_info.freeze_authority,
+            extensions:       mint_info.extensions,
+            name:             mint_info.name,
+            symbol:           mint_info.symbol,
+            uri:              mint_info.uri,
+        }
+    }
+}
+
+/// Holdings of a wallet across all of its token accounts.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Portfolio)]
+pub struct Portfolio {
+    pub owner:    String,
+    pub lamports: u64,
+    pub sol:      String,
+    pub tokens:   Vec<PortfolioToken>,
+}
+
+/// Balance of a single mint, summed over every token account holding it.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::PortfolioToken)]
+pub struct PortfolioToken {
+    pub mint:             String,
+    pub amount:           String,
+    pub decimals:         Option<u8>,
+    pub ui_amount_string: Option<String>,
+    pub name:             Option<String>,
+    pub symbol:           Option<String>,
+    pub token_accounts:   Vec<String>,
+}
+
+impl From<token::Portfolio> for Portfolio {
+    fn from(portfolio: token::Portfolio) -> Self {
+        Portfolio {
+            owner:    portfolio.owner,
+            lamports: portfolio.lamports,
+            sol:      portfolio.sol,
+            tokens:   portfolio
+                .tokens
+                .into_iter()
+                .map(|token| PortfolioToken {
+                    mint:             token.mint,
+                    amount:           token.amount,
+                    decimals:         token.decimals,
+                    ui_amount_string: token.ui_amount_string,
+                    name:             token.name,
+                    symbol:           token.symbol,
+                    token_accounts:   token.token_accounts,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[schema(as = v1::AccountsRequest)]
+pub struct AccountsRequest {
+    pub pubkeys: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::AccountLookup)]
+pub struct AccountLookup {
+    pub pubkey:  String,
+    pub status:  AccountLookupStatus,
+    pub account: Option<Account>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = v1::AccountLookupStatus)]
+pub enum AccountLookupStatus {
+    Found,
+    NotFound,
+    InvalidPubkey,
+}
+
+impl From<handlers::AccountLookupStatus> for AccountLookupStatus {
+    fn from(status: handlers::AccountLookupStatus) -> Self {
+        match status {
+            handlers::AccountLookupStatus::Found => AccountLookupStatus::Found,
+            handlers::AccountLookupStatus::NotFound => AccountLookupStatus::NotFound,
+            handlers::AccountLookupStatus::InvalidPubkey => AccountLookupStatus::InvalidPubkey,
+        }
+    }
+}
+
+impl From<handlers::AccountLookup> for AccountLookup {
+    fn from(lookup: handlers::AccountLookup) -> Self {
+        AccountLookup {
+            account: lookup
+                .account
+                .map(|account_data| Account::new(lookup.pubkey.clone(), account_data)),
+            pubkey:  lookup.pubkey,
+            status:  lookup.status.into(),
+        }
+    }
+}
+
+/// Point-in-time copy of an account as returned by the RPC node.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::AccountSnapshot)]
+pub struct AccountSnapshot {
+    /// Unix timestamp at which the snapshot was taken.
+    pub captured_at: i64,
+    pub account:     Account,
+}
+
+impl From<InternalAccountSnapshot> for AccountSnapshot {
+    fn from(snapshot: InternalAccountSnapshot) -> Self {
+        AccountSnapshot {
+            captured_at: snapshot.captured_at,
+            account:     Account::new(snapshot.pubkey, snapshot.account),
+        }
+    }
+}
+
+/// Conditions a transaction has to meet to be delivered, all of the given ones must hold.
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::WebhookFilter)]
+pub struct WebhookFilter {
+    pub account:            Option<String>,
+    pub program_id:         Option<String>,
+    /// Type of an instruction parsed by the node, e.g. `transfer`.
+    pub instruction_type:   Option<String>,
+    /// Lamports gained or lost by `account`, or by any account when no account is given.
+    pub min_lamport_change: Option<u64>,
+}
+
+impl From<webhook::WebhookFilter> for WebhookFilter {
+    fn from(filter: webhook::WebhookFilter) -> Self {
+        WebhookFilter {
+            account:            filter.account,
+            program_id:         filter.program_id,
+            instruction_type:   filter.instruction_type,
+            min_lamport_change: filter.min_lamport_change,
+        }
+    }
+}
+
+impl From<WebhookFilter> for webhook::WebhookFilter {
+    fn from(filter: WebhookFilter) -> Self {
+        webhook::WebhookFilter {
+            account:            filter.account,
+            program_id:         filter.program_id,
+            instruction_type:   filter.instruction_type,
+            min_lamport_change: filter.min_lamport_change,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::WebhookRequest)]
+pub struct WebhookRequest {
+    pub url:    String,
+    #[serde(flatten)]
+    pub filter: WebhookFilter,
+}
+
+/// Endpoint notified of the processed transactions matching its filter.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Webhook)]
+pub struct Webhook {
+    pub id:         String,
+    pub url:        String,
+    pub filter:     WebhookFilter,
+    pub created_at: i64,
+}
+
+impl From<webhook::Webhook> for Webhook {
+    fn from(webhook: webhook::Webhook) -> Self {
+        Webhook {
+            id:         webhook.id,
+            url:        webhook.url,
+            filter:     webhook.filter.into(),
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+/// Webhook as registered, the only response including its signing secret.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::RegisteredWebhook)]
+pub struct RegisteredWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret:  String,
+}
+
+impl From<handlers::RegisteredWebhook> for RegisteredWebhook {
+    fn from(registered: handlers::RegisteredWebhook) -> Self {
+        RegisteredWebhook {
+            webhook: registered.webhook.into(),
+            secret:  registered.secret,
+        }
+    }
+}
+
+/// One transaction sent to a webhook, with the outcome of its last attempt.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Delivery)]
+pub struct Delivery {
+    pub id:          String,
+    pub webhook_id:  String,
+    pub signature:   Option<String>,
+    pub status:      DeliveryStatus,
+    pub attempts:    u32,
+    pub http_status: Option<u16>,
+    pub error:       Option<String>,
+    pub created_at:  i64,
+    pub updated_at:  i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = v1::DeliveryStatus)]
+pub enum DeliveryStatus {
+    /// Still being attempted.
+    Pending,
+    Delivered,
+    /// Every attempt failed, the payload was moved to the dead letters.
+    Failed,
+}
+
+impl From<webhook::DeliveryStatus> for DeliveryStatus {
+    fn from(status: webhook::DeliveryStatus) -> Self {
+        match status {
+            webhook::DeliveryStatus::Pending => DeliveryStatus::Pending,
+            webhook::DeliveryStatus::Delivered => DeliveryStatus::Delivered,
+            webhook::DeliveryStatus::Failed => DeliveryStatus::Failed,
+        }
+    }
+}
+
+impl From<webhook::Delivery> for Delivery {
+    fn from(delivery: webhook::Delivery) -> Self {
+        Delivery {
+            id:          delivery.id,
+            webhook_id:  delivery.webhook_id,
+            signature:   delivery.signature,
+            status:      delivery.status.into(),
+            attempts:    delivery.attempts,
+            http_status: delivery.http_status,
+            error:       delivery.error,
+            created_at:  delivery.created_at,
+            updated_at:  delivery.updated_at,
+        }
+    }
+}
+
+/// Payload that could not be delivered, kept for inspection.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::DeadLetter)]
+pub struct DeadLetter {
+    pub delivery_id: String,
+    pub webhook_id:  String,
+    pub url:         String,
+    pub payload:     Transaction,
+    pub error:       String,
+    pub failed_at:   i64,
+}
+
+impl From<webhook::DeadLetter> for DeadLetter {
+    fn from(dead_letter: webhook::DeadLetter) -> Self {
+        DeadLetter {
+            delivery_id: dead_letter.delivery_id,
+            webhook_id:  dead_letter.webhook_id,
+            url:         dead_letter.url,
+            payload:     dead_letter.payload.into(),
+            error:       dead_letter.error,
+            failed_at:   dead_letter.failed_at,
+        }
+    }
+}
+
+/// Alert raised by a rule.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Alert)]
+pub struct Alert {
+    pub id:           String,
+    pub rule:         String,
+    pub severity:     Severity,
+    pub message:      String,
+    /// Address the condition is about.
+    pub address:      String,
+    pub signature:    Option<String>,
+    pub slot:         Option<u64>,
+    pub triggered_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = v1::Severity)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl From<rule::Severity> for Severity {
+    fn from(severity: rule::Severity) -> Self {
+        match severity {
+            rule::Severity::Info => Severity::Info,
+            rule::Severity::Warning => Severity::Warning,
+            rule::Severity::Critical => Severity::Critical,
+        }
+    }
+}
+
+impl From<Severity> for rule::Severity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Info => rule::Severity::Info,
+            Severity::Warning => rule::Severity::Warning,
+            Severity::Critical => rule::Severity::Critical,
+        }
+    }
+}
+
+impl From<rule::Alert> for Alert {
+    fn from(alert: rule::Alert) -> Self {
+        Alert {
+            id:           alert.id,
+            rule:         alert.rule,
+            severity:     alert.severity.into(),
+            message:      alert.message,
+            address:      alert.address,
+            signature:    alert.signature,
+            slot:         alert.slot,
+            triggered_at: alert.triggered_at,
+        }
+    }
+}
+
+/// Health of one RPC endpoint, its URLs reduced to their scheme, host and port.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::EndpointStatus)]
+pub struct EndpointStatus {
+    pub rpc_url:      String,
+    pub ws_url:       Option<String>,
+    pub weight:       u32,
+    pub healthy:      bool,
+    pub slot:         Option<u64>,
+    pub slot_lag:     Option<u64>,
+    pub last_error:   Option<String>,
+    pub last_checked: Option<i64>,
+    pub budget:       Budget,
+}
+
+/// Consumed budget of one endpoint since the process started.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::Budget)]
+pub struct Budget {
+    pub requests_per_second: Option<f64>,
+    pub monthly_credits:     Option<u64>,
+    /// Credits consumed in the current month.
+    pub credits_used:        u64,
+    /// Calls that had to wait for credits.
+    pub throttled:           u64,
+    /// Calls that failed because no credits were available in time.
+    pub rejected:            u64,
+}
+
+impl From<InternalEndpointStatus> for EndpointStatus {
+    fn from(status: InternalEndpointStatus) -> Self {
+        EndpointStatus {
+            rpc_url:      status.rpc_url,
+            ws_url:       status.ws_url,
+            weight:       status.weight,
+            healthy:      status.healthy,
+            slot:         status.slot,
+            slot_lag:     status.slot_lag,
+            last_error:   status.last_error,
+            last_checked: status.last_checked,
+            budget:       status.budget.into(),
+        }
+    }
+}
+
+impl From<BudgetStatus> for Budget {
+    fn from(budget: BudgetStatus) -> Self {
+        Budget {
+            requests_per_second: budget.requests_per_second,
+            monthly_credits:     budget.monthly_credits,
+            credits_used:        budget.credits_used,
+            throttled:           budget.throttled,
+            rejected:            budget.rejected,
+        }
+    }
+}
+
+/// Counters of one RPC method, accumulated since startup.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::CallMetrics)]
+pub struct CallMetrics {
+    pub method:          String,
+    /// Logical calls, each made of one or more attempts.
+    pub calls:           u64,
+    pub attempts:        u64,
+    pub retries:         u64,
+    pub successes:       u64,
+    pub failures:        u64,
+    pub last_error_kind: Option<ErrorKind>,
+}
+
+/// Class of an RPC failure.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = v1::ErrorKind)]
+pub enum ErrorKind {
+    InvalidInput,
+    NotFound,
+    RateLimited,
+    UpstreamTimeout,
+    UpstreamError,
+    DecodeFailure,
+}
+
+impl From<RetrievalErrorKind> for ErrorKind {
+    fn from(kind: RetrievalErrorKind) -> Self {
+        match kind {
+            RetrievalErrorKind::InvalidInput => ErrorKind::InvalidInput,
+            RetrievalErrorKind::NotFound => ErrorKind::NotFound,
+            RetrievalErrorKind::RateLimited => ErrorKind::RateLimited,
+            RetrievalErrorKind::UpstreamTimeout => ErrorKind::UpstreamTimeout,
+            RetrievalErrorKind::UpstreamError => ErrorKind::UpstreamError,
+            RetrievalErrorKind::DecodeFailure => ErrorKind::DecodeFailure,
+        }
+    }
+}
+
+impl CallMetrics {
+    pub fn new(method: &str, metrics: InternalCallMetrics) -> Self {
+        CallMetrics {
+            method:          method.to_string(),
+            calls:           metrics.calls,
+            attempts:        metrics.attempts,
+            retries:         metrics.retries,
+            successes:       metrics.successes,
+            failures:        metrics.failures,
+            last_error_kind: metrics.last_error_kind.map(Into::into),
+        }
+    }
+}
+
+/// Hit, miss and eviction counters of the response cache.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = v1::CacheMetrics)]
+pub struct CacheMetrics {
+    pub entries:       usize,
+    /// Size of the cached responses serialized as JSON.
+    pub bytes:         usize,
+    pub hits:          u64,
+    pub misses:        u64,
+    /// Entries dropped to make room for new ones.
+    pub evictions:     u64,
+    /// Entries dropped because an update was reported for them.
+    pub invalidations: u64,
+}
+
+impl From<InternalCacheMetrics> for CacheMetrics {
+    fn from(metrics: InternalCacheMetrics) -> Self {
+        CacheMetrics {
+            entries:       metrics.entries,
+            bytes:         metrics.bytes,
+            hits:          metrics.hits,
+            misses:        metrics.misses,
+            evictions:     metrics.evictions,
+            invalidations: metrics.invalidations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use serde_json::json;
+
+    #[test]
+    fn test_transaction_is_camel_case() {
+        let transaction_data: processor::TransactionData = serde_json::from_value(json!({
+            "signatures": ["Signature1"],
+            "blockhash": "Blockhash",
+            "accounts": [
+                {"pubkey": "Payer", "writable": true, "signer": true, "lamport_change": -5000}
+            ],
+            "instructions": [{
+                "program_id": "11111111111111111111111111111111",
+                "data": "",
+                "instruction_type": "transfer"
+            }],
+            "token_balances": [],
+            "slot": 7,
+            "block_time": 1700000000,
+            "fee": 5000
+        }))
+        .unwrap();
+
+        let transaction = serde_json::to_value(Transaction::from(transaction_data)).unwrap();
+
+        assert_eq!(
+            transaction,
+            json!({
+                "signature": "Signature1",
+                "signatures": ["Signature1"],
+                "slot": 7,
+                "blockTime": 1700000000,
+                "blockhash": "Blockhash",
+                "fee": 5000,
+                "error": null,
+                "success": true,
+                "accounts": [
+                    {"pubkey": "Payer", "writable": true, "signer": true, "lamportChange": -5000}
+                ],
+                "instructions": [{
+                    "programId": "11111111111111111111111111111111",
+                    "data": "",
+                    "instructionType": "transfer"
+                }],
+                "tokenTransfers": []
+            })
+        );
+    }
+
+    #[test]
+    fn test_account_data_is_base64() {
+        let account_data: processor::AccountData = serde_json::from_value(json!({
+            "lamports": 1,
+            "data": [1, 2, 3],
+            "owner": "11111111111111111111111111111111",
+            "executable": false,
+            "rent_epoch": 0,
+            "token_account": null,
+            "mint": null,
+            "context_slot": 7,
+            "fetched_at": 0
+        }))
+        .unwrap();
+
+        let account =
+            serde_json::to_value(Account::new("Account".to_string(), account_data)).unwrap();
+
+        assert_eq!(account["data"], "AQID");
+        assert_eq!(account["space"], 3);
+        assert_eq!(account["contextSlot"], 7);
+        assert_eq!(account["rentEpoch"], 0);
+    }
+
+    #[test]
+    fn test_webhook_request_is_camel_case() {
+        let request: WebhookRequest = serde_json::from_value(json!({
+            "url": "https://example.com/hook",
+            "programId": "Program",
+            "minLamportChange": 1000
+        }))
+        .unwrap();
+        let filter = webhook::WebhookFilter::from(request.filter);
+
+        assert_eq!(filter.program_id.as_deref(), Some("Program"));
+        assert_eq!(filter.min_lamport_change, Some(1000));
+    }
+
+    #[test]
+    fn test_queries_are_camel_case() {
+        let uri = "/?fromSlot=5&sort=desc&status=failed&programId=Program&minFee=10"
+            .parse()
+            .unwrap();
+        let Query(list_query) = Query::<ListQuery>::try_from_uri(&uri).unwrap();
+        let Query(filter) = Query::<TransactionFilter>::try_from_uri(&uri).unwrap();
+        let list_query = handlers::ListQuery::from(list_query);
+        let filter = storage::TransactionFilter::from(filter);
+
+        assert_eq!(list_query.from_slot, Some(5));
+        assert_eq!(list_query.sort, Some(handlers::SortOrder::Desc));
+        assert_eq!(list_query.status, Some(handlers::StatusFilter::Failed));
+        assert_eq!(filter.program_id.as_deref(), Some("Program"));
+        assert_eq!(filter.min_fee, Some(10));
+    }
+
+    #[test]
+    fn test_enums_are_converted() {
+        let lookup = AccountLookup::from(handlers::AccountLookup {
+            pubkey:  "Account".to_string(),
+            status:  handlers::AccountLookupStatus::InvalidPubkey,
+            account: None,
+        });
+
+        assert_eq!(
+            serde_json::to_value(lookup).unwrap(),
+            json!({"pubkey": "Account", "status": "invalid_pubkey", "account": null})
+        );
+        assert_eq!(
+            serde_json::to_value(ErrorKind::from(RetrievalErrorKind::RateLimited)).unwrap(),
+            json!("rate_limited")
+        );
+        assert_eq!(
+            rule::Severity::from(Severity::from(rule::Severity::Critical)),
+            rule::Severity::Critical
+        );
+    }
+}
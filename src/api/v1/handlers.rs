@@ -0,0 +1,505 @@
+use crate::{
+    api::{
+        handlers::{self, CommitmentQuery, HistoryQuery},
+        v1::dto::{
+            Account, AccountLookup, AccountQuery, AccountSnapshot, AccountsRequest, Alert,
+            AlertQuery, Block, CacheMetrics, CallMetrics, DeadLetter, Delivery, EndpointStatus,
+            Error, ListQuery, Mint, Page, Portfolio, ProgramAccountsQuery, RegisteredWebhook,
+            Transaction, TransactionFilter, V1Error, Webhook, WebhookRequest,
+        },
+    },
+    data_processing::{Processor, TokenRegistry},
+    data_retrieval::RpcSolanaClient,
+    data_storage::Storage,
+    webhooks::WebhookDispatcher,
+};
+use axum::{
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    Extension, Json,
+};
+use std::sync::Arc;
+
+// Each handler delegates to its unversioned counterpart and converts the result, so changes to
+// the processor model only need the conversions of `dto` updated.
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/transactions/{signature}",
+    tag = "v1",
+    params(
+        ("signature" = String, Path, description = "Base58 transaction signature"),
+        CommitmentQuery
+    ),
+    responses(
+        (status = 200, description = "Processed transaction", body = Transaction),
+        (status = 400, description = "Invalid signature", body = Error),
+        (status = 404, description = "Transaction not found", body = Error),
+        (status = 429, description = "Rate limited by the RPC provider", body = Error),
+        (status = 502, description = "Upstream error or undecodable response", body = Error),
+        (status = 503, description = "Upstream timeout", body = Error)
+    )
+)]
+pub async fn get_transaction(
+    rpc_solana_client: Extension<Arc<RpcSolanaClient>>, processor: Extension<Arc<Processor>>,
+    token_registry: Extension<Arc<TokenRegistry>>, storage: Extension<Arc<Storage>>,
+    signature: Path<String>, query: Query<CommitmentQuery>,
+) -> Result<(HeaderMap, Json<Transaction>), V1Error> {
+    let (headers, Json(transaction_data)) = handlers::get_transaction_by_signature(
+        rpc_solana_client,
+        processor,
+        token_registry,
+        storage,
+        signature,
+        query,
+    )
+    .await?;
+    Ok((headers, Json(transaction_data.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/transactions/search",
+    tag = "v1",
+    params(TransactionFilter, ListQuery),
+    responses(
+        (status = 200, description = "Matching stored transactions", body = Page<Transaction>),
+        (status = 400, description = "Invalid query", body = Error)
+    )
+)]
+pub async fn search_transactions(
+    filter: Query<TransactionFilter>, list_query: Query<ListQuery>,
+    storage: Extension<Arc<Storage>>,
+) -> Result<Json<Page<Transaction>>, V1Error> {
+    let Json(page) =
+        handlers::search_transactions(Query(filter.0.into()), Query(list_query.0.into()), storage)
+            .await?;
+    Ok(Json(Page::from_page(page)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/blocks/{slot}",
+    tag = "v1",
+    params(("slot" = u64, Path), CommitmentQuery),
+    responses(
+        (status = 200, description = "Processed block", body = Block),
+        (status = 400, description = "Invalid slot", body = Error),
+        (status = 404, description = "Block not found", body = Error),
+        (status = 429, description = "Rate limited by the RPC provider", body = Error),
+        (status = 502, description = "Upstream error or undecodable response", body = Error),
+        (status = 503, description = "Upstream timeout", body = Error)
+    )
+)]
+pub async fn get_block(
+    rpc_solana_client: Extension<Arc<RpcSolanaClient>>, processor: Extension<Arc<Processor>>,
+    token_registry: Extension<Arc<TokenRegistry>>, storage: Extension<Arc<Storage>>,
+    slot: Path<u64>, query: Query<CommitmentQuery>,
+) -> Result<(HeaderMap, Json<Block>), V1Error> {
+    let (headers, Json(block_data)) = handlers::get_transaction_by_slot(
+        rpc_solana_client,
+        processor,
+        token_registry,
+        storage,
+        slot,
+        query,
+    )
+    .await?;
+    Ok((headers, Json(block_data.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/blocks/{slot}/transactions",
+    tag = "v1",
+    params(("slot" = u64, Path), CommitmentQuery, ListQuery),
+    responses(
+        (status = 200, description = "Processed transactions of the block", body = Page<Transaction>),
+        (status = 400, description = "Invalid slot or query", body = Error),
+        (status = 404, description = "Block not found", body = Error),
+        (status = 429, description = "Rate limited by the RPC provider", body = Error),
+        (status = 502, description = "Upstream error or undecodable response", body = Error),
+        (status = 503, description = "Upstream timeout", body = Error)
+    )
+)]
+pub async fn get_block_transactions(
+    rpc_solana_client: Extension<Arc<RpcSolanaClient>>, processor: Extension<Arc<Processor>>,
+    token_registry: Extension<Arc<TokenRegistry>>, storage: Extension<Arc<Storage>>,
+    slot: Path<u64>, query: Query<CommitmentQuery>, list_query: Query<ListQuery>,
+) -> Result<(HeaderMap, Json<Page<Transaction>>), V1Error> {
+    let (headers, Json(page)) = handlers::get_block_transactions(
+        rpc_solana_client,
+        processor,
+        token_registry,
+        storage,
+        slot,
+        query,
+        Query(list_query.0.into()),
+    )
+    .await?;
+    Ok((headers, Json(Page::from_page(page))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/accounts/{pubkey}",
+    tag = "v1",
+    params(("pubkey" = String, Path), AccountQuery),
+    responses(
+        (status = 200, description = "Processed account", body = Account),
+        (status = 400, description = "Invalid pubkey", body = Error),
+        (status = 404, description = "Account not found", body = Error),
+        (status = 429, description = "Rate limited by the RPC provider", body = Error),
+        (status = 502, description = "Upstream error or undecodable response", body = Error),
+        (status = 503, description = "Upstream timeout", body = Error)
+    )
+)]
+pub async fn get_account(
+    Path(pubkey): Path<String>, rpc_solana_client: Extension<Arc<RpcSolanaClient>>,
+    processor: Extension<Arc<Processor>>, token_registry: Extension<Arc<TokenRegistry>>,
+    query: Query<AccountQuery>,
+) -> Result<(HeaderMap, Json<Account>), V1Error> {
+    let (headers, Json(account_data)) = handlers::get_account_by_pubkey(
+        Path(pubkey.clone()),
+        rpc_solana_client,
+        processor,
+        token_registry,
+        Query(query.0.into()),
+    )
+    .await?;
+    Ok((headers, Json(Account::new(pubkey, account_data))))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/accounts",
+    tag = "v1",
+    params(AccountQuery),
+    request_body = AccountsRequest,
+    responses(
+        (status = 200, description = "Lookup of every pubkey, in request order", body = Vec<AccountLookup>),
+        (status = 400, description = "Invalid request", body = Error),
+        (status = 429, description = "Rate limited by the RPC provider", body = Error),
+        (status = 502, description = "Upstream error or undecodable response", body = Error),
+        (status = 503, description = "Upstream timeout", body = Error)
+    )
+)]
+pub async fn get_accounts(
+    rpc_solana_client: Extension<Arc<RpcSolanaClient>>, processor: Extension<Arc<Processor>>,
+    token_registry: Extension<Arc<TokenRegistry>>, query: Query<AccountQuery>,
+    Json(request): Json<AccountsRequest>,
+) -> Result<(HeaderMap, Json<Vec<AccountLookup>>), V1Error> {
+    let (headers, Json(lookups)) = handlers::get_accounts(
+        rpc_solana_client,
+        processor,
+        token_registry,
+        Query(query.0.into()),
+        Json(handlers::AccountsRequest {
+            pubkeys: request.pubkeys,
+        }),
+    )
+    .await?;
+    Ok((headers, Json(lookups.into_iter().map(Into::into).collect())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/accounts/{pubkey}/transactions",
+    tag = "v1",
+    params(("pubkey" = String, Path), HistoryQuery, ListQuery),
+    responses(
+        (status = 200, description = "Processed transactions of the account", body = Page<Transaction>),
+        (status = 400, description = "Invalid pubkey or query", body = Error),
+        (status = 429, description = "Rate limited by the RPC provider", body = Error),
+        (status = 502, description = "Upstream error or undecodable response", body = Error),
+        (status = 503, description = "Upstream timeout", body = Error)
+    )
+)]
+pub async fn get_account_transactions(
+    pubkey: Path<String>, query: Query<HistoryQuery>, list_query: Query<ListQuery>,
+    rpc_solana_client: Extension<Arc<RpcSolanaClient>>, processor: Extension<Arc<Processor>>,
+    token_registry: Extension<Arc<TokenRegistry>>, storage: Extension<Arc<Storage>>,
+) -> Result<(HeaderMap, Json<Page<Transaction>>), V1Error> {
+    let (headers, Json(history)) = handlers::get_account_transactions(
+        pubkey,
+        query,
+        Query(list_query.0.into()),
+        rpc_solana_client,
+        processor,
+        token_registry,
+        storage,
+    )
+    .await?;
+    Ok((headers, Json(Page::from_page(history.page))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/accounts/{pubkey}/tokens",
+    tag = "v1",
+    params(("pubkey" = String, Path, description = "Wallet owning the token accounts"), AccountQuery),
+    responses(
+        (status = 200, description = "SOL and token balances of the wallet", body = Portfolio),
+        (status = 400, description = "Invalid pubkey", body = Error),
+        (status = 429, description = "Rate limited by the RPC provider", body = Error),
+        (status = 502, description = "Upstream error or undecodable response", body = Error),
+        (status = 503, description = "Upstream timeout", body = Error)
+    )
+)]
+pub async fn get_token_portfolio(
+    owner: Path<String>, rpc_solana_client: Extension<Arc<RpcSolanaClient>>,
+    processor: Extension<Arc<Processor>>, token_registry: Extension<Arc<TokenRegistry>>,
+    query: Query<AccountQuery>,
+) -> Result<(HeaderMap, Json<Portfolio>), V1Error> {
+    let (headers, Json(portfolio)) = handlers::get_token_portfolio(
+        owner,
+        rpc_solana_client,
+        processor,
+        token_registry,
+        Query(query.0.into()),
+    )
+    .await?;
+    Ok((headers, Json(portfolio.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/accounts/{pubkey}/snapshots",
+    tag = "v1",
+    params(("pubkey" = String, Path), ListQuery),
+    responses(
+        (status = 200, description = "Stored snapshots of the account", body = Page<AccountSnapshot>),
+        (status = 400, description = "Invalid query", body = Error)
+    )
+)]
+pub async fn get_account_snapshots(
+    pubkey: Path<String>, list_query: Query<ListQuery>, storage: Extension<Arc<Storage>>,
+) -> Result<Json<Page<AccountSnapshot>>, V1Error> {
+    let Json(page) =
+        handlers::get_account_snapshots(pubkey, Query(list_query.0.into()), storage).await?;
+    Ok(Json(Page::from_page(page)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/programs/{program_id}/accounts",
+    tag = "v1",
+    params(("program_id" = String, Path), ProgramAccountsQuery),
+    responses(
+        (status = 200, description = "Accounts owned by the program, one JSON object per line, sent once the node has returned all of them", body = Account, content_type = "application/x-ndjson"),
+        (status = 400, description = "Invalid program id or filter", body = Error),
+        (status = 429, description = "Rate limited by the RPC provider", body = Error),
+        (status = 502, description = "Upstream error or undecodable response", body = Error),
+        (status = 503, description = "Upstream timeout", body = Error)
+    )
+)]
+pub async fn get_program_accounts(
+    Path(program_id): Path<String>, Query(query): Query<ProgramAccountsQuery>,
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(processor): Extension<Arc<Processor>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>,
+    Extension(storage): Extension<Arc<Storage>>,
+) -> Result<Response, V1Error> {
+    Ok(handlers::stream_program_accounts(
+        program_id,
+        query.into(),
+        rpc_solana_client,
+        processor,
+        token_registry,
+        storage,
+        Account::new,
+    )
+    .await?)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/tokens/{mint}",
+    tag = "v1",
+    params(("mint" = String, Path), AccountQuery),
+    responses(
+        (status = 200, description = "Mint with its metadata", body = Mint),
+        (status = 400, description = "Invalid mint", body = Error),
+        (status = 404, description = "Mint not found", body = Error),
+        (status = 429, description = "Rate limited by the RPC provider", body = Error),
+        (status = 502, description = "Upstream error or undecodable response", body = Error),
+        (status = 503, description = "Upstream timeout", body = Error)
+    )
+)]
+pub async fn get_mint(
+    mint: Path<String>, rpc_solana_client: Extension<Arc<RpcSolanaClient>>,
+    token_registry: Extension<Arc<TokenRegistry>>, query: Query<AccountQuery>,
+) -> Result<(HeaderMap, Json<Mint>), V1Error> {
+    let (headers, Json(mint_info)) = handlers::get_mint(
+        mint,
+        rpc_solana_client,
+        token_registry,
+        Query(query.0.into()),
+    )
+    .await?;
+    Ok((headers, Json(mint_info.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/metrics/rpc",
+    tag = "v1",
+    responses(
+        (status = 200, description = "Counters of every RPC method called", body = Vec<CallMetrics>)
+    )
+)]
+pub async fn get_rpc_metrics(
+    rpc_solana_client: Extension<Arc<RpcSolanaClient>>,
+) -> Json<Vec<CallMetrics>> {
+    let Json(metrics) = handlers::get_rpc_metrics(rpc_solana_client).await;
+    Json(
+        metrics
+            .into_iter()
+            .map(|(method, metrics)| CallMetrics::new(method, metrics))
+            .collect(),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/metrics/cache",
+    tag = "v1",
+    responses(
+        (status = 200, description = "Counters of the RPC response cache", body = CacheMetrics)
+    )
+)]
+pub async fn get_cache_metrics(
+    rpc_solana_client: Extension<Arc<RpcSolanaClient>>,
+) -> Json<CacheMetrics> {
+    let Json(metrics) = handlers::get_cache_metrics(rpc_solana_client).await;
+    Json(metrics.into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rpc/endpoints",
+    tag = "v1",
+    responses(
+        (status = 200, description = "Health of every RPC endpoint", body = Vec<EndpointStatus>)
+    )
+)]
+pub async fn get_rpc_endpoints(
+    rpc_solana_client: Extension<Arc<RpcSolanaClient>>,
+) -> Json<Vec<EndpointStatus>> {
+    let Json(endpoints) = handlers::get_rpc_endpoints(rpc_solana_client).await;
+    Json(endpoints.into_iter().map(Into::into).collect())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks",
+    tag = "v1",
+    request_body = WebhookRequest,
+    responses(
+        (status = 201, description = "Registered webhook with its signing secret", body = RegisteredWebhook),
+        (status = 400, description = "Invalid URL or filter", body = Error)
+    )
+)]
+pub async fn create_webhook(
+    webhook_dispatcher: Extension<Arc<WebhookDispatcher>>, Json(request): Json<WebhookRequest>,
+) -> Result<(StatusCode, Json<RegisteredWebhook>), V1Error> {
+    let (status, Json(registered)) = handlers::create_webhook(
+        webhook_dispatcher,
+        Json(handlers::WebhookRequest {
+            url:    request.url,
+            filter: request.filter.into(),
+        }),
+    )
+    .await?;
+    Ok((status, Json(registered.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks",
+    tag = "v1",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "Registered webhooks", body = Page<Webhook>),
+        (status = 400, description = "Invalid query", body = Error)
+    )
+)]
+pub async fn get_webhooks(
+    list_query: Query<ListQuery>, storage: Extension<Arc<Storage>>,
+) -> Result<Json<Page<Webhook>>, V1Error> {
+    let Json(page) = handlers::get_webhooks(Query(list_query.0.into()), storage).await?;
+    Ok(Json(Page::from_page(page)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{id}",
+    tag = "v1",
+    params(("id" = String, Path)),
+    responses(
+        (status = 204, description = "Webhook removed"),
+        (status = 404, description = "Webhook not found", body = Error)
+    )
+)]
+pub async fn delete_webhook(
+    id: Path<String>, webhook_dispatcher: Extension<Arc<WebhookDispatcher>>,
+) -> Result<StatusCode, V1Error> {
+    Ok(handlers::delete_webhook(id, webhook_dispatcher).await?)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}/deliveries",
+    tag = "v1",
+    params(("id" = String, Path), ListQuery),
+    responses(
+        (status = 200, description = "Recent deliveries of the webhook", body = Page<Delivery>),
+        (status = 400, description = "Invalid query", body = Error),
+        (status = 404, description = "Webhook not found", body = Error)
+    )
+)]
+pub async fn get_webhook_deliveries(
+    id: Path<String>, list_query: Query<ListQuery>, storage: Extension<Arc<Storage>>,
+) -> Result<Json<Page<Delivery>>, V1Error> {
+    let Json(page) =
+        handlers::get_webhook_deliveries(id, Query(list_query.0.into()), storage).await?;
+    Ok(Json(Page::from_page(page)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}/dead-letters",
+    tag = "v1",
+    params(("id" = String, Path), ListQuery),
+    responses(
+        (status = 200, description = "Payloads the webhook never accepted", body = Page<DeadLetter>),
+        (status = 400, description = "Invalid query", body = Error),
+        (status = 404, description = "Webhook not found", body = Error)
+    )
+)]
+pub async fn get_webhook_dead_letters(
+    id: Path<String>, list_query: Query<ListQuery>, storage: Extension<Arc<Storage>>,
+) -> Result<Json<Page<DeadLetter>>, V1Error> {
+    let Json(page) =
+        handlers::get_webhook_dead_letters(id, Query(list_query.0.into()), storage).await?;
+    Ok(Json(Page::from_page(page)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/alerts",
+    tag = "v1",
+    params(AlertQuery, ListQuery),
+    responses(
+        (status = 200, description = "Stored alerts", body = Page<Alert>),
+        (status = 400, description = "Invalid query", body = Error)
+    )
+)]
+pub async fn get_alerts(
+    query: Query<AlertQuery>, list_query: Query<ListQuery>, storage: Extension<Arc<Storage>>,
+) -> Result<Json<Page<Alert>>, V1Error> {
+    let Json(page) =
+        handlers::get_alerts(Query(query.0.into()), Query(list_query.0.into()), storage).await?;
+    Ok(Json(Page::from_page(page)))
+}
@@ -0,0 +1,48 @@
+use axum::{
+    routing::{delete, get, post, MethodRouter},
+    Router,
+};
+
+use crate::api::v1::handlers::{
+    create_webhook, delete_webhook, get_account, get_account_snapshots, get_account_transactions,
+    get_accounts, get_alerts, get_block, get_block_transactions, get_cache_metrics, get_mint,
+    get_program_accounts, get_rpc_endpoints, get_rpc_metrics, get_token_portfolio, get_transaction,
+    get_webhook_dead_letters, get_webhook_deliveries, get_webhooks, search_transactions,
+};
+
+/// Paths of the v1 API, relative to `/api/v1`, with their handlers.
+pub fn routes() -> Vec<(&'static str, MethodRouter)> {
+    vec![
+        ("/transactions/search", get(search_transactions)),
+        ("/transactions/:signature", get(get_transaction)),
+        ("/blocks/:slot", get(get_block)),
+        ("/blocks/:slot/transactions", get(get_block_transactions)),
+        ("/accounts", post(get_accounts)),
+        ("/accounts/:pubkey", get(get_account)),
+        (
+            "/accounts/:pubkey/transactions",
+            get(get_account_transactions),
+        ),
+        ("/accounts/:pubkey/tokens", get(get_token_portfolio)),
+        ("/accounts/:pubkey/snapshots", get(get_account_snapshots)),
+        ("/programs/:program_id/accounts", get(get_program_accounts)),
+        ("/tokens/:mint", get(get_mint)),
+        ("/metrics/rpc", get(get_rpc_metrics)),
+        ("/metrics/cache", get(get_cache_metrics)),
+        ("/rpc/endpoints", get(get_rpc_endpoints)),
+        ("/alerts", get(get_alerts)),
+        ("/webhooks", get(get_webhooks).post(create_webhook)),
+        ("/webhooks/:id", delete(delete_webhook)),
+        ("/webhooks/:id/deliveries", get(get_webhook_deliveries)),
+        ("/webhooks/:id/dead-letters", get(get_webhook_dead_letters)),
+    ]
+}
+
+/// Routes nested under `/api/v1`, relying on the extensions layered by `create_router`.
+pub fn create_router() -> Router {
+    routes()
+        .into_iter()
+        .fold(Router::new(), |router, (path, method_router)| {
+            router.route(path, method_router)
+        })
+}
@@ -0,0 +1,464 @@
+use crate::{
+    api::handlers::CommitmentQuery,
+    data_processing::{
+        processor::{AccountData, TransactionData},
+        Processor, TokenRegistry,
+    },
+    data_retrieval::{
+        stream_hub::{StreamHub, Topic},
+        RetrievalError, RpcSolanaClient,
+    },
+};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query,
+    },
+    response::Response,
+    Extension,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_account_decoder::UiAccount;
+use solana_client::rpc_response::{Response as RpcResponse, RpcKeyedAccount, SlotInfo};
+use solana_sdk::{clock::Slot, commitment_config::CommitmentConfig};
+use std::{collections::HashMap, sync::Arc};
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc},
+    task::JoinHandle,
+};
+use tracing::{error, warn};
+
+/// Messages queued for one client before its subscriptions wait for the socket.
+const OUTGOING_CAPACITY: usize = 256;
+/// Subscriptions one client can hold at once.
+const MAX_SUBSCRIPTIONS: usize = 100;
+
+/// Data a client can subscribe to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(tag = "channel", rename_all = "snake_case")]
+pub enum Subscription {
+    Account {
+        pubkey: String,
+    },
+    Program {
+        program_id: String,
+    },
+    /// Delivers the transaction once it reaches the commitment, at least `confirmed`, then ends.
+    Signature {
+        signature: String,
+    },
+    Slot,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Operation {
+    Subscribe,
+    Unsubscribe,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientMessage {
+    op:           Operation,
+    #[serde(flatten)]
+    subscription: Subscription,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Subscribed {
+        subscription: Subscription,
+    },
+    Unsubscribed {
+        subscription: Subscription,
+    },
+    Error {
+        message:      String,
+        subscription: Option<Subscription>,
+    },
+    /// Updates of the subscription were dropped because the client read too slowly.
+    Lagged {
+        subscription: Subscription,
+        skipped:      u64,
+    },
+    Account {
+        pubkey:  String,
+        account: AccountData,
+    },
+    ProgramAccount {
+        program_id: String,
+        pubkey:     String,
+        account:    AccountData,
+    },
+    Transaction {
+        signature:   String,
+        transaction: TransactionData,
+    },
+    Slot {
+        slot:   Slot,
+        parent: Slot,
+        root:   Slot,
+    },
+}
+
+impl ServerMessage {
+    fn error(subscription: Option<Subscription>, message: impl Into<String>) -> Self {
+        ServerMessage::Error {
+            message: message.into(),
+            subscription,
+        }
+    }
+}
+
+/// Upgrades to a websocket on which the client subscribes to processed accounts, program
+/// accounts, transactions and slots.
+#[utoipa::path(
+    get,
+    path = "/api/ws",
+    tag = "streams",
+    params(CommitmentQuery),
+    responses(
+        (status = 101, description = "Switched to the WebSocket protocol")
+    )
+)]
+pub async fn websocket(
+    ws: WebSocketUpgrade, Query(query): Query<CommitmentQuery>,
+    Extension(rpc_solana_client): Extension<Arc<RpcSolanaClient>>,
+    Extension(processor): Extension<Arc<Processor>>,
+    Extension(token_registry): Extension<Arc<TokenRegistry>>,
+    Extension(stream_hub): Extension<Arc<StreamHub>>,
+) -> Response {
+    let session = Arc::new(Session {
+        commitment: rpc_solana_client.commitment(query.commitment),
+        rpc_solana_client,
+        processor,
+        token_registry,
+        stream_hub,
+    });
+    ws.on_upgrade(move |socket| session.run(socket))
+}
+
+/// One websocket client. Its subscriptions listen to the shared upstream subscriptions of the
+/// stream hub, so any number of clients only need one upstream subscription per topic.
+struct Session {
+    rpc_solana_client: Arc<RpcSolanaClient>,
+    processor:         Arc<Processor>,
+    token_registry:    Arc<TokenRegistry>,
+    stream_hub:        Arc<StreamHub>,
+    commitment:        CommitmentConfig,
+}
+
+impl Session {
+    async fn run(self: Arc<Self>, mut socket: WebSocket) {
+        let (outgoing, mut outgoing_receiver) = mpsc::channel(OUTGOING_CAPACITY);
+        let mut subscriptions: HashMap<Subscription, JoinHandle<()>> = HashMap::new();
+
+        loop {
+            let message = tokio::select! {
+                message = outgoing_receiver.recv() => match message {
+                    Some(message) => message,
+                    None => break,
+                },
+                incoming = socket.recv() => match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        self.handle(&text, &mut subscriptions, &outgoing)
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                },
+            };
+
+            let text = match serde_json::to_string(&message) {
+                Ok(text) => text,
+                Err(e) => {
+                    error!("Failed to encode websocket message: {}", e);
+                    continue;
+                }
+            };
+            if socket.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+
+        for (_, task) in subscriptions {
+            task.abort();
+        }
+    }
+
+    fn handle(
+        self: &Arc<Self>, text: &str, subscriptions: &mut HashMap<Subscription, JoinHandle<()>>,
+        outgoing: &mpsc::Sender<ServerMessage>,
+    ) -> ServerMessage {
+        let request: ClientMessage = match serde_json::from_str(text) {
+            Ok(request) => request,
+            Err(e) => return ServerMessage::error(None, format!("invalid message: {}", e)),
+        };
+        let subscription = request.subscription;
+
+        match request.op {
+            Operation::Subscribe => {
+                // Finished signature subscriptions can be subscribed to again.
+                if subscriptions
+                    .get(&subscription)
+                    .is_some_and(|task| !task.is_finished())
+                {
+                    return ServerMessage::error(Some(subscription), "already subscribed");
+                }
+                subscriptions.retain(|_, task| !task.is_finished());
+                if subscriptions.len() >= MAX_SUBSCRIPTIONS {
+                    return ServerMessage::error(
+                        Some(subscription),
+                        format!("at most {} subscriptions per connection", MAX_SUBSCRIPTIONS),
+                    );
+                }
+                match self.subscribe(subscription.clone(), outgoing.clone()) {
+                    Ok(task) => {
+                        subscriptions.insert(subscription.clone(), task);
+                        ServerMessage::Subscribed { subscription }
+                    }
+                    Err(e) => ServerMessage::error(Some(subscription), e.to_string()),
+                }
+            }
+            Operation::Unsubscribe => match subscriptions.remove(&subscription) {
+                Some(task) => {
+                    task.abort();
+                    ServerMessage::Unsubscribed { subscription }
+                }
+                None => ServerMessage::error(Some(subscription), "not subscribed"),
+            },
+        }
+    }
+
+    /// Starts forwarding processed updates of the subscription to the client.
+    fn subscribe(
+        self: &Arc<Self>, subscription: Subscription, outgoing: mpsc::Sender<ServerMessage>,
+    ) -> Result<JoinHandle<()>, RetrievalError> {
+        let topic = match &subscription {
+            Subscription::Account { pubkey } => Topic::Account(pubkey.clone()),
+            Subscription::Program { program_id } => Topic::Program(program_id.clone()),
+            Subscription::Signature { signature } => Topic::Signature(signature.clone()),
+            Subscription::Slot => Topic::Slot,
+        };
+        let commitment = self.commitment_of(&subscription);
+        let (_, mut receiver) = self.stream_hub.subscribe(topic, commitment, None)?;
+
+        let session = Arc::clone(self);
+        Ok(tokio::spawn(async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Skipped {} updates of {:?}", skipped, subscription);
+                        let lagged = ServerMessage::Lagged {
+                            subscription: subscription.clone(),
+                            skipped,
+                        };
+                        if outgoing.send(lagged).await.is_err() {
+                            return;
+                        }
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+
+                let message = match session.process(&subscription, event.data).await {
+                    Ok(message) => message,
+                    Err(e) => Some(ServerMessage::error(
+                        Some(subscription.clone()),
+                        e.to_string(),
+                    )),
+                };
+                let sent = match message {
+                    Some(message) => outgoing.send(message).await.is_ok(),
+                    None => true,
+                };
+                // A signature is only notified once.
+                if !sent || matches!(subscription, Subscription::Signature { .. }) {
+                    return;
+                }
+            }
+        }))
+    }
+
+    /// Commitment of the subscription, raised to `confirmed` for signatures since transactions
+    /// cannot be fetched at `processed`.
+    fn commitment_of(&self, subscription: &Subscription) -> CommitmentConfig {
+        match subscription {
+            Subscription::Signature { .. } if !self.commitment.is_at_least_confirmed() => {
+                CommitmentConfig::confirmed()
+            }
+            _ => self.commitment,
+        }
+    }
+
+    /// Turns an upstream notification into processed data, `None` if it cannot be processed.
+    async fn process(
+        &self, subscription: &Subscription, data: Value,
+    ) -> Result<Option<ServerMessage>, RetrievalError> {
+        match subscription {
+            Subscription::Account { pubkey } => {
+                let response: RpcResponse<UiAccount> = decode(data)?;
+                Ok(self
+                    .process_account(pubkey, &response.value, response.context.slot)
+                    .await
+                    .map(|account| ServerMessage::Account {
+                        pubkey: pubkey.clone(),
+                        account,
+                    }))
+            }
+            Subscription::Program { program_id } => {
+                let response: RpcResponse<RpcKeyedAccount> = decode(data)?;
+                let pubkey = response.value.pubkey;
+                Ok(self
+                    .process_account(&pubkey, &response.value.account, response.context.slot)
+                    .await
+                    .map(|account| ServerMessage::ProgramAccount {
+                        program_id: program_id.clone(),
+                        pubkey,
+                        account,
+                    }))
+            }
+            Subscription::Signature { signature } => {
+                let encoded_transaction = self
+                    .rpc_solana_client
+                    .get_transaction(signature, self.commitment_of(subscription))
+                    .await?;
+                let Some(mut transaction) = self.processor.process_transaction(encoded_transaction)
+                else {
+                    return Ok(None);
+                };
+                self.token_registry
+                    .enrich_transaction(&mut transaction)
+                    .await;
+                Ok(Some(ServerMessage::Transaction {
+                    signature: signature.clone(),
+                    transaction,
+                }))
+            }
+            Subscription::Slot => {
+                let slot_info: SlotInfo = decode(data)?;
+                Ok(Some(ServerMessage::Slot {
+                    slot:   slot_info.slot,
+                    parent: slot_info.parent,
+                    root:   slot_info.root,
+                }))
+            }
+        }
+    }
+
+    async fn process_account(
+        &self, pubkey: &str, ui_account: &UiAccount, context_slot: Slot,
+    ) -> Option<AccountData> {
+        let mut account_data = self.processor.process_account(
+            ui_account.decode()?,
+            context_slot,
+            Utc::now().timestamp(),
+        )?;
+        self.token_registry
+            .enrich_account(pubkey, &mut account_data)
+            .await;
+        Some(account_data)
+    }
+}
+
+fn decode<T: serde::de::DeserializeOwned>(data: Value) -> Result<T, RetrievalError> {
+    serde_json::from_value(data)
+        .map_err(|e| RetrievalError::decode_failure(format!("subscription update: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data_storage::Storage, test_fixtures::Services};
+    use axum::{routing::get, Router};
+    use futures::{SinkExt, StreamExt};
+    use serde_json::json;
+    use tokio_tungstenite::{connect_async, tungstenite};
+
+    #[test]
+    fn test_client_message() {
+        let request: ClientMessage = serde_json::from_str(
+            r#"{"op": "subscribe", "channel": "program", "program_id": "Prog"}"#,
+        )
+        .unwrap();
+
+        assert!(matches!(request.op, Operation::Subscribe));
+        assert_eq!(
+            request.subscription,
+            Subscription::Program {
+                program_id: "Prog".to_string(),
+            }
+        );
+        assert!(serde_json::from_str::<ClientMessage>(r#"{"op": "unsubscribe"}"#).is_err());
+    }
+
+    #[test]
+    fn test_server_message() {
+        let message = ServerMessage::Subscribed {
+            subscription: Subscription::Slot,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({"type": "subscribed", "subscription": {"channel": "slot"}})
+        );
+
+        let message = ServerMessage::Lagged {
+            subscription: Subscription::Slot,
+            skipped:      3,
+        };
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            serde_json::json!({"type": "lagged", "subscription": {"channel": "slot"}, "skipped": 3})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_websocket_subscriptions() {
+        let services = Services::new(Arc::new(Storage::default()));
+        let app = Router::new()
+            .route("/api/ws", get(websocket))
+            .layer(Extension(services.rpc_solana_client))
+            .layer(Extension(Arc::new(Processor)))
+            .layer(Extension(services.token_registry))
+            .layer(Extension(services.stream_hub));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/api/ws", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let (mut socket, _) = connect_async(url).await.unwrap();
+        for request in [
+            json!({"op": "subscribe", "channel": "slot"}),
+            json!({"op": "subscribe", "channel": "slot"}),
+            json!({"op": "unsubscribe", "channel": "slot"}),
+            json!({"op": "subscribe", "channel": "account", "pubkey": "invalid"}),
+        ] {
+            let request = tungstenite::Message::Text(request.to_string());
+            socket.send(request).await.unwrap();
+        }
+
+        let mut responses = vec![];
+        for _ in 0 .. 4 {
+            let response = socket.next().await.unwrap().unwrap();
+            responses.push(serde_json::from_str::<Value>(response.to_text().unwrap()).unwrap());
+        }
+        let slot = json!({"channel": "slot"});
+        assert_eq!(
+            responses[0],
+            json!({"type": "subscribed", "subscription": slot})
+        );
+        assert_eq!(
+            responses[1],
+            json!({"type": "error", "message": "already subscribed", "subscription": slot})
+        );
+        assert_eq!(
+            responses[2],
+            json!({"type": "unsubscribed", "subscription": slot})
+        );
+        assert_eq!(responses[3]["type"], "error");
+        assert_eq!(responses[3]["subscription"]["pubkey"], "invalid");
+    }
+}
@@ -1,5 +1,8 @@
-use serde::Deserialize;
-use std::{error::Error, fs};
+use crate::{alerts::rule::AlertRule, data_retrieval::error::RetrievalErrorKind};
+use serde::{Deserialize, Serialize};
+use solana_sdk::commitment_config::CommitmentLevel;
+use std::{collections::HashMap, error::Error, fs, path::PathBuf};
+use utoipa::ToSchema;
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
@@ -10,14 +13,329 @@ pub struct Config {
     pub transaction_signature: String,
     pub account_pubkey:        String,
     pub port:                  String,
+    /// Commitment of RPC reads and subscriptions when a request does not ask for one.
+    #[serde(default = "default_commitment")]
+    pub commitment:            CommitmentLevel,
+    #[serde(default)]
+    pub retry:                 RetryConfig,
+    /// Additional providers; when empty `solana_rpc_url` and `solana_ws_url` are the only endpoint.
+    #[serde(default)]
+    pub rpc_endpoints:         Vec<EndpointConfig>,
+    #[serde(default)]
+    pub pool:                  PoolConfig,
+    #[serde(default)]
+    pub rate_limit:            RateLimitConfig,
+    #[serde(default)]
+    pub cache:                 CacheConfig,
+    #[serde(default)]
+    pub storage:               StorageConfig,
+    #[serde(default)]
+    pub stream:                StreamConfig,
+    #[serde(default)]
+    pub webhooks:              WebhookConfig,
+    #[serde(default)]
+    pub alerts:                AlertsConfig,
+    #[serde(default)]
+    pub health:                HealthConfig,
+    #[serde(default)]
+    pub docs:                  DocsConfig,
+}
+
+/// One RPC provider of the endpoint pool.
+#[derive(Deserialize, Debug, Clone)]
+pub struct EndpointConfig {
+    pub rpc_url:             String,
+    pub ws_url:              Option<String>,
+    /// Share of the requests routed to this endpoint relative to the other healthy ones.
+    #[serde(default = "default_weight")]
+    pub weight:              u32,
+    /// Credits refilled per second, unlimited when absent.
+    pub requests_per_second: Option<f64>,
+    /// Credits that can be spent at once, defaults to `requests_per_second`.
+    pub burst:               Option<f64>,
+    /// Credits allowed per calendar month (UTC), unlimited when absent.
+    pub monthly_credits:     Option<u64>,
+    /// Combines concurrent requests into JSON-RPC batch calls, for providers that support them.
+    pub batching:            Option<BatchConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BatchConfig {
+    /// How long the first request of a batch waits for others to join it.
+    pub window_ms: u64,
+    pub max_size:  usize,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn default_commitment() -> CommitmentLevel {
+    CommitmentLevel::Confirmed
+}
+
+/// Health checking of the endpoint pool.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PoolConfig {
+    pub health_check_interval_secs: u64,
+    /// Slots an endpoint may trail the most advanced one before it stops receiving requests.
+    pub max_slot_lag:               u64,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            health_check_interval_secs: 10,
+            max_slot_lag:               50,
+        }
+    }
+}
+
+/// Cost of RPC methods against the endpoint budgets.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Longest time a call waits for credits before failing with `rate_limited`.
+    pub acquire_timeout_ms: u64,
+    pub default_cost:       u32,
+    /// Credits charged per call, keyed by RPC method name.
+    pub method_costs:       HashMap<String, u32>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            acquire_timeout_ms: 5000,
+            default_cost:       1,
+            method_costs:       HashMap::from([
+                ("getBlock".to_string(), 10),
+                ("getProgramAccounts".to_string(), 10),
+                ("getSignaturesForAddress".to_string(), 5),
+                ("getTokenAccountsByOwner".to_string(), 5),
+            ]),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn cost(&self, method: &str) -> u32 {
+        self.method_costs
+            .get(method)
+            .copied()
+            .unwrap_or(self.default_cost)
+    }
+}
+
+/// Bounds of the in-memory storage, the oldest entries being evicted first.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Transactions kept along with their account, program and slot indexes.
+    pub max_transactions: usize,
+    /// Block headers kept, those of the lowest slots being evicted first.
+    pub max_blocks:       usize,
+    /// Account snapshots kept across all accounts, the oldest being evicted first.
+    pub max_snapshots:    usize,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            max_transactions: 100_000,
+            max_blocks:       10_000,
+            max_snapshots:    100_000,
+        }
+    }
+}
+
+/// Response cache of the RPC client. Finalized transactions and blocks never expire.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Entries kept before the least recently used ones are evicted, zero disables the cache.
+    pub max_entries:        usize,
+    /// Bytes of serialized responses kept before the least recently used ones are evicted, zero
+    /// disables the cache.
+    pub max_bytes:          usize,
+    /// Lifetime of account state, also dropped when an account subscription reports a change.
+    pub account_ttl_ms:     u64,
+    /// Lifetime of transactions that are confirmed but not yet finalized.
+    pub confirmed_ttl_secs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            max_entries:        10_000,
+            max_bytes:          256 * 1024 * 1024,
+            account_ttl_ms:     2_000,
+            confirmed_ttl_secs: 30,
+        }
+    }
+}
+
+/// Server-sent event streams of subscription updates.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct StreamConfig {
+    /// Recent updates kept per subscription for clients resuming with `Last-Event-ID`.
+    pub buffer_size:    usize,
+    /// Interval of the heartbeat events sent while no update arrives.
+    pub heartbeat_secs: u64,
+    /// Subscriptions open at once, each holding a thread and an upstream websocket subscription.
+    pub max_channels:   usize,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        StreamConfig {
+            buffer_size:    100,
+            heartbeat_secs: 15,
+            max_channels:   100,
+        }
+    }
+}
+
+/// Delivery of processed transactions to registered webhooks.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// Total number of attempts before a payload is dead-lettered, including the first one.
+    pub max_attempts:              u32,
+    pub base_delay_ms:             u64,
+    pub max_delay_ms:              u64,
+    /// Time a webhook has to answer an attempt.
+    pub timeout_ms:                u64,
+    /// Deliveries kept per webhook in the delivery history.
+    pub history_size:              usize,
+    /// Dead letters kept per webhook, the oldest dropped first.
+    pub dead_letter_size:          usize,
+    /// Deliveries in progress at once, including their retries.
+    pub max_concurrent_deliveries: usize,
+    /// Webhooks that can be registered at once.
+    pub max_webhooks:              usize,
+    /// Hosts webhooks may be delivered to even though they are or resolve to non-public addresses.
+    pub allowed_hosts:             Vec<String>,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        WebhookConfig {
+            max_attempts:              5,
+            base_delay_ms:             500,
+            max_delay_ms:              60_000,
+            timeout_ms:                10_000,
+            history_size:              100,
+            dead_letter_size:          100,
+            max_concurrent_deliveries: 64,
+            max_webhooks:              100,
+            allowed_hosts:             Vec::new(),
+        }
+    }
+}
+
+/// Alert rules evaluated against processed transactions and account updates.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AlertsConfig {
+    /// Alerts kept in the table served by `/api/alerts`.
+    pub history_size: usize,
+    pub rules:        Vec<AlertRule>,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        AlertsConfig {
+            history_size: 1000,
+            rules:        vec![],
+        }
+    }
+}
+
+/// Checks of the `/ready` endpoint.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, ToSchema)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// Slots the last processed slot may trail the chain before the service stops being ready,
+    /// unchecked when absent.
+    pub max_ingestion_lag: Option<u64>,
+}
+
+/// API documentation served next to the OpenAPI document.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DocsConfig {
+    /// Directory holding `swagger-ui.css` and `swagger-ui-bundle.js` of `swagger-ui-dist`.
+    pub swagger_ui_dir: PathBuf,
+}
+
+impl Default for DocsConfig {
+    fn default() -> Self {
+        DocsConfig {
+            swagger_ui_dir: PathBuf::from("assets/swagger-ui"),
+        }
+    }
+}
+
+/// Retry policy applied to every RPC call.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Total number of attempts, including the first one.
+    pub max_attempts:        u32,
+    pub base_delay_ms:       u64,
+    pub max_delay_ms:        u64,
+    /// Randomizes each delay between zero and the computed backoff.
+    pub jitter:              bool,
+    pub retryable_errors:    Vec<RetrievalErrorKind>,
+    /// Waits for the provider's `Retry-After` header instead of the computed backoff, still capped
+    /// at `max_delay_ms`.
+    pub respect_retry_after: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts:        3,
+            base_delay_ms:       200,
+            max_delay_ms:        5000,
+            jitter:              true,
+            retryable_errors:    vec![
+                RetrievalErrorKind::RateLimited,
+                RetrievalErrorKind::UpstreamTimeout,
+                RetrievalErrorKind::UpstreamError,
+            ],
+            respect_retry_after: true,
+        }
+    }
 }
 
 impl Config {
     pub fn from_file(file_path: &str) -> Result<Self, Box<dyn Error>> {
         let config_string = fs::read_to_string(file_path)?;
         let config: Config = toml::from_str(&config_string)?;
+        for rule in &config.alerts.rules {
+            rule.validate()?;
+        }
         Ok(config)
     }
+
+    /// Endpoints of the RPC pool, falling back to the single configured provider.
+    pub fn endpoints(&self) -> Vec<EndpointConfig> {
+        if !self.rpc_endpoints.is_empty() {
+            return self.rpc_endpoints.clone();
+        }
+        vec![EndpointConfig {
+            rpc_url:             self.solana_rpc_url.clone(),
+            ws_url:              Some(self.solana_ws_url.clone()),
+            weight:              default_weight(),
+            requests_per_second: None,
+            burst:               None,
+            monthly_credits:     None,
+            batching:            None,
+        }]
+    }
 }
 
 #[cfg(test)]
@@ -55,6 +373,146 @@ mod tests {
         assert_eq!(config.transaction_signature, "5N7k3w3Asd5Lk2D8");
         assert_eq!(config.account_pubkey, "6k3MnB5z3Q2N7E");
         assert_eq!(config.port, "8080");
+        assert_eq!(config.retry.max_attempts, 3);
+        assert_eq!(config.commitment, CommitmentLevel::Confirmed);
+        assert_eq!(config.webhooks.max_attempts, 5);
+        assert!(config.health.max_ingestion_lag.is_none());
+        assert_eq!(config.endpoints().len(), 1);
+        assert_eq!(
+            config.endpoints()[0].rpc_url,
+            "https://api.devnet.solana.com"
+        );
+    }
+
+    #[test]
+    fn test_config_from_file_endpoints() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let toml_content = r#"
+            solana_rpc_url = "https://api.devnet.solana.com"
+            solana_ws_url = "wss://api.devnet.solana.com"
+            api_bind_address = "127.0.0.1:8080"
+            transaction_signature = "5N7k3w3Asd5Lk2D8"
+            account_pubkey = "6k3MnB5z3Q2N7E"
+            port = "8080"
+
+            [[rpc_endpoints]]
+            rpc_url = "https://primary.example.com"
+            ws_url = "wss://primary.example.com"
+            weight = 3
+
+            [[rpc_endpoints]]
+            rpc_url = "https://backup.example.com"
+            requests_per_second = 10
+            monthly_credits = 1000000
+
+            [rpc_endpoints.batching]
+            window_ms = 5
+            max_size = 20
+
+            [rate_limit.method_costs]
+            getBlock = 25
+        "#;
+
+        temp_file.write_all(toml_content.as_bytes()).unwrap();
+
+        let config = Config::from_file(temp_file.path().to_str().unwrap()).unwrap();
+        let endpoints = config.endpoints();
+
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[0].weight, 3);
+        assert_eq!(endpoints[1].weight, 1);
+        assert!(endpoints[1].ws_url.is_none());
+        assert_eq!(config.pool.max_slot_lag, 50);
+        assert_eq!(endpoints[1].requests_per_second, Some(10.0));
+        assert!(endpoints[0].batching.is_none());
+        assert_eq!(endpoints[1].batching.as_ref().unwrap().max_size, 20);
+        assert_eq!(config.rate_limit.cost("getBlock"), 25);
+        assert_eq!(config.rate_limit.cost("getAccountInfo"), 1);
+    }
+
+    #[test]
+    fn test_config_from_file_retry() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let toml_content = r#"
+            solana_rpc_url = "https://api.devnet.solana.com"
+            solana_ws_url = "wss://api.devnet.solana.com"
+            api_bind_address = "127.0.0.1:8080"
+            transaction_signature = "5N7k3w3Asd5Lk2D8"
+            account_pubkey = "6k3MnB5z3Q2N7E"
+            port = "8080"
+            commitment = "finalized"
+
+            [retry]
+            max_attempts = 5
+            retryable_errors = ["rate_limited"]
+        "#;
+
+        temp_file.write_all(toml_content.as_bytes()).unwrap();
+
+        let config = Config::from_file(temp_file.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(config.commitment, CommitmentLevel::Finalized);
+        assert_eq!(config.retry.max_attempts, 5);
+        assert_eq!(config.retry.base_delay_ms, 200);
+        assert_eq!(
+            config.retry.retryable_errors,
+            vec![RetrievalErrorKind::RateLimited]
+        );
+    }
+
+    #[test]
+    fn test_config_from_file_alert_rules() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let toml_content = r#"
+            solana_rpc_url = "https://api.devnet.solana.com"
+            solana_ws_url = "wss://api.devnet.solana.com"
+            api_bind_address = "127.0.0.1:8080"
+            transaction_signature = "5N7k3w3Asd5Lk2D8"
+            account_pubkey = "6k3MnB5z3Q2N7E"
+            port = "8080"
+
+            [[alerts.rules]]
+            name = "program failures"
+            severity = "critical"
+            debounce_secs = 300
+            condition = { type = "failed_transaction", program_id = "11111111111111111111111111111111" }
+            sinks = [{ type = "webhook", url = "https://example.com/alerts" }]
+        "#;
+
+        temp_file.write_all(toml_content.as_bytes()).unwrap();
+
+        let config = Config::from_file(temp_file.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(config.alerts.history_size, 1000);
+        assert_eq!(config.alerts.rules.len(), 1);
+        assert_eq!(config.alerts.rules[0].name, "program failures");
+        assert_eq!(config.alerts.rules[0].debounce_secs, 300);
+    }
+
+    #[test]
+    fn test_config_from_file_invalid_alert_rule() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let toml_content = r#"
+            solana_rpc_url = "https://api.devnet.solana.com"
+            solana_ws_url = "wss://api.devnet.solana.com"
+            api_bind_address = "127.0.0.1:8080"
+            transaction_signature = "5N7k3w3Asd5Lk2D8"
+            account_pubkey = "6k3MnB5z3Q2N7E"
+            port = "8080"
+
+            [[alerts.rules]]
+            name = "low balance"
+            condition = { type = "low_balance", account = "not-a-pubkey", min_sol = 1.0 }
+        "#;
+
+        temp_file.write_all(toml_content.as_bytes()).unwrap();
+
+        let result = Config::from_file(temp_file.path().to_str().unwrap());
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("alert rule low balance"));
     }
 
     #[test]
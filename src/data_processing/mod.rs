@@ -1,3 +1,6 @@
 pub mod processor;
+pub mod token;
+pub mod token_registry;
 
 pub use processor::Processor;
+pub use token_registry::TokenRegistry;
@@ -1,3 +1,4 @@
+use crate::data_processing::token::{parse_token_account, MintInfo, TokenAccountData};
 use serde::{Deserialize, Serialize};
 use solana_sdk::{
     account::Account as SolanaAccount,
@@ -5,51 +6,197 @@ use solana_sdk::{
 };
 use solana_transaction_status::{
     EncodedConfirmedBlock, EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
-    UiInstruction::Parsed, UiMessage, UiParsedInstruction::PartiallyDecoded, UiParsedMessage,
-    UiRawMessage,
+    EncodedTransactionWithStatusMeta, UiInstruction::Parsed, UiMessage, UiParsedInstruction,
+    UiParsedInstruction::PartiallyDecoded, UiParsedMessage, UiRawMessage, UiTransactionStatusMeta,
+    UiTransactionTokenBalance,
 };
+use std::collections::BTreeMap;
+use tracing::debug;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct TransactionData {
-    signatures:   Vec<String>,
-    blockhash:    String,
-    accounts:     Vec<Account>,
-    instructions: Vec<InstructionData>,
+    signatures:         Vec<String>,
+    blockhash:          String,
+    accounts:           Vec<Account>,
+    instructions:       Vec<InstructionData>,
+    pub token_balances: Vec<TokenBalanceChange>,
+    #[schema(value_type = Option<u64>)]
+    pub slot:           Option<Slot>,
+    #[schema(value_type = Option<i64>)]
+    pub block_time:     Option<UnixTimestamp>,
+    /// Why the transaction failed, absent when it succeeded or the status meta is missing.
+    #[serde(default)]
+    pub error:          Option<String>,
+    /// Lamports paid for the transaction, absent when the status meta is missing.
+    #[serde(default)]
+    pub fee:            Option<u64>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct Account {
-    pubkey:   String,
-    writable: bool,
-    signer:   bool,
+    pubkey:         String,
+    writable:       bool,
+    signer:         bool,
+    /// Lamports gained, negative when lost, zero when the status meta is missing.
+    #[serde(default)]
+    lamport_change: i64,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct InstructionData {
-    program_id: String,
-    data:       String,
+    program_id:       String,
+    /// Base58 encoded data, empty for instructions parsed by the node.
+    data:             String,
+    /// Type of instructions parsed by the node, e.g. `transfer`.
+    #[serde(default)]
+    instruction_type: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+impl Account {
+    pub fn pubkey(&self) -> &str {
+        &self.pubkey
+    }
+
+    pub fn writable(&self) -> bool {
+        self.writable
+    }
+
+    pub fn signer(&self) -> bool {
+        self.signer
+    }
+
+    pub fn lamport_change(&self) -> i64 {
+        self.lamport_change
+    }
+}
+
+impl InstructionData {
+    pub fn program_id(&self) -> &str {
+        &self.program_id
+    }
+
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    pub fn instruction_type(&self) -> Option<&str> {
+        self.instruction_type.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct TokenBalanceChange {
+    pub account:     String,
+    pub mint:        String,
+    pub owner:       Option<String>,
+    pub program_id:  Option<String>,
+    pub decimals:    u8,
+    pub pre_amount:  Option<String>,
+    pub post_amount: Option<String>,
+    pub name:        Option<String>,
+    pub symbol:      Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct AccountData {
-    pub lamports:   u64,
-    pub data:       Vec<u8>,
-    pub owner:      String,
-    pub executable: bool,
-    pub rent_epoch: u64,
+    pub lamports:      u64,
+    pub data:          Vec<u8>,
+    pub owner:         String,
+    pub executable:    bool,
+    pub rent_epoch:    u64,
+    pub token_account: Option<TokenAccountData>,
+    pub mint:          Option<MintInfo>,
+    /// Slot of the RPC response the account was read from.
+    #[schema(value_type = u64)]
+    pub context_slot:  Slot,
+    /// Unix timestamp at which the account was fetched.
+    pub fetched_at:    i64,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct BlockData {
     pub previous_blockhash: String,
     pub blockhash:          String,
+    #[schema(value_type = u64)]
     pub parent_slot:        Slot,
     pub transactions:       Vec<Option<TransactionData>>,
     pub num_partitions:     Option<u64>,
+    #[schema(value_type = Option<i64>)]
     pub block_time:         Option<UnixTimestamp>,
     pub block_height:       Option<u64>,
 }
 
+impl TransactionData {
+    /// The first signature, which identifies the transaction.
+    pub fn signature(&self) -> Option<&str> {
+        self.signatures.first().map(String::as_str)
+    }
+
+    pub fn signatures(&self) -> &[String] {
+        &self.signatures
+    }
+
+    pub fn blockhash(&self) -> &str {
+        &self.blockhash
+    }
+
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    pub fn instructions(&self) -> &[InstructionData] {
+        &self.instructions
+    }
+
+    /// Keys of the accounts of the transaction, including signers and programs.
+    pub fn account_keys(&self) -> impl Iterator<Item = &str> {
+        self.accounts.iter().map(|account| account.pubkey.as_str())
+    }
+
+    /// Programs invoked by the top-level instructions.
+    pub fn program_ids(&self) -> impl Iterator<Item = &str> {
+        self.instructions
+            .iter()
+            .map(|instruction| instruction.program_id.as_str())
+    }
+
+    pub fn is_signer(&self, address: &str) -> bool {
+        self.accounts
+            .iter()
+            .any(|account| account.signer && account.pubkey == address)
+    }
+
+    /// Whether the address is one of the accounts of the transaction, including its programs.
+    pub fn involves(&self, address: &str) -> bool {
+        self.accounts
+            .iter()
+            .any(|account| account.pubkey == address)
+    }
+
+    pub fn lamport_change(&self, address: &str) -> i64 {
+        self.accounts
+            .iter()
+            .find(|account| account.pubkey == address)
+            .map_or(0, |account| account.lamport_change)
+    }
+
+    /// Largest gain or loss of lamports among the accounts.
+    pub fn largest_lamport_change(&self) -> u64 {
+        self.accounts
+            .iter()
+            .map(|account| account.lamport_change.unsigned_abs())
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub fn has_instruction_type(&self, instruction_type: &str) -> bool {
+        self.instructions
+            .iter()
+            .any(|instruction| instruction.instruction_type.as_deref() == Some(instruction_type))
+    }
+}
+
 pub struct Processor;
 
 impl Processor {
@@ -69,23 +216,31 @@ impl Processor {
             .account_keys
             .iter()
             .map(|account| Account {
-                pubkey:   account.pubkey.clone(),
-                writable: account.writable,
-                signer:   account.signer,
+                pubkey:         account.pubkey.clone(),
+                writable:       account.writable,
+                signer:         account.signer,
+                lamport_change: 0,
             })
             .collect();
 
         let instructions = parsed_message
             .instructions
             .iter()
-            .filter_map(|instruction| {
-                if let Parsed(PartiallyDecoded(instruction_detail)) = instruction {
-                    Some(InstructionData {
-                        program_id: instruction_detail.program_id.clone(),
-                        data:       instruction_detail.data.clone(),
-                    })
-                } else {
-                    println!("Unhandled instruction type");
+            .filter_map(|instruction| match instruction {
+                Parsed(PartiallyDecoded(instruction_detail)) => Some(InstructionData {
+                    program_id:       instruction_detail.program_id.clone(),
+                    data:             instruction_detail.data.clone(),
+                    instruction_type: None,
+                }),
+                Parsed(UiParsedInstruction::Parsed(instruction_detail)) => Some(InstructionData {
+                    program_id:       instruction_detail.program_id.clone(),
+                    data:             String::new(),
+                    instruction_type: instruction_detail.parsed["type"]
+                        .as_str()
+                        .map(str::to_string),
+                }),
+                _ => {
+                    debug!("Skipping compiled instruction of a parsed message");
                     None
                 }
             })
@@ -96,6 +251,11 @@ impl Processor {
             blockhash,
             accounts,
             instructions,
+            token_balances: vec![],
+            slot: None,
+            block_time: None,
+            error: None,
+            fee: None,
         })
     }
 
@@ -103,34 +263,98 @@ impl Processor {
         println!("Raw message data: {:?}", raw_message);
     }
 
-    pub fn process_transaction(
-        &self, vtx: EncodedConfirmedTransactionWithStatusMeta,
-    ) -> Option<TransactionData> {
-        let encoded_transaction = vtx.transaction.transaction;
-
-        match encoded_transaction {
-            EncodedTransaction::Json(ui_transaction) => {
-                let transaction_data = self.process_message(ui_transaction.message);
-                if let Some(mut data) = transaction_data {
-                    data.signatures = ui_transaction.signatures.clone();
-                    return Some(data);
-                }
-                None
+    fn process_token_balances(
+        &self, accounts: &[Account], meta: &UiTransactionStatusMeta,
+    ) -> Vec<TokenBalanceChange> {
+        let pre_balances: Option<Vec<UiTransactionTokenBalance>> =
+            meta.pre_token_balances.clone().into();
+        let post_balances: Option<Vec<UiTransactionTokenBalance>> =
+            meta.post_token_balances.clone().into();
+
+        let mut changes: BTreeMap<u8, TokenBalanceChange> = BTreeMap::new();
+        let balances = pre_balances
+            .unwrap_or_default()
+            .into_iter()
+            .map(|balance| (balance, true))
+            .chain(
+                post_balances
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|balance| (balance, false)),
+            );
+
+        for (balance, is_pre) in balances {
+            let change =
+                changes
+                    .entry(balance.account_index)
+                    .or_insert_with(|| TokenBalanceChange {
+                        account:     accounts
+                            .get(balance.account_index as usize)
+                            .map(|account| account.pubkey.clone())
+                            .unwrap_or_default(),
+                        mint:        balance.mint.clone(),
+                        owner:       balance.owner.clone().into(),
+                        program_id:  balance.program_id.clone().into(),
+                        decimals:    balance.ui_token_amount.decimals,
+                        pre_amount:  None,
+                        post_amount: None,
+                        name:        None,
+                        symbol:      None,
+                    });
+            if is_pre {
+                change.pre_amount = Some(balance.ui_token_amount.amount);
+            } else {
+                change.post_amount = Some(balance.ui_token_amount.amount);
             }
-            _ => {
-                println!("Unsupported transaction format.");
-                None
+        }
+
+        changes.into_values().collect()
+    }
+
+    fn process_transaction_with_meta(
+        &self, tx: EncodedTransactionWithStatusMeta,
+    ) -> Option<TransactionData> {
+        let mut transaction_data = self.process_encoded_transaction(tx.transaction)?;
+        if let Some(meta) = &tx.meta {
+            transaction_data.token_balances =
+                self.process_token_balances(&transaction_data.accounts, meta);
+            transaction_data.error = meta.err.as_ref().map(ToString::to_string);
+            transaction_data.fee = Some(meta.fee);
+            for (account, (pre, post)) in transaction_data
+                .accounts
+                .iter_mut()
+                .zip(meta.pre_balances.iter().zip(&meta.post_balances))
+            {
+                account.lamport_change = *post as i64 - *pre as i64;
             }
         }
+        Some(transaction_data)
+    }
+
+    pub fn process_transaction(
+        &self, vtx: EncodedConfirmedTransactionWithStatusMeta,
+    ) -> Option<TransactionData> {
+        let mut transaction_data = self.process_transaction_with_meta(vtx.transaction)?;
+        transaction_data.slot = Some(vtx.slot);
+        transaction_data.block_time = vtx.block_time;
+        Some(transaction_data)
     }
 
-    pub fn process_account(&self, account: SolanaAccount) -> Option<AccountData> {
+    /// Processes an account read at `context_slot` from a response that arrived at `fetched_at`.
+    pub fn process_account(
+        &self, account: SolanaAccount, context_slot: Slot, fetched_at: i64,
+    ) -> Option<AccountData> {
+        let token_account = parse_token_account(&account.owner, &account.data);
         Some(AccountData {
-            lamports:   account.lamports,
-            data:       account.data,
-            owner:      account.owner.to_string(),
+            lamports: account.lamports,
+            data: account.data,
+            owner: account.owner.to_string(),
             executable: account.executable,
             rent_epoch: account.rent_epoch,
+            token_account,
+            mint: None,
+            context_slot,
+            fetched_at,
         })
     }
 
@@ -150,7 +374,7 @@ impl Processor {
             }
         }
     }
-    pub fn process_block(&self, block: EncodedConfirmedBlock) -> Option<BlockData> {
+    pub fn process_block(&self, slot: Slot, block: EncodedConfirmedBlock) -> Option<BlockData> {
         Some(BlockData {
             previous_blockhash: block.previous_blockhash,
             blockhash:          block.blockhash,
@@ -158,7 +382,12 @@ impl Processor {
             transactions:       block
                 .transactions
                 .iter()
-                .map(|tx| self.process_encoded_transaction(tx.transaction.clone()))
+                .map(|tx| {
+                    let mut transaction_data = self.process_transaction_with_meta(tx.clone())?;
+                    transaction_data.slot = Some(slot);
+                    transaction_data.block_time = block.block_time;
+                    Some(transaction_data)
+                })
                 .collect(),
             num_partitions:     block.num_partitions,
             block_time:         block.block_time,
@@ -173,8 +402,7 @@ mod tests {
     use solana_sdk::pubkey::Pubkey;
     use solana_transaction_status::{
         EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
-        EncodedTransactionWithStatusMeta, UiMessage,
-        UiParsedMessage, UiTransaction,
+        EncodedTransactionWithStatusMeta, UiMessage, UiParsedMessage, UiTransaction,
     };
 
     #[test]
@@ -209,6 +437,72 @@ mod tests {
         assert_eq!(transaction_data.blockhash, "Blockhash");
     }
 
+    #[test]
+    fn test_process_transaction_token_balances() {
+        let processor = Processor;
+
+        let encoded_transaction: EncodedConfirmedTransactionWithStatusMeta =
+            serde_json::from_value(serde_json::json!({
+                "slot": 0,
+                "transaction": {
+                    "signatures": ["Signature1"],
+                    "message": {
+                        "accountKeys": [
+                            {"pubkey": "Payer", "writable": true, "signer": true, "source": "transaction"},
+                            {"pubkey": "TokenAccount", "writable": true, "signer": false, "source": "transaction"}
+                        ],
+                        "instructions": [{
+                            "program": "system",
+                            "programId": "11111111111111111111111111111111",
+                            "parsed": {"type": "transfer", "info": {}},
+                            "stackHeight": null
+                        }],
+                        "recentBlockhash": "Blockhash"
+                    }
+                },
+                "meta": {
+                    "err": null,
+                    "status": {"Ok": null},
+                    "fee": 5000,
+                    "preBalances": [10, 20],
+                    "postBalances": [5, 20],
+                    "preTokenBalances": [{
+                        "accountIndex": 1,
+                        "mint": "Mint",
+                        "uiTokenAmount": {"uiAmount": 1.0, "decimals": 6, "amount": "1000000", "uiAmountString": "1"}
+                    }],
+                    "postTokenBalances": [{
+                        "accountIndex": 1,
+                        "mint": "Mint",
+                        "uiTokenAmount": {"uiAmount": 2.0, "decimals": 6, "amount": "2000000", "uiAmountString": "2"}
+                    }]
+                },
+                "blockTime": null
+            }))
+            .unwrap();
+
+        let transaction_data = processor.process_transaction(encoded_transaction).unwrap();
+
+        assert_eq!(transaction_data.token_balances.len(), 1);
+        let balance = &transaction_data.token_balances[0];
+        assert_eq!(balance.account, "TokenAccount");
+        assert_eq!(balance.mint, "Mint");
+        assert_eq!(balance.decimals, 6);
+        assert_eq!(balance.pre_amount.as_deref(), Some("1000000"));
+        assert_eq!(balance.post_amount.as_deref(), Some("2000000"));
+        assert_eq!(transaction_data.lamport_change("Payer"), -5);
+        assert!(transaction_data.error.is_none());
+        assert_eq!(transaction_data.fee, Some(5000));
+        assert!(transaction_data.is_signer("Payer"));
+        assert_eq!(
+            transaction_data.program_ids().collect::<Vec<_>>(),
+            vec!["11111111111111111111111111111111"]
+        );
+        assert_eq!(transaction_data.largest_lamport_change(), 5);
+        assert!(transaction_data.has_instruction_type("transfer"));
+        assert!(!transaction_data.has_instruction_type("approve"));
+    }
+
     #[test]
     fn test_process_account() {
         let processor = Processor;
@@ -222,7 +516,7 @@ mod tests {
             rent_epoch: 0,
         };
 
-        let result = processor.process_account(solana_account);
+        let result = processor.process_account(solana_account, 42, 100);
 
         assert!(result.is_some());
         let account_data = result.unwrap();
@@ -230,6 +524,8 @@ mod tests {
         assert_eq!(account_data.data, vec![1, 2, 3]);
         assert_eq!(account_data.owner, pubkey.to_string());
         assert!(!account_data.executable);
+        assert_eq!(account_data.context_slot, 42);
+        assert_eq!(account_data.fetched_at, 100);
     }
 
     #[test]
@@ -247,7 +543,7 @@ mod tests {
             num_partitions:     None,
         };
 
-        let result = processor.process_block(block);
+        let result = processor.process_block(1, block);
 
         assert!(result.is_some());
         let block_data = result.unwrap();
@@ -257,6 +553,39 @@ mod tests {
         assert!(block_data.transactions.is_empty());
     }
 
+    #[test]
+    fn test_process_block_transactions() {
+        let processor = Processor;
+
+        let block: EncodedConfirmedBlock = serde_json::from_value(serde_json::json!({
+            "previousBlockhash": "PreviousBlockhash",
+            "blockhash": "Blockhash",
+            "parentSlot": 41,
+            "transactions": [{
+                "transaction": {
+                    "signatures": ["Signature1"],
+                    "message": {
+                        "accountKeys": [],
+                        "instructions": [],
+                        "recentBlockhash": "Blockhash"
+                    }
+                },
+                "meta": null
+            }],
+            "rewards": [],
+            "blockTime": 100,
+            "blockHeight": 40
+        }))
+        .unwrap();
+
+        let block_data = processor.process_block(42, block).unwrap();
+
+        let transaction_data = block_data.transactions[0].as_ref().unwrap();
+        assert_eq!(transaction_data.signature(), Some("Signature1"));
+        assert_eq!(transaction_data.slot, Some(42));
+        assert_eq!(transaction_data.block_time, Some(100));
+    }
+
     #[test]
     fn test_process_encoded_transaction_unsupported_format() {
         let processor = Processor;
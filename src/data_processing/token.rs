@@ -0,0 +1,374 @@
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::{
+    parse_token::{
+        get_token_account_mint, is_known_spl_token_id, parse_token_v2, real_number_string_trimmed,
+        TokenAccountType,
+    },
+    parse_token_extension::UiExtension,
+};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey, pubkey::Pubkey};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Program owning the Metaplex token metadata accounts.
+pub const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Account discriminator of a Metaplex `MetadataV1` account.
+const METADATA_V1_KEY: u8 = 4;
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct MintInfo {
+    pub mint:             String,
+    pub program_id:       String,
+    pub decimals:         u8,
+    pub supply:           String,
+    pub mint_authority:   Option<String>,
+    pub freeze_authority: Option<String>,
+    #[schema(value_type = Vec<Object>)]
+    pub extensions:       Vec<serde_json::Value>,
+    pub name:             Option<String>,
+    pub symbol:           Option<String>,
+    pub uri:              Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct TokenAccountData {
+    pub mint:             String,
+    pub owner:            String,
+    pub amount:           String,
+    pub decimals:         Option<u8>,
+    pub ui_amount_string: Option<String>,
+    pub name:             Option<String>,
+    pub symbol:           Option<String>,
+}
+
+/// Holdings of a wallet across all of its token accounts.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct Portfolio {
+    pub owner:    String,
+    pub lamports: u64,
+    pub sol:      String,
+    pub tokens:   Vec<PortfolioToken>,
+}
+
+/// Balance of a single mint, summed over every token account holding it.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct PortfolioToken {
+    pub mint:             String,
+    pub amount:           String,
+    pub decimals:         Option<u8>,
+    pub ui_amount_string: Option<String>,
+    pub name:             Option<String>,
+    pub symbol:           Option<String>,
+    pub token_accounts:   Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenMetadata {
+    pub name:   String,
+    pub symbol: String,
+    pub uri:    String,
+}
+
+impl MintInfo {
+    pub fn apply_metadata(&mut self, metadata: TokenMetadata) {
+        self.name = Some(metadata.name);
+        self.symbol = Some(metadata.symbol);
+        self.uri = Some(metadata.uri);
+    }
+}
+
+impl TokenAccountData {
+    pub fn apply_mint(&mut self, mint_info: &MintInfo) {
+        self.decimals = Some(mint_info.decimals);
+        self.ui_amount_string = self
+            .amount
+            .parse()
+            .ok()
+            .map(|amount| real_number_string_trimmed(amount, mint_info.decimals));
+        self.name = mint_info.name.clone();
+        self.symbol = mint_info.symbol.clone();
+    }
+}
+
+impl Portfolio {
+    /// Merges token accounts per mint. Token accounts are expected to be enriched with their mint.
+    pub fn new(
+        owner: &str, lamports: u64, token_accounts: Vec<(String, TokenAccountData)>,
+    ) -> Self {
+        let mut by_mint: BTreeMap<String, (u64, TokenAccountData, Vec<String>)> = BTreeMap::new();
+        for (pubkey, token_account) in token_accounts {
+            let amount = token_account.amount.parse::<u64>().unwrap_or_default();
+            let entry = by_mint
+                .entry(token_account.mint.clone())
+                .or_insert_with(|| (0, token_account, vec![]));
+            // The summed balance of a mint is bounded by its supply, which fits in a u64.
+            entry.0 = entry.0.saturating_add(amount);
+            entry.2.push(pubkey);
+        }
+
+        let tokens = by_mint
+            .into_iter()
+            .map(
+                |(mint, (amount, token_account, token_accounts))| PortfolioToken {
+                    mint,
+                    amount: amount.to_string(),
+                    decimals: token_account.decimals,
+                    ui_amount_string: token_account
+                        .decimals
+                        .map(|decimals| real_number_string_trimmed(amount, decimals)),
+                    name: token_account.name,
+                    symbol: token_account.symbol,
+                    token_accounts,
+                },
+            )
+            .collect();
+
+        Portfolio {
+            owner: owner.to_string(),
+            lamports,
+            sol: real_number_string_trimmed(lamports, LAMPORTS_PER_SOL.ilog10() as u8),
+            tokens,
+        }
+    }
+}
+
+/// Parses an SPL Token or Token-2022 mint, including any on-mint token metadata extension.
+pub fn parse_mint(mint: &str, owner: &Pubkey, data: &[u8]) -> Option<MintInfo> {
+    if !is_known_spl_token_id(owner) {
+        return None;
+    }
+
+    let ui_mint = match parse_token_v2(data, None) {
+        Ok(TokenAccountType::Mint(ui_mint)) => ui_mint,
+        _ => return None,
+    };
+
+    let mut mint_info = MintInfo {
+        mint:             mint.to_string(),
+        program_id:       owner.to_string(),
+        decimals:         ui_mint.decimals,
+        supply:           ui_mint.supply,
+        mint_authority:   ui_mint.mint_authority,
+        freeze_authority: ui_mint.freeze_authority,
+        extensions:       vec![],
+        name:             None,
+        symbol:           None,
+        uri:              None,
+    };
+
+    for extension in ui_mint.extensions {
+        if let UiExtension::TokenMetadata(metadata) = &extension {
+            mint_info.apply_metadata(TokenMetadata {
+                name:   metadata.name.clone(),
+                symbol: metadata.symbol.clone(),
+                uri:    metadata.uri.clone(),
+            });
+        }
+        if let Ok(value) = serde_json::to_value(&extension) {
+            mint_info.extensions.push(value);
+        }
+    }
+
+    Some(mint_info)
+}
+
+/// Parses the base layout shared by SPL Token and Token-2022 token accounts.
+pub fn parse_token_account(owner: &Pubkey, data: &[u8]) -> Option<TokenAccountData> {
+    if !is_known_spl_token_id(owner) {
+        return None;
+    }
+
+    let mint = get_token_account_mint(data)?;
+    let token_owner = Pubkey::try_from(data.get(32 .. 64)?).ok()?;
+    let amount = u64::from_le_bytes(data.get(64 .. 72)?.try_into().ok()?);
+
+    Some(TokenAccountData {
+        mint:             mint.to_string(),
+        owner:            token_owner.to_string(),
+        amount:           amount.to_string(),
+        decimals:         None,
+        ui_amount_string: None,
+        name:             None,
+        symbol:           None,
+    })
+}
+
+/// Derives the Metaplex metadata account address for a mint.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Parses the name, symbol and uri of a Metaplex metadata account.
+pub fn parse_metadata(data: &[u8]) -> Option<TokenMetadata> {
+    if *data.first()? != METADATA_V1_KEY {
+        return None;
+    }
+
+    // Skip the key, update authority and mint.
+    let mut offset = 1 + 32 + 32;
+    let name = read_borsh_string(data, &mut offset)?;
+    let symbol = read_borsh_string(data, &mut offset)?;
+    let uri = read_borsh_string(data, &mut offset)?;
+
+    Some(TokenMetadata { name, symbol, uri })
+}
+
+fn read_borsh_string(data: &[u8], offset: &mut usize) -> Option<String> {
+    let len = u32::from_le_bytes(data.get(*offset .. *offset + 4)?.try_into().ok()?) as usize;
+    *offset += 4;
+    let bytes = data.get(*offset .. *offset + len)?;
+    *offset += len;
+
+    // Metaplex pads fixed-size fields with null bytes.
+    Some(
+        String::from_utf8_lossy(bytes)
+            .trim_end_matches('\0')
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn borsh_string(value: &str, padded_len: usize) -> Vec<u8> {
+        let mut bytes = (padded_len as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.resize(4 + padded_len, 0);
+        bytes
+    }
+
+    fn packed_mint(decimals: u8, supply: u64, mint_authority: Option<Pubkey>) -> Vec<u8> {
+        let mut data = vec![];
+        match mint_authority {
+            Some(authority) => {
+                data.extend_from_slice(&1u32.to_le_bytes());
+                data.extend_from_slice(authority.as_ref());
+            }
+            None => data.extend_from_slice(&[0; 36]),
+        }
+        data.extend_from_slice(&supply.to_le_bytes());
+        data.push(decimals);
+        data.push(1);
+        data.extend_from_slice(&[0; 36]);
+        data
+    }
+
+    #[test]
+    fn test_parse_mint() {
+        let authority = Pubkey::new_unique();
+        let data = packed_mint(6, 1_000_000, Some(authority));
+
+        let mint_info = parse_mint("Mint", &spl_token_program_id(), &data).unwrap();
+
+        assert_eq!(mint_info.decimals, 6);
+        assert_eq!(mint_info.supply, "1000000");
+        assert_eq!(mint_info.mint_authority, Some(authority.to_string()));
+        assert_eq!(mint_info.freeze_authority, None);
+        assert!(mint_info.extensions.is_empty());
+    }
+
+    #[test]
+    fn test_parse_mint_wrong_owner() {
+        let data = packed_mint(6, 1, None);
+
+        assert!(parse_mint("Mint", &Pubkey::new_unique(), &data).is_none());
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let mut data = vec![METADATA_V1_KEY];
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend(borsh_string("USD Coin", 32));
+        data.extend(borsh_string("USDC", 10));
+        data.extend(borsh_string("https://example.com/usdc.json", 200));
+
+        let metadata = parse_metadata(&data).unwrap();
+
+        assert_eq!(metadata.name, "USD Coin");
+        assert_eq!(metadata.symbol, "USDC");
+        assert_eq!(metadata.uri, "https://example.com/usdc.json");
+    }
+
+    #[test]
+    fn test_parse_metadata_truncated() {
+        let data = vec![METADATA_V1_KEY, 0, 0];
+
+        assert!(parse_metadata(&data).is_none());
+    }
+
+    #[test]
+    fn test_token_account_apply_mint() {
+        let mut token_account = TokenAccountData {
+            mint:             "Mint".to_string(),
+            owner:            "Owner".to_string(),
+            amount:           "1500000".to_string(),
+            decimals:         None,
+            ui_amount_string: None,
+            name:             None,
+            symbol:           None,
+        };
+        let mint_info = MintInfo {
+            mint:             "Mint".to_string(),
+            program_id:       spl_token_program_id().to_string(),
+            decimals:         6,
+            supply:           "0".to_string(),
+            mint_authority:   None,
+            freeze_authority: None,
+            extensions:       vec![],
+            name:             Some("USD Coin".to_string()),
+            symbol:           Some("USDC".to_string()),
+            uri:              None,
+        };
+
+        token_account.apply_mint(&mint_info);
+
+        assert_eq!(token_account.decimals, Some(6));
+        assert_eq!(token_account.ui_amount_string.as_deref(), Some("1.5"));
+        assert_eq!(token_account.symbol.as_deref(), Some("USDC"));
+    }
+
+    #[test]
+    fn test_portfolio_merges_balances_per_mint() {
+        let token_account = |mint: &str, amount: &str| TokenAccountData {
+            mint:             mint.to_string(),
+            owner:            "Owner".to_string(),
+            amount:           amount.to_string(),
+            decimals:         Some(2),
+            ui_amount_string: None,
+            name:             None,
+            symbol:           Some(mint.to_uppercase()),
+        };
+
+        let portfolio = Portfolio::new(
+            "Owner",
+            1_500_000_000,
+            vec![
+                ("Account1".to_string(), token_account("b", "150")),
+                ("Account2".to_string(), token_account("a", "1")),
+                ("Account3".to_string(), token_account("b", "50")),
+            ],
+        );
+
+        assert_eq!(portfolio.sol, "1.5");
+        assert_eq!(portfolio.tokens.len(), 2);
+        assert_eq!(portfolio.tokens[0].mint, "a");
+        assert_eq!(portfolio.tokens[1].amount, "200");
+        assert_eq!(portfolio.tokens[1].ui_amount_string.as_deref(), Some("2"));
+        assert_eq!(portfolio.tokens[1].symbol.as_deref(), Some("B"));
+        assert_eq!(
+            portfolio.tokens[1].token_accounts,
+            vec!["Account1", "Account3"]
+        );
+    }
+
+    fn spl_token_program_id() -> Pubkey {
+        pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA")
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    data_processing::{
+        processor::{AccountData, TransactionData},
+        token::{metadata_address, parse_metadata, parse_mint, MintInfo},
+    },
+    data_retrieval::{RetrievalError, RpcSolanaClient},
+    data_storage::Storage,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::{collections::BTreeSet, str::FromStr, sync::Arc};
+use tracing::warn;
+
+/// Registry of SPL mints seen during processing.
+///
+/// Mints are fetched once through the RPC client, enriched with Metaplex metadata when the mint
+/// does not carry a Token-2022 metadata extension, and cached in storage.
+pub struct TokenRegistry {
+    rpc_solana_client: Arc<RpcSolanaClient>,
+    storage:           Arc<Storage>,
+}
+
+impl TokenRegistry {
+    pub fn new(rpc_solana_client: Arc<RpcSolanaClient>, storage: Arc<Storage>) -> Self {
+        TokenRegistry {
+            rpc_solana_client,
+            storage,
+        }
+    }
+
+    /// Returns the mint info, or `None` if the account exists but is not a mint.
+    ///
+    /// `commitment` only applies when the mint is not registered yet. A `min_context_slot` always
+    /// fetches the mint again, since the registered one may be older.
+    pub async fn get_mint(
+        &self, mint: &str, commitment: CommitmentConfig, min_context_slot: Option<u64>,
+    ) -> Result<Option<MintInfo>, RetrievalError> {
+        if min_context_slot.is_none() {
+            if let Some(mint_info) = self.storage.get_mint(mint) {
+                return Ok(Some(mint_info));
+            }
+        }
+
+        let account = self
+            .rpc_solana_client
+            .get_account(mint, commitment, min_context_slot)
+            .await?
+            .value;
+        Ok(self
+            .register_mint(mint, &account.owner, &account.data, commitment)
+            .await)
+    }
+
+    async fn register_mint(
+        &self, mint: &str, owner: &Pubkey, data: &[u8], commitment: CommitmentConfig,
+    ) -> Option<MintInfo> {
+        let mut mint_info = parse_mint(mint, owner, data)?;
+
+        if mint_info.name.is_none() {
+            if let Ok(mint_pubkey) = Pubkey::from_str(mint) {
+                let metadata_pubkey = metadata_address(&mint_pubkey).to_string();
+                match self
+                    .rpc_solana_client
+                    .get_optional_account(&metadata_pubkey, commitment, None)
+                    .await
+                    .map(|response| response.value)
+                {
+                    Ok(Some(account)) => {
+                        if let Some(metadata) = parse_metadata(&account.data) {
+                            mint_info.apply_metadata(metadata);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to fetch metadata for mint {}: {}", mint, e),
+                }
+            }
+        }
+
+        self.storage.insert_mint(mint_info.clone());
+        Some(mint_info)
+    }
+
+    pub async fn enrich_transaction(&self, transaction_data: &mut TransactionData) {
+        let mints: BTreeSet<String> = transaction_data
+            .token_balances
+            .iter()
+            .map(|balance| balance.mint.clone())
+            .collect();
+
+        for mint in mints {
+            let mint_info = match self
+                .get_mint(&mint, self.rpc_solana_client.commitment(None), None)
+                .await
+            {
+                Ok(Some(mint_info)) => mint_info,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to fetch mint {}: {}", mint, e);
+                    continue;
+                }
+            };
+
+            for balance in transaction_data
+                .token_balances
+                .iter_mut()
+                .filter(|balance| balance.mint == mint)
+            {
+                balance.name = mint_info.name.clone();
+                balance.symbol = mint_info.symbol.clone();
+            }
+        }
+    }
+
+    pub async fn enrich_account(&self, pubkey: &str, account_data: &mut AccountData) {
+        if let Some(mint) = account_data
+            .token_account
+            .as_ref()
+            .map(|token_account| token_account.mint.clone())
+        {
+            match self
+                .get_mint(&mint, self.rpc_solana_client.commitment(None), None)
+                .await
+            {
+                Ok(Some(mint_info)) => {
+                    if let Some(token_account) = account_data.token_account.as_mut() {
+                        token_account.apply_mint(&mint_info);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Failed to fetch mint {}: {}", mint, e),
+            }
+            return;
+        }
+
+        account_data.mint = match self.storage.get_mint(pubkey) {
+            Some(mint_info) => Some(mint_info),
+            None => match Pubkey::from_str(&account_data.owner) {
+                Ok(owner) => {
+                    let commitment = self.rpc_solana_client.commitment(None);
+                    self.register_mint(pubkey, &owner, &account_data.data, commitment)
+                        .await
+                }
+                Err(_) => None,
+            },
+        };
+    }
+}
@@ -0,0 +1,234 @@
+use lru::LruCache;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    num::NonZeroUsize,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use utoipa::ToSchema;
+
+/// How long a fetched response may be served from the cache.
+pub enum CacheTtl {
+    /// The response can no longer change, e.g. a finalized transaction.
+    Forever,
+    Expire(Duration),
+    /// The response must not be cached, e.g. a transaction that is not found yet.
+    Skip,
+}
+
+/// Hit, miss and eviction counters of the response cache.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct CacheMetrics {
+    pub entries:       usize,
+    /// Size of the cached responses serialized as JSON.
+    pub bytes:         usize,
+    pub hits:          u64,
+    pub misses:        u64,
+    /// Entries dropped to make room for new ones.
+    pub evictions:     u64,
+    /// Entries dropped because an update was reported for them.
+    pub invalidations: u64,
+}
+
+struct CacheEntry {
+    value:      Value,
+    /// Unix timestamp at which the response arrived.
+    fetched_at: i64,
+    expires_at: Option<Instant>,
+    size:       usize,
+}
+
+struct CacheState {
+    entries: Option<LruCache<String, CacheEntry>>,
+    metrics: CacheMetrics,
+}
+
+impl CacheState {
+    fn remove(&mut self, key: &str) -> bool {
+        let Some(entry) = self.entries.as_mut().and_then(|entries| entries.pop(key)) else {
+            return false;
+        };
+        self.metrics.bytes -= entry.size;
+        true
+    }
+}
+
+/// LRU cache of raw RPC responses, keyed like the in-flight requests and bounded both in entries
+/// and in bytes, as a single block can weigh megabytes.
+pub struct ResponseCache {
+    state:     Mutex<CacheState>,
+    max_bytes: usize,
+}
+
+impl ResponseCache {
+    /// A cache of at most `max_entries` responses weighing at most `max_bytes` serialized,
+    /// disabled when either is zero.
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        let entries = match max_bytes {
+            0 => None,
+            _ => NonZeroUsize::new(max_entries).map(LruCache::new),
+        };
+        ResponseCache {
+            state: Mutex::new(CacheState {
+                entries,
+                metrics: CacheMetrics::default(),
+            }),
+            max_bytes,
+        }
+    }
+
+    /// The cached response with the Unix timestamp at which it arrived.
+    pub fn get(&self, key: &str) -> Option<(Value, i64)> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let entries = state.entries.as_mut()?;
+
+        let value = entries
+            .get(key)
+            .filter(|entry| {
+                entry
+                    .expires_at
+                    .is_none_or(|expires_at| expires_at > Instant::now())
+            })
+            .map(|entry| (entry.value.clone(), entry.fetched_at));
+        // Drops the expired entry, if any.
+        if value.is_none() {
+            state.remove(key);
+        }
+        match value {
+            Some(_) => state.metrics.hits += 1,
+            None => state.metrics.misses += 1,
+        }
+        value
+    }
+
+    pub fn insert(&self, key: String, value: Value, fetched_at: i64, ttl: CacheTtl) {
+        let expires_at = match ttl {
+            CacheTtl::Forever => None,
+            CacheTtl::Expire(ttl) => Some(Instant::now() + ttl),
+            CacheTtl::Skip => return,
+        };
+        let size = key.len() + value.to_string().len();
+
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        if state.entries.is_none() {
+            return;
+        }
+        state.remove(&key);
+        if size > self.max_bytes {
+            return;
+        }
+        while state.metrics.bytes + size > self.max_bytes {
+            let Some((_, evicted)) = state.entries.as_mut().and_then(LruCache::pop_lru) else {
+                break;
+            };
+            state.metrics.bytes -= evicted.size;
+            state.metrics.evictions += 1;
+        }
+
+        let Some(entries) = state.entries.as_mut() else {
+            return;
+        };
+        let entry = CacheEntry {
+            value,
+            fetched_at,
+            expires_at,
+            size,
+        };
+        if let Some((_, evicted)) = entries.push(key, entry) {
+            state.metrics.bytes -= evicted.size;
+            state.metrics.evictions += 1;
+        }
+        state.metrics.bytes += size;
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.remove(key) {
+            state.metrics.invalidations += 1;
+        }
+    }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        let state = self.state.lock().unwrap();
+        CacheMetrics {
+            entries: state.entries.as_ref().map_or(0, LruCache::len),
+            ..state.metrics.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_get_respects_ttl() {
+        let cache = ResponseCache::new(10, 1024);
+        cache.insert("finalized".to_string(), json!(1), 100, CacheTtl::Forever);
+        cache.insert(
+            "expired".to_string(),
+            json!(2),
+            0,
+            CacheTtl::Expire(Duration::ZERO),
+        );
+        cache.insert("skipped".to_string(), json!(3), 0, CacheTtl::Skip);
+
+        assert_eq!(cache.get("finalized"), Some((json!(1), 100)));
+        assert_eq!(cache.get("expired"), None);
+        assert_eq!(cache.get("skipped"), None);
+
+        let metrics = cache.metrics();
+        assert_eq!((metrics.hits, metrics.misses, metrics.entries), (1, 2, 1));
+    }
+
+    #[test]
+    fn test_insert_evicts_least_recently_used() {
+        let cache = ResponseCache::new(2, 1024);
+        cache.insert("a".to_string(), json!(1), 0, CacheTtl::Forever);
+        cache.insert("b".to_string(), json!(2), 0, CacheTtl::Forever);
+        cache.get("a");
+        cache.insert("c".to_string(), json!(3), 0, CacheTtl::Forever);
+
+        assert_eq!(cache.get("a"), Some((json!(1), 0)));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.metrics().evictions, 1);
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let cache = ResponseCache::new(10, 1024);
+        cache.insert("account".to_string(), json!(1), 0, CacheTtl::Forever);
+
+        cache.invalidate("account");
+
+        assert_eq!(cache.get("account"), None);
+        assert_eq!(cache.metrics().invalidations, 1);
+    }
+
+    #[test]
+    fn test_insert_evicts_to_stay_within_bytes() {
+        // Each entry weighs its one byte key and the four bytes of its value.
+        let cache = ResponseCache::new(10, 12);
+        cache.insert("a".to_string(), json!("aa"), 0, CacheTtl::Forever);
+        cache.insert("b".to_string(), json!("bb"), 0, CacheTtl::Forever);
+        cache.insert("c".to_string(), json!("cc"), 0, CacheTtl::Forever);
+        cache.insert(
+            "d".to_string(),
+            json!("much too large"),
+            0,
+            CacheTtl::Forever,
+        );
+
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get("b"), Some((json!("bb"), 0)));
+        assert_eq!(cache.get("c"), Some((json!("cc"), 0)));
+        assert_eq!(cache.get("d"), None);
+        let metrics = cache.metrics();
+        assert_eq!((metrics.entries, metrics.bytes), (2, 10));
+        assert_eq!(metrics.evictions, 1);
+    }
+}
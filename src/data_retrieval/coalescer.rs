@@ -0,0 +1,176 @@
+use crate::data_retrieval::error::RetrievalError;
+use std::{collections::HashMap, future::Future, sync::Mutex};
+use tokio::sync::broadcast;
+
+/// Shares the result of one in-flight call between concurrent callers asking for the same key.
+///
+/// The first caller runs the call, later callers wait for its result. When the first caller is
+/// dropped before finishing, the waiting callers run the call themselves.
+pub struct Coalescer<T> {
+    in_flight: Mutex<HashMap<String, broadcast::Sender<Result<T, RetrievalError>>>>,
+}
+
+impl<T: Clone> Coalescer<T> {
+    pub fn new() -> Self {
+        Coalescer {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn run<F, Fut>(&self, key: String, call: F) -> Result<T, RetrievalError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, RetrievalError>>,
+    {
+        let receiver = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(&key) {
+                Some(sender) => Some(sender.subscribe()),
+                None => {
+                    in_flight.insert(key.clone(), broadcast::channel(1).0);
+                    None
+                }
+            }
+        };
+
+        if let Some(mut receiver) = receiver {
+            return match receiver.recv().await {
+                Ok(result) => result,
+                Err(_) => call().await,
+            };
+        }
+
+        let mut guard = InFlight {
+            coalescer: self,
+            key:       Some(key),
+        };
+        let result = call().await;
+        if let Some(sender) = guard.finish() {
+            // No receivers only means nobody else asked for the key meanwhile.
+            let _ = sender.send(result.clone());
+        }
+        result
+    }
+}
+
+/// Removes the in-flight entry even when the owning call is dropped, which closes the channel
+/// and wakes up the waiting callers.
+struct InFlight<'a, T> {
+    coalescer: &'a Coalescer<T>,
+    /// Taken by `finish`, after which the entry may already belong to a new call.
+    key:       Option<String>,
+}
+
+impl<T> InFlight<'_, T> {
+    fn finish(&mut self) -> Option<broadcast::Sender<Result<T, RetrievalError>>> {
+        let key = self.key.take()?;
+        self.coalescer.in_flight.lock().unwrap().remove(&key)
+    }
+}
+
+impl<T> Drop for InFlight<'_, T> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.coalescer.in_flight.lock().unwrap().remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn test_run_coalesces_concurrent_calls() {
+        let coalescer = Coalescer::new();
+        let calls = AtomicU32::new(0);
+        let call = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(7)
+        };
+
+        let (first, second, other) = tokio::join!(
+            coalescer.run("account".to_string(), call),
+            coalescer.run("account".to_string(), call),
+            coalescer.run("other".to_string(), call),
+        );
+
+        assert_eq!((first.unwrap(), second.unwrap(), other.unwrap()), (7, 7, 7));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_shares_errors() {
+        let coalescer = Coalescer::<u64>::new();
+        let call = || async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Err(RetrievalError::not_found("account"))
+        };
+
+        let (first, second) = tokio::join!(
+            coalescer.run("account".to_string(), call),
+            coalescer.run("account".to_string(), call),
+        );
+
+        assert_eq!(first.unwrap_err().message, "account");
+        assert_eq!(second.unwrap_err().message, "account");
+    }
+
+    #[tokio::test]
+    async fn test_run_falls_back_when_first_caller_is_dropped() {
+        let coalescer = Coalescer::new();
+
+        let first = tokio::time::timeout(
+            Duration::from_millis(20),
+            coalescer.run("account".to_string(), || async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(1)
+            }),
+        );
+        let second = async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            coalescer
+                .run("account".to_string(), || async { Ok(2) })
+                .await
+        };
+
+        let (first, result) = tokio::join!(first, second);
+
+        assert!(first.is_err());
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_finished_call_keeps_next_call_in_flight() {
+        let coalescer = Coalescer::<u64>::new();
+        let (sender, _) = broadcast::channel(1);
+        coalescer
+            .in_flight
+            .lock()
+            .unwrap()
+            .insert("account".to_string(), sender);
+        let mut guard = InFlight {
+            coalescer: &coalescer,
+            key:       Some("account".to_string()),
+        };
+
+        assert!(guard.finish().is_some());
+        // A new call for the key starts before the guard of the finished one is dropped.
+        let (sender, mut receiver) = broadcast::channel(1);
+        coalescer
+            .in_flight
+            .lock()
+            .unwrap()
+            .insert("account".to_string(), sender);
+        drop(guard);
+
+        let sender = coalescer.in_flight.lock().unwrap().remove("account");
+        assert!(sender.unwrap().send(Ok(3)).is_ok());
+        assert_eq!(receiver.recv().await.unwrap().unwrap(), 3);
+    }
+}
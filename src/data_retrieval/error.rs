@@ -0,0 +1,349 @@
+use crate::data_retrieval::http_sender::HttpStatusError;
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_custom_error::{
+        JSON_RPC_SERVER_ERROR_BLOCK_CLEANED_UP, JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+        JSON_RPC_SERVER_ERROR_BLOCK_STATUS_NOT_AVAILABLE_YET,
+        JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED,
+        JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED, JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+        JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+        JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE,
+    },
+    rpc_request::RpcError,
+};
+use solana_pubsub_client::pubsub_client::PubsubClientError;
+use std::{fmt, io, sync::Arc, time::Duration};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// JSON-RPC "invalid params" error code.
+const JSON_RPC_INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC "internal error" error code.
+const JSON_RPC_INTERNAL_ERROR: i64 = -32603;
+
+/// Class of a retrieval failure, used to pick the API status code and the retry behaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RetrievalErrorKind {
+    InvalidInput,
+    NotFound,
+    RateLimited,
+    UpstreamTimeout,
+    UpstreamError,
+    DecodeFailure,
+}
+
+impl RetrievalErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RetrievalErrorKind::InvalidInput => "invalid_input",
+            RetrievalErrorKind::NotFound => "not_found",
+            RetrievalErrorKind::RateLimited => "rate_limited",
+            RetrievalErrorKind::UpstreamTimeout => "upstream_timeout",
+            RetrievalErrorKind::UpstreamError => "upstream_error",
+            RetrievalErrorKind::DecodeFailure => "decode_failure",
+        }
+    }
+}
+
+impl fmt::Display for RetrievalErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Failure of an RPC or websocket call, keeping the upstream error as its source.
+///
+/// Cloning shares the source, so one failure can be handed to every caller of a coalesced request.
+#[derive(Debug, Clone, Error)]
+#[error("{kind}: {message}{}", details(*.rpc_code, *.http_status))]
+pub struct RetrievalError {
+    kind:            RetrievalErrorKind,
+    pub message:     String,
+    /// JSON-RPC error code returned by the node, if any.
+    pub rpc_code:    Option<i64>,
+    /// HTTP status returned by the provider, if any.
+    pub http_status: Option<u16>,
+    pub retryable:   bool,
+    /// Delay requested by the provider through the `Retry-After` header.
+    pub retry_after: Option<Duration>,
+    #[source]
+    source:          Option<Arc<dyn std::error::Error + Send + Sync>>,
+}
+
+/// Suffix of the message naming the RPC code and HTTP status, when known.
+fn details(rpc_code: Option<i64>, http_status: Option<u16>) -> String {
+    let mut details = String::new();
+    if let Some(rpc_code) = rpc_code {
+        details.push_str(&format!(" (rpc code {})", rpc_code));
+    }
+    if let Some(http_status) = http_status {
+        details.push_str(&format!(" (http status {})", http_status));
+    }
+    details
+}
+
+impl RetrievalError {
+    pub fn new(kind: RetrievalErrorKind, message: impl Into<String>) -> Self {
+        RetrievalError {
+            kind,
+            message: message.into(),
+            rpc_code: None,
+            http_status: None,
+            retryable: false,
+            retry_after: None,
+            source: None,
+        }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        RetrievalError::new(RetrievalErrorKind::InvalidInput, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        RetrievalError::new(RetrievalErrorKind::NotFound, message)
+    }
+
+    pub fn decode_failure(message: impl Into<String>) -> Self {
+        RetrievalError::new(RetrievalErrorKind::DecodeFailure, message)
+    }
+
+    pub fn invalid_pubkey(pubkey: &str, e: impl fmt::Display) -> Self {
+        RetrievalError::invalid_input(format!("invalid pubkey {}: {}", pubkey, e))
+    }
+
+    pub fn invalid_signature(signature: &str, e: impl fmt::Display) -> Self {
+        RetrievalError::invalid_input(format!("invalid signature {}: {}", signature, e))
+    }
+
+    /// The node answered from a slot older than the one the caller has already seen.
+    pub fn stale_context(context_slot: u64, min_context_slot: u64) -> Self {
+        let mut error = RetrievalError::new(
+            RetrievalErrorKind::UpstreamError,
+            format!(
+                "context slot {} is older than min_context_slot {}",
+                context_slot, min_context_slot
+            ),
+        );
+        error.retryable = true;
+        error
+    }
+
+    pub fn kind(&self) -> RetrievalErrorKind {
+        self.kind
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.retryable
+    }
+
+    fn upstream(
+        kind: RetrievalErrorKind, retryable: bool,
+        source: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        RetrievalError {
+            kind,
+            message: source.to_string(),
+            rpc_code: None,
+            http_status: None,
+            retryable,
+            retry_after: None,
+            source: Some(Arc::new(source)),
+        }
+    }
+}
+
+impl From<ClientError> for RetrievalError {
+    fn from(e: ClientError) -> Self {
+        let mut rpc_code = None;
+        let mut http_status = None;
+        let mut retry_after = None;
+
+        let (kind, retryable) = match e.kind() {
+            ClientErrorKind::Reqwest(reqwest_error) => {
+                http_status = reqwest_error.status().map(|status| status.as_u16());
+                match http_status {
+                    _ if reqwest_error.is_timeout() => (RetrievalErrorKind::UpstreamTimeout, true),
+                    Some(status) => classify_http_status(status),
+                    None => (RetrievalErrorKind::UpstreamError, true),
+                }
+            }
+            ClientErrorKind::Middleware(middleware_error) => {
+                match middleware_error.downcast_ref::<HttpStatusError>() {
+                    Some(status_error) => {
+                        http_status = Some(status_error.status);
+                        retry_after = status_error.retry_after;
+                        classify_http_status(status_error.status)
+                    }
+                    None => (RetrievalErrorKind::UpstreamError, false),
+                }
+            }
+            ClientErrorKind::Io(io_error) if io_error.kind() == io::ErrorKind::TimedOut => {
+                (RetrievalErrorKind::UpstreamTimeout, true)
+            }
+            ClientErrorKind::Io(_) => (RetrievalErrorKind::UpstreamError, true),
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+                rpc_code = Some(*code);
+                classify_rpc_code(*code)
+            }
+            ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => {
+                (RetrievalErrorKind::UpstreamError, true)
+            }
+            ClientErrorKind::RpcError(RpcError::ParseError(_)) | ClientErrorKind::SerdeJson(_) => {
+                (RetrievalErrorKind::DecodeFailure, false)
+            }
+            _ => (RetrievalErrorKind::UpstreamError, false),
+        };
+
+        RetrievalError {
+            rpc_code,
+            http_status,
+            retry_after,
+            ..RetrievalError::upstream(kind, retryable, e)
+        }
+    }
+}
+
+impl From<PubsubClientError> for RetrievalError {
+    fn from(e: PubsubClientError) -> Self {
+        let (kind, retryable) = match &e {
+            PubsubClientError::UrlParseError(_) => (RetrievalErrorKind::InvalidInput, false),
+            PubsubClientError::ConnectionError(_)
+            | PubsubClientError::WsError(_)
+            | PubsubClientError::ConnectionClosed(_) => (RetrievalErrorKind::UpstreamError, true),
+            PubsubClientError::JsonParseError(_)
+            | PubsubClientError::UnexpectedMessageError(_)
+            | PubsubClientError::UnexpectedSubscriptionResponse(_)
+            | PubsubClientError::UnexpectedGetVersionResponse(_) => {
+                (RetrievalErrorKind::DecodeFailure, false)
+            }
+            _ => (RetrievalErrorKind::UpstreamError, false),
+        };
+
+        RetrievalError::upstream(kind, retryable, e)
+    }
+}
+
+fn classify_http_status(status: u16) -> (RetrievalErrorKind, bool) {
+    match status {
+        429 => (RetrievalErrorKind::RateLimited, true),
+        status => (RetrievalErrorKind::UpstreamError, status >= 500),
+    }
+}
+
+fn classify_rpc_code(code: i64) -> (RetrievalErrorKind, bool) {
+    match code {
+        JSON_RPC_INVALID_PARAMS => (RetrievalErrorKind::InvalidInput, false),
+        // The block may still become available once the node catches up, so it is retried like
+        // any transient upstream failure rather than reported missing.
+        JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE
+        | JSON_RPC_SERVER_ERROR_BLOCK_STATUS_NOT_AVAILABLE_YET => {
+            (RetrievalErrorKind::UpstreamError, true)
+        }
+        JSON_RPC_SERVER_ERROR_BLOCK_CLEANED_UP
+        | JSON_RPC_SERVER_ERROR_SLOT_SKIPPED
+        | JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED
+        | JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE => {
+            (RetrievalErrorKind::NotFound, false)
+        }
+        JSON_RPC_INTERNAL_ERROR
+        | JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+        | JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED => {
+            (RetrievalErrorKind::UpstreamError, true)
+        }
+        _ => (RetrievalErrorKind::UpstreamError, false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config::RetryConfig;
+    use solana_client::rpc_request::RpcResponseErrorData;
+    use std::error::Error as _;
+
+    fn rpc_response_error(code: i64) -> ClientError {
+        ClientError::from(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code,
+            message: "message".to_string(),
+            data: RpcResponseErrorData::Empty,
+        }))
+    }
+
+    #[test]
+    fn test_classify_rpc_response_errors() {
+        let skipped = RetrievalError::from(rpc_response_error(JSON_RPC_SERVER_ERROR_SLOT_SKIPPED));
+        assert_eq!(skipped.kind(), RetrievalErrorKind::NotFound);
+        assert_eq!(skipped.rpc_code, Some(JSON_RPC_SERVER_ERROR_SLOT_SKIPPED));
+        assert!(!skipped.is_retryable());
+
+        let invalid = RetrievalError::from(rpc_response_error(JSON_RPC_INVALID_PARAMS));
+        assert_eq!(invalid.kind(), RetrievalErrorKind::InvalidInput);
+
+        let unhealthy =
+            RetrievalError::from(rpc_response_error(JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY));
+        assert_eq!(unhealthy.kind(), RetrievalErrorKind::UpstreamError);
+        assert!(unhealthy.is_retryable());
+
+        for code in [
+            JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE,
+            JSON_RPC_SERVER_ERROR_BLOCK_STATUS_NOT_AVAILABLE_YET,
+        ] {
+            let pending = RetrievalError::from(rpc_response_error(code));
+            assert_eq!(pending.kind(), RetrievalErrorKind::UpstreamError);
+            assert!(pending.is_retryable());
+            assert!(RetryConfig::default()
+                .retryable_errors
+                .contains(&pending.kind()));
+        }
+    }
+
+    #[test]
+    fn test_classify_decode_errors() {
+        let serde_error = serde_json::from_str::<u64>("\"not a number\"").unwrap_err();
+
+        let e = RetrievalError::from(ClientError::from(ClientErrorKind::SerdeJson(serde_error)));
+
+        assert_eq!(e.kind(), RetrievalErrorKind::DecodeFailure);
+        assert!(!e.is_retryable());
+        assert!(e.source().is_some());
+    }
+
+    #[test]
+    fn test_classify_http_status_errors() {
+        let status_error = HttpStatusError {
+            status:      429,
+            retry_after: Some(Duration::from_secs(2)),
+        };
+
+        let e = RetrievalError::from(ClientError::from(ClientErrorKind::Middleware(
+            status_error.into(),
+        )));
+
+        assert_eq!(e.kind(), RetrievalErrorKind::RateLimited);
+        assert_eq!(e.http_status, Some(429));
+        assert_eq!(e.retry_after, Some(Duration::from_secs(2)));
+        assert!(e.is_retryable());
+    }
+
+    #[test]
+    fn test_stale_context_is_retryable() {
+        let e = RetrievalError::stale_context(10, 12);
+
+        assert_eq!(e.kind(), RetrievalErrorKind::UpstreamError);
+        assert!(e.is_retryable());
+        assert_eq!(
+            e.message,
+            "context slot 10 is older than min_context_slot 12"
+        );
+    }
+
+    #[test]
+    fn test_display_includes_rpc_code() {
+        let e = RetrievalError::from(rpc_response_error(JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY));
+
+        assert!(e.to_string().starts_with("upstream_error: "));
+        assert!(e.to_string().ends_with("(rpc code -32005)"));
+    }
+}
@@ -0,0 +1,487 @@
+use crate::{config::config::BatchConfig, data_retrieval::rpc_pool::redact_url};
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde_json::Value;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    rpc_custom_error::{
+        MinContextSlotNotReachedErrorData, NodeUnhealthyErrorData,
+        JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED, JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+        JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE,
+    },
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_response::RpcSimulateTransactionResult,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use solana_rpc_client_api::error_object::RpcErrorObject;
+use std::{
+    collections::HashMap,
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, warn};
+
+/// Timeout applied to every HTTP request sent to the RPC node.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Methods whose responses are too large to share a batch, always sent on their own.
+const UNBATCHED_METHODS: [RpcRequest; 2] = [RpcRequest::GetBlock, RpcRequest::GetProgramAccounts];
+
+/// Non-success HTTP response from the RPC provider.
+///
+/// Travels through the RPC client as a `ClientErrorKind::Middleware` error so the status and the
+/// `Retry-After` header reach the retry policy.
+#[derive(Debug, Error)]
+#[error("HTTP status {status}")]
+pub struct HttpStatusError {
+    pub status:      u16,
+    pub retry_after: Option<Duration>,
+}
+
+/// JSON-RPC over HTTP transport.
+///
+/// Unlike the sender bundled with the RPC client it never retries on its own, so rate limited
+/// responses are surfaced to the retry policy instead of being silently slept on. When batching
+/// is configured, small requests sent within the batch window are posted together as one JSON-RPC
+/// batch.
+pub struct HttpSender {
+    transport:  Arc<Transport>,
+    request_id: AtomicU64,
+    stats:      RwLock<RpcTransportStats>,
+    batching:   Option<BatchConfig>,
+    /// Queue of the batching task, started on the first request since it needs a runtime.
+    batcher:    OnceLock<mpsc::UnboundedSender<PendingRequest>>,
+}
+
+struct Transport {
+    client: reqwest::Client,
+    url:    String,
+}
+
+struct PendingRequest {
+    id:      u64,
+    body:    Value,
+    respond: oneshot::Sender<ClientResult<Value>>,
+}
+
+impl HttpSender {
+    pub fn new(url: &str, batching: Option<BatchConfig>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .pool_idle_timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("build rpc http client");
+
+        HttpSender {
+            transport:  Arc::new(Transport {
+                client,
+                url: url.to_string(),
+            }),
+            request_id: AtomicU64::new(0),
+            stats:      RwLock::new(RpcTransportStats::default()),
+            batching:   batching.filter(|batching| batching.max_size > 1),
+            batcher:    OnceLock::new(),
+        }
+    }
+
+    async fn post(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let body = request.build_request_json(id, params);
+
+        let Some(batching) = self
+            .batching
+            .as_ref()
+            .filter(|_| !UNBATCHED_METHODS.contains(&request))
+        else {
+            return self.transport.post_single(&body).await;
+        };
+
+        let batcher = self.batcher.get_or_init(|| {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            tokio::spawn(run_batcher(
+                Arc::clone(&self.transport),
+                batching.clone(),
+                receiver,
+            ));
+            sender
+        });
+        let (respond, response) = oneshot::channel();
+        if batcher.send(PendingRequest { id, body, respond }).is_err() {
+            return Err(batcher_stopped());
+        }
+        match response.await {
+            Ok(result) => result,
+            Err(_) => Err(batcher_stopped()),
+        }
+    }
+}
+
+impl Transport {
+    async fn post_json(&self, body: &Value) -> ClientResult<Value> {
+        let response = self.client.post(&self.url).json(body).send().await?;
+
+        if !response.status().is_success() {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            let status_error = HttpStatusError {
+                status: response.status().as_u16(),
+                retry_after,
+            };
+            return Err(ClientErrorKind::Middleware(status_error.into()).into());
+        }
+
+        Ok(response.json::<Value>().await?)
+    }
+
+    async fn post_single(&self, body: &Value) -> ClientResult<Value> {
+        into_result(self.post_json(body).await?)
+    }
+
+    /// Posts the requests as one batch, falling back to individual requests when the provider
+    /// rejects the batch format. A failed batch call, e.g. rate limited, fails every request.
+    async fn post_batch(&self, batch: Vec<PendingRequest>) {
+        if let [_] = batch.as_slice() {
+            let pending = batch.into_iter().next().unwrap();
+            let _ = pending.respond.send(self.post_single(&pending.body).await);
+            return;
+        }
+
+        let body = Value::Array(batch.iter().map(|pending| pending.body.clone()).collect());
+        let mut responses = match self.post_json(&body).await {
+            Ok(Value::Array(responses)) => responses
+                .into_iter()
+                .filter_map(|response| Some((response["id"].as_u64()?, response)))
+                .collect::<HashMap<_, _>>(),
+            // Anything but an array, typically a JSON-RPC error object, means batches are not
+            // supported.
+            Ok(_) => {
+                warn!(
+                    "JSON-RPC batch rejected by {}, sending requests individually",
+                    redact_url(&self.url)
+                );
+                join_all(batch.into_iter().map(|pending| async move {
+                    let _ = pending.respond.send(self.post_single(&pending.body).await);
+                }))
+                .await;
+                return;
+            }
+            Err(e) => {
+                for pending in batch {
+                    let _ = pending.respond.send(Err(shared_error(&e)));
+                }
+                return;
+            }
+        };
+
+        for pending in batch {
+            let result = match responses.remove(&pending.id) {
+                Some(response) => into_result(response),
+                None => Err(ClientError::from(ClientErrorKind::RpcError(
+                    RpcError::RpcRequestError("missing response in JSON-RPC batch".to_string()),
+                ))),
+            };
+            let _ = pending.respond.send(result);
+        }
+    }
+}
+
+/// Collects queued requests until the window elapses or the batch is full, then posts them.
+async fn run_batcher(
+    transport: Arc<Transport>, batching: BatchConfig,
+    mut receiver: mpsc::UnboundedReceiver<PendingRequest>,
+) {
+    while let Some(first) = receiver.recv().await {
+        let mut batch = vec![first];
+        let window = tokio::time::sleep(Duration::from_millis(batching.window_ms));
+        tokio::pin!(window);
+        while batch.len() < batching.max_size {
+            tokio::select! {
+                _ = &mut window => break,
+                pending = receiver.recv() => match pending {
+                    Some(pending) => batch.push(pending),
+                    None => break,
+                },
+            }
+        }
+
+        let transport = Arc::clone(&transport);
+        tokio::spawn(async move { transport.post_batch(batch).await });
+    }
+}
+
+/// Converts a JSON-RPC response object into its result or error, decoding the error data the
+/// way the sender bundled with the RPC client does.
+#[allow(clippy::result_large_err)] // Same error type as `RpcSender::send`.
+fn into_result(mut json: Value) -> ClientResult<Value> {
+    if json["error"].is_object() {
+        let rpc_error = match serde_json::from_value::<RpcErrorObject>(json["error"].clone()) {
+            Ok(rpc_error_object) => {
+                let data = json["error"]["data"].take();
+                let mut message = rpc_error_object.message;
+                let data = match rpc_error_object.code {
+                    JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE => {
+                        match serde_json::from_value::<RpcSimulateTransactionResult>(data) {
+                            Ok(data) => RpcResponseErrorData::SendTransactionPreflightFailure(data),
+                            Err(e) => {
+                                debug!("Failed to deserialize RpcSimulateTransactionResult: {}", e);
+                                RpcResponseErrorData::Empty
+                            }
+                        }
+                    }
+                    JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY => {
+                        match serde_json::from_value::<NodeUnhealthyErrorData>(data) {
+                            Ok(NodeUnhealthyErrorData { num_slots_behind }) => {
+                                RpcResponseErrorData::NodeUnhealthy { num_slots_behind }
+                            }
+                            Err(_) => RpcResponseErrorData::Empty,
+                        }
+                    }
+                    // The error data has no variant of its own, keep the slot in the message.
+                    JSON_RPC_SERVER_ERROR_MIN_CONTEXT_SLOT_NOT_REACHED => {
+                        if let Ok(MinContextSlotNotReachedErrorData { context_slot }) =
+                            serde_json::from_value(data)
+                        {
+                            message = format!("{} (context slot {})", message, context_slot);
+                        }
+                        RpcResponseErrorData::Empty
+                    }
+                    _ => RpcResponseErrorData::Empty,
+                };
+                RpcError::RpcResponseError {
+                    code: rpc_error_object.code,
+                    message,
+                    data,
+                }
+            }
+            Err(e) => RpcError::RpcRequestError(format!("Failed to deserialize RPC error: {}", e)),
+        };
+        return Err(ClientError::from(ClientErrorKind::RpcError(rpc_error)));
+    }
+
+    Ok(json["result"].take())
+}
+
+/// Copy of the error of a batch call for each of its requests, classified the same way.
+fn shared_error(e: &ClientError) -> ClientError {
+    let kind = match e.kind() {
+        ClientErrorKind::Middleware(middleware_error) => {
+            match middleware_error.downcast_ref::<HttpStatusError>() {
+                Some(status_error) => ClientErrorKind::Middleware(
+                    HttpStatusError {
+                        status:      status_error.status,
+                        retry_after: status_error.retry_after,
+                    }
+                    .into(),
+                ),
+                None => ClientErrorKind::Custom(e.to_string()),
+            }
+        }
+        ClientErrorKind::Reqwest(reqwest_error) => {
+            let kind = match reqwest_error.is_timeout() {
+                true => io::ErrorKind::TimedOut,
+                false => io::ErrorKind::Other,
+            };
+            ClientErrorKind::Io(io::Error::new(kind, reqwest_error.to_string()))
+        }
+        _ => ClientErrorKind::Custom(e.to_string()),
+    };
+    ClientError::from(kind)
+}
+
+fn batcher_stopped() -> ClientError {
+    ClientError::from(ClientErrorKind::Custom(
+        "JSON-RPC batching task stopped".to_string(),
+    ))
+}
+
+#[async_trait]
+impl RpcSender for HttpSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let start = Instant::now();
+        let result = self.post(request, params).await;
+
+        let mut stats = self.stats.write().unwrap();
+        stats.request_count += 1;
+        stats.elapsed_time += start.elapsed();
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.stats.read().unwrap().clone()
+    }
+
+    fn url(&self) -> String {
+        self.transport.url.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data_retrieval::error::{RetrievalError, RetrievalErrorKind},
+        test_fixtures,
+    };
+    use axum::{
+        http::{header, StatusCode},
+        response::{IntoResponse, Response},
+        Json,
+    };
+    use serde_json::json;
+    use std::sync::atomic::AtomicUsize;
+
+    static POSTS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Answers every request of a batch with its own id as result.
+    async fn echo_ids(Json(body): Json<Value>) -> Json<Value> {
+        POSTS.fetch_add(1, Ordering::SeqCst);
+        let respond = |request: &Value| json!({"jsonrpc": "2.0", "id": request["id"], "result": request["id"]});
+        match &body {
+            Value::Array(requests) => Json(Value::Array(requests.iter().map(respond).collect())),
+            request => Json(respond(request)),
+        }
+    }
+
+    #[test]
+    fn test_into_result_keeps_error_data() {
+        let unhealthy = into_result(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": {"code": -32005, "message": "Node is unhealthy", "data": {"numSlotsBehind": 12}}
+        }))
+        .unwrap_err();
+        let min_context_slot = into_result(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": {"code": -32016, "message": "Minimum context slot has not been reached", "data": {"contextSlot": 40}}
+        }))
+        .unwrap_err();
+
+        assert!(matches!(
+            unhealthy.kind(),
+            ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                data: RpcResponseErrorData::NodeUnhealthy {
+                    num_slots_behind: Some(12),
+                },
+                ..
+            })
+        ));
+        match min_context_slot.kind() {
+            ClientErrorKind::RpcError(RpcError::RpcResponseError { message, .. }) => assert_eq!(
+                message,
+                "Minimum context slot has not been reached (context slot 40)"
+            ),
+            kind => panic!("unexpected error {:?}", kind),
+        }
+    }
+
+    /// Sends two slot requests in one batch to a node answering batches with `batch_answer`
+    /// and single requests with their id, returning the results and the number of posts.
+    async fn send_batch(batch_answer: Response) -> (Vec<ClientResult<Value>>, usize) {
+        let posts = Arc::new(AtomicUsize::new(0));
+        let posts_clone = Arc::clone(&posts);
+        let batch_answer = Arc::new(std::sync::Mutex::new(Some(batch_answer)));
+        let answer = move |Json(body): Json<Value>| {
+            posts_clone.fetch_add(1, Ordering::SeqCst);
+            let batch_answer = Arc::clone(&batch_answer);
+            async move {
+                match body {
+                    Value::Array(_) => batch_answer.lock().unwrap().take().unwrap(),
+                    request => Json(
+                        json!({"jsonrpc": "2.0", "id": request["id"], "result": request["id"]}),
+                    )
+                    .into_response(),
+                }
+            }
+        };
+        let sender = HttpSender::new(
+            &test_fixtures::serve_rpc(answer).await,
+            Some(BatchConfig {
+                window_ms: 20,
+                max_size:  10,
+            }),
+        );
+
+        let results = join_all(
+            [RpcRequest::GetSlot, RpcRequest::GetSlot]
+                .into_iter()
+                .map(|request| sender.send(request, Value::Null)),
+        )
+        .await;
+        (results, posts.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_send_batch_falls_back_when_batches_are_unsupported() {
+        let rejection = Json(json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "error": {"code": -32600, "message": "batch requests are not supported"}
+        }));
+
+        let (results, posts) = send_batch(rejection.into_response()).await;
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(posts, 3);
+    }
+
+    #[tokio::test]
+    async fn test_send_batch_shares_status_error() {
+        let rate_limited = (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, "2")]);
+
+        let (results, posts) = send_batch(rate_limited.into_response()).await;
+
+        assert_eq!(posts, 1);
+        for result in results {
+            let e = RetrievalError::from(result.unwrap_err());
+            assert_eq!(e.kind(), RetrievalErrorKind::RateLimited);
+            assert_eq!(e.http_status, Some(429));
+            assert_eq!(e.retry_after, Some(Duration::from_secs(2)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_batches_concurrent_requests() {
+        let url = test_fixtures::serve_rpc(echo_ids).await;
+        let sender = HttpSender::new(
+            &url,
+            Some(BatchConfig {
+                window_ms: 20,
+                max_size:  10,
+            }),
+        );
+
+        let requests = [
+            RpcRequest::GetSlot,
+            RpcRequest::GetBlock,
+            RpcRequest::GetSlot,
+            RpcRequest::GetSlot,
+        ];
+        let results = join_all(
+            requests
+                .into_iter()
+                .map(|request| sender.send(request, Value::Null)),
+        )
+        .await;
+
+        let mut ids = results
+            .into_iter()
+            .map(|result| result.unwrap().as_u64().unwrap())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+        // The block is fetched on its own, the slots in one batch.
+        assert_eq!(POSTS.load(Ordering::SeqCst), 2);
+        assert_eq!(sender.get_transport_stats().request_count, 4);
+    }
+}
@@ -1,5 +1,14 @@
+pub mod cache;
+pub mod coalescer;
+pub mod error;
+pub mod http_sender;
 pub mod pub_sub_solana_client;
+pub mod rate_limiter;
+pub mod retry;
+pub mod rpc_pool;
 pub mod rpc_solana_client;
+pub mod stream_hub;
 
+pub use error::RetrievalError;
 pub use pub_sub_solana_client::PubSubSolanaClient;
 pub use rpc_solana_client::RpcSolanaClient;
@@ -1,44 +1,133 @@
+use crate::data_retrieval::{
+    error::RetrievalError,
+    rpc_pool::{redact_url, redact_urls},
+};
 use solana_account_decoder::UiAccountEncoding;
-use solana_pubsub_client::pubsub_client::{AccountSubscription, LogsSubscription, PubsubClient};
+use solana_pubsub_client::pubsub_client::{
+    AccountSubscription, LogsSubscription, ProgramSubscription, PubsubClient,
+    SignatureSubscription, SlotsSubscription,
+};
 use solana_rpc_client_api::config::{
-    RpcAccountInfoConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSignatureSubscribeConfig,
+    RpcTransactionLogsConfig, RpcTransactionLogsFilter,
 };
-use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
-use std::{error::Error, str::FromStr};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use std::str::FromStr;
+use tracing::warn;
 pub struct PubSubSolanaClient {
-    ws_url: String,
+    ws_urls: Vec<String>,
 }
 
 impl PubSubSolanaClient {
-    pub fn new(ws_url: String) -> Self {
-        PubSubSolanaClient { ws_url }
+    /// Subscriptions connect to the first of `ws_urls` that accepts them.
+    pub fn new(ws_urls: Vec<String>) -> Self {
+        PubSubSolanaClient { ws_urls }
     }
 
-    fn default_commitment() -> CommitmentConfig {
-        CommitmentConfig::confirmed()
+    fn subscribe<T>(
+        &self, subscribe: impl Fn(&str) -> Result<T, RetrievalError>,
+    ) -> Result<T, RetrievalError> {
+        let mut last_error = None;
+        for ws_url in &self.ws_urls {
+            match subscribe(ws_url) {
+                Ok(subscription) => return Ok(subscription),
+                Err(e) => {
+                    warn!(
+                        "Failed to subscribe through {}: {}",
+                        redact_url(ws_url),
+                        redact_urls(&e.to_string())
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| RetrievalError::invalid_input("no websocket endpoint configured")))
     }
 
     pub fn subscribe_account(
-        &self, pubkey_str: &str,
-    ) -> Result<AccountSubscription, Box<dyn Error>> {
-        let pubkey = Pubkey::from_str(pubkey_str)?;
+        &self, pubkey_str: &str, commitment: CommitmentConfig,
+    ) -> Result<AccountSubscription, RetrievalError> {
+        let pubkey = Pubkey::from_str(pubkey_str)
+            .map_err(|e| RetrievalError::invalid_pubkey(pubkey_str, e))?;
 
+        // Base64 keeps the raw account data decodable, like the RPC client does.
         let config = RpcAccountInfoConfig {
-            encoding:         Some(UiAccountEncoding::JsonParsed),
+            encoding:         Some(UiAccountEncoding::Base64),
             data_slice:       None,
-            commitment:       Some(Self::default_commitment()),
+            commitment:       Some(commitment),
             min_context_slot: None,
         };
 
-        PubsubClient::account_subscribe(&self.ws_url, &pubkey, Some(config)).map_err(Into::into)
+        self.subscribe(|ws_url| {
+            PubsubClient::account_subscribe(ws_url, &pubkey, Some(config.clone()))
+                .map_err(RetrievalError::from)
+        })
+    }
+
+    /// Subscribes to changes of every account owned by the program.
+    pub fn subscribe_program(
+        &self, program_id: &str, commitment: CommitmentConfig,
+    ) -> Result<ProgramSubscription, RetrievalError> {
+        let program_id = Pubkey::from_str(program_id)
+            .map_err(|e| RetrievalError::invalid_pubkey(program_id, e))?;
+
+        let config = RpcProgramAccountsConfig {
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(commitment),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+
+        self.subscribe(|ws_url| {
+            PubsubClient::program_subscribe(ws_url, &program_id, Some(config.clone()))
+                .map_err(RetrievalError::from)
+        })
     }
 
-    pub fn subscribe_logs(&self) -> Result<LogsSubscription, Box<dyn Error>> {
+    /// Subscribes to the transaction reaching `commitment`, notified once.
+    pub fn subscribe_signature(
+        &self, signature_str: &str, commitment: CommitmentConfig,
+    ) -> Result<SignatureSubscription, RetrievalError> {
+        let signature = Signature::from_str(signature_str)
+            .map_err(|e| RetrievalError::invalid_signature(signature_str, e))?;
+
+        let config = RpcSignatureSubscribeConfig {
+            commitment:                   Some(commitment),
+            enable_received_notification: Some(false),
+        };
+
+        self.subscribe(|ws_url| {
+            PubsubClient::signature_subscribe(ws_url, &signature, Some(config.clone()))
+                .map_err(RetrievalError::from)
+        })
+    }
+
+    pub fn subscribe_slot(&self) -> Result<SlotsSubscription, RetrievalError> {
+        self.subscribe(|ws_url| PubsubClient::slot_subscribe(ws_url).map_err(RetrievalError::from))
+    }
+
+    /// Subscribes to the logs of all transactions, or of those mentioning the given address.
+    pub fn subscribe_logs(
+        &self, mentions: Option<&str>, commitment: CommitmentConfig,
+    ) -> Result<LogsSubscription, RetrievalError> {
+        let filter = match mentions {
+            Some(address) => {
+                let address = Pubkey::from_str(address)
+                    .map_err(|e| RetrievalError::invalid_pubkey(address, e))?;
+                RpcTransactionLogsFilter::Mentions(vec![address.to_string()])
+            }
+            None => RpcTransactionLogsFilter::All,
+        };
         let config = RpcTransactionLogsConfig {
-            commitment: Some(Self::default_commitment()),
+            commitment: Some(commitment),
         };
 
-        PubsubClient::logs_subscribe(&self.ws_url, RpcTransactionLogsFilter::All, config)
-            .map_err(Into::into)
+        self.subscribe(|ws_url| {
+            PubsubClient::logs_subscribe(ws_url, filter.clone(), config.clone())
+                .map_err(RetrievalError::from)
+        })
     }
 }
@@ -0,0 +1,234 @@
+use crate::data_retrieval::error::{RetrievalError, RetrievalErrorKind};
+use chrono::{Datelike, Utc};
+use serde::Serialize;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use utoipa::ToSchema;
+
+/// Token bucket refilled at `requests_per_second` credits per second, with an optional monthly
+/// credit budget.
+///
+/// Callers that find the bucket empty sleep until enough credits are refilled, failing once
+/// `acquire_timeout` would be exceeded.
+pub struct RateLimiter {
+    requests_per_second: Option<f64>,
+    burst:               f64,
+    monthly_credits:     Option<u64>,
+    acquire_timeout:     Duration,
+    state:               Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens:       f64,
+    refilled_at:  Instant,
+    /// Month the credit counter belongs to, as `year * 12 + month`.
+    month:        i32,
+    credits_used: u64,
+    throttled:    u64,
+    rejected:     u64,
+}
+
+/// Consumed budget of one endpoint. Counters are kept in memory and restart with the process.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BudgetStatus {
+    pub requests_per_second: Option<f64>,
+    pub monthly_credits:     Option<u64>,
+    /// Credits consumed in the current month.
+    pub credits_used:        u64,
+    /// Calls that had to wait for credits.
+    pub throttled:           u64,
+    /// Calls that failed because no credits were available in time.
+    pub rejected:            u64,
+}
+
+impl RateLimiter {
+    pub fn new(
+        requests_per_second: Option<f64>, burst: Option<f64>, monthly_credits: Option<u64>,
+        acquire_timeout: Duration,
+    ) -> Self {
+        let burst = burst.or(requests_per_second).unwrap_or_default().max(1.0);
+        RateLimiter {
+            requests_per_second: requests_per_second.filter(|rps| *rps > 0.0),
+            burst,
+            monthly_credits,
+            acquire_timeout,
+            state: Mutex::new(BucketState {
+                tokens:       burst,
+                refilled_at:  Instant::now(),
+                month:        current_month(),
+                credits_used: 0,
+                throttled:    0,
+                rejected:     0,
+            }),
+        }
+    }
+
+    /// Waits until `cost` credits are available and consumes them.
+    pub async fn acquire(&self, cost: u32) -> Result<(), RetrievalError> {
+        let deadline = Instant::now() + self.acquire_timeout;
+        // A call costing more than the bucket holds only waits for a full bucket, then leaves it
+        // in debt for the rest of its cost.
+        let cost_f64 = f64::from(cost);
+        let tokens_needed = cost_f64.min(self.burst);
+        let mut waited = false;
+
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                state.roll_month(current_month());
+
+                if let Some(monthly_credits) = self.monthly_credits {
+                    if state.credits_used + u64::from(cost) > monthly_credits {
+                        state.rejected += 1;
+                        return Err(RetrievalError::new(
+                            RetrievalErrorKind::RateLimited,
+                            "monthly credit budget exhausted",
+                        ));
+                    }
+                }
+
+                let wait = match self.requests_per_second {
+                    Some(requests_per_second) => {
+                        state.refill(requests_per_second, self.burst);
+                        (state.tokens < tokens_needed).then(|| {
+                            Duration::from_secs_f64(
+                                (tokens_needed - state.tokens) / requests_per_second,
+                            )
+                        })
+                    }
+                    None => None,
+                };
+                match wait {
+                    Some(wait) if Instant::now() + wait > deadline => {
+                        state.rejected += 1;
+                        return Err(RetrievalError::new(
+                            RetrievalErrorKind::RateLimited,
+                            "timed out waiting for request budget",
+                        ));
+                    }
+                    Some(wait) => wait,
+                    None => {
+                        if self.requests_per_second.is_some() {
+                            state.tokens -= cost_f64;
+                        }
+                        state.credits_used += u64::from(cost);
+                        state.throttled += u64::from(waited);
+                        return Ok(());
+                    }
+                }
+            };
+
+            waited = true;
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Whether the monthly budget still has credits left.
+    pub fn has_budget(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.roll_month(current_month());
+        self.monthly_credits
+            .is_none_or(|monthly_credits| state.credits_used < monthly_credits)
+    }
+
+    pub fn status(&self) -> BudgetStatus {
+        let state = self.state.lock().unwrap();
+        BudgetStatus {
+            requests_per_second: self.requests_per_second,
+            monthly_credits:     self.monthly_credits,
+            credits_used:        state.credits_used,
+            throttled:           state.throttled,
+            rejected:            state.rejected,
+        }
+    }
+}
+
+impl BucketState {
+    fn refill(&mut self, requests_per_second: f64, burst: f64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * requests_per_second).min(burst);
+        self.refilled_at = now;
+    }
+
+    fn roll_month(&mut self, month: i32) {
+        if self.month != month {
+            self.month = month;
+            self.credits_used = 0;
+        }
+    }
+}
+
+fn current_month() -> i32 {
+    let now = Utc::now();
+    now.year() * 12 + now.month() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_unlimited() {
+        let limiter = RateLimiter::new(None, None, None, Duration::ZERO);
+
+        for _ in 0 .. 100 {
+            limiter.acquire(10).await.unwrap();
+        }
+
+        assert_eq!(limiter.status().credits_used, 1000);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_times_out_when_bucket_is_empty() {
+        let limiter = RateLimiter::new(Some(1.0), Some(2.0), None, Duration::ZERO);
+
+        limiter.acquire(2).await.unwrap();
+        let e = limiter.acquire(1).await.unwrap_err();
+
+        assert_eq!(e.kind(), RetrievalErrorKind::RateLimited);
+        assert!(!e.is_retryable());
+        assert_eq!(limiter.status().rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_refill() {
+        let limiter = RateLimiter::new(Some(100.0), Some(1.0), None, Duration::from_secs(1));
+
+        limiter.acquire(1).await.unwrap();
+        limiter.acquire(1).await.unwrap();
+
+        assert_eq!(limiter.status().throttled, 1);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_above_burst_leaves_bucket_in_debt() {
+        let limiter = RateLimiter::new(Some(100.0), Some(1.0), None, Duration::from_secs(1));
+
+        limiter.acquire(5).await.unwrap();
+        let started = Instant::now();
+        limiter.acquire(1).await.unwrap();
+
+        // The next call waits for the 4 credits owed plus its own.
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_eq!(limiter.status().throttled, 1);
+
+        let limiter = RateLimiter::new(Some(1.0), Some(1.0), None, Duration::ZERO);
+        limiter.acquire(5).await.unwrap();
+        assert!(limiter.acquire(1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_monthly_budget() {
+        let limiter = RateLimiter::new(None, None, Some(15), Duration::ZERO);
+
+        limiter.acquire(10).await.unwrap();
+        assert!(limiter.has_budget());
+        assert!(limiter.acquire(10).await.is_err());
+
+        limiter.acquire(5).await.unwrap();
+        assert!(!limiter.has_budget());
+    }
+}
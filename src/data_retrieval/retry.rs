@@ -0,0 +1,195 @@
+use crate::{
+    config::config::RetryConfig,
+    data_retrieval::error::{RetrievalError, RetrievalErrorKind},
+};
+use rand::Rng;
+use serde::Serialize;
+use std::{collections::BTreeMap, future::Future, sync::Mutex, time::Duration};
+use tracing::warn;
+use utoipa::ToSchema;
+
+/// Counters of one RPC method, accumulated since startup.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct CallMetrics {
+    /// Logical calls, each made of one or more attempts.
+    pub calls:           u64,
+    pub attempts:        u64,
+    pub retries:         u64,
+    pub successes:       u64,
+    pub failures:        u64,
+    pub last_error_kind: Option<RetrievalErrorKind>,
+}
+
+/// Retries failed RPC calls with exponential backoff and records per-method metrics.
+pub struct RetryPolicy {
+    config:  RetryConfig,
+    metrics: Mutex<BTreeMap<&'static str, CallMetrics>>,
+}
+
+impl RetryPolicy {
+    pub fn new(config: RetryConfig) -> Self {
+        RetryPolicy {
+            config,
+            metrics: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Runs `call` until it succeeds, fails with a non-retryable error or runs out of attempts.
+    pub async fn run<T, F, Fut>(
+        &self, method: &'static str, mut call: F,
+    ) -> Result<T, RetrievalError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, RetrievalError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Ok(value) => {
+                    self.record(method, attempt, None);
+                    return Ok(value);
+                }
+                Err(e) if attempt < self.config.max_attempts && self.should_retry(&e) => {
+                    let delay = self.delay(attempt, e.retry_after);
+                    warn!(
+                        "{} attempt {} failed, retrying in {:?}: {}",
+                        method, attempt, delay, e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    self.record(method, attempt, Some(e.kind()));
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    pub fn metrics(&self) -> BTreeMap<&'static str, CallMetrics> {
+        self.metrics.lock().unwrap().clone()
+    }
+
+    fn should_retry(&self, e: &RetrievalError) -> bool {
+        e.is_retryable() && self.config.retryable_errors.contains(&e.kind())
+    }
+
+    fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let max_delay = Duration::from_millis(self.config.max_delay_ms);
+        if let Some(retry_after) = retry_after.filter(|_| self.config.respect_retry_after) {
+            return retry_after.min(max_delay);
+        }
+
+        let backoff = Duration::from_millis(self.config.base_delay_ms)
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(max_delay);
+        if self.config.jitter {
+            backoff.mul_f64(rand::thread_rng().gen::<f64>())
+        } else {
+            backoff
+        }
+    }
+
+    fn record(&self, method: &'static str, attempts: u32, error: Option<RetrievalErrorKind>) {
+        let mut metrics = self.metrics.lock().unwrap();
+        let call_metrics = metrics.entry(method).or_default();
+        call_metrics.calls += 1;
+        call_metrics.attempts += u64::from(attempts);
+        call_metrics.retries += u64::from(attempts - 1);
+        match error {
+            Some(kind) => {
+                call_metrics.failures += 1;
+                call_metrics.last_error_kind = Some(kind);
+            }
+            None => call_metrics.successes += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn retry_config() -> RetryConfig {
+        RetryConfig {
+            base_delay_ms: 1,
+            max_delay_ms: 10,
+            jitter: false,
+            ..RetryConfig::default()
+        }
+    }
+
+    fn rate_limited() -> RetrievalError {
+        let mut e = RetrievalError::new(RetrievalErrorKind::RateLimited, "429");
+        e.retryable = true;
+        e
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_until_success() {
+        let policy = RetryPolicy::new(retry_config());
+        let calls = AtomicU32::new(0);
+
+        let result = policy
+            .run("getAccountInfo", || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(rate_limited()),
+                    _ => Ok(42),
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 42);
+        let metrics = &policy.metrics()["getAccountInfo"];
+        assert_eq!(metrics.attempts, 2);
+        assert_eq!(metrics.retries, 1);
+        assert_eq!(metrics.successes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_does_not_retry_non_retryable_errors() {
+        let policy = RetryPolicy::new(retry_config());
+        let calls = AtomicU32::new(0);
+
+        let result: Result<(), _> = policy
+            .run("getTransaction", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(RetrievalError::not_found("transaction"))
+            })
+            .await;
+
+        assert_eq!(result.unwrap_err().kind(), RetrievalErrorKind::NotFound);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(policy.metrics()["getTransaction"].failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_after_max_attempts() {
+        let policy = RetryPolicy::new(retry_config());
+        let calls = AtomicU32::new(0);
+
+        let result: Result<(), _> = policy
+            .run("getBlock", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(rate_limited())
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::new(retry_config());
+
+        assert_eq!(policy.delay(1, None), Duration::from_millis(1));
+        assert_eq!(policy.delay(3, None), Duration::from_millis(4));
+        assert_eq!(policy.delay(10, None), Duration::from_millis(10));
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(60))),
+            Duration::from_millis(10)
+        );
+    }
+}
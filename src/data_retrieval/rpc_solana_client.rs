@@ -1,79 +1,642 @@
-use solana_account_decoder::UiAccountEncoding;
-use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig};
+use crate::{
+    config::config::{CacheConfig, EndpointConfig, PoolConfig, RateLimitConfig, RetryConfig},
+    data_retrieval::{
+        cache::{CacheMetrics, CacheTtl, ResponseCache},
+        coalescer::Coalescer,
+        error::RetrievalError,
+        retry::{CallMetrics, RetryPolicy},
+        rpc_pool::{EndpointStatus, RpcPool},
+    },
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use solana_account_decoder::{UiAccount, UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{
+        RpcAccountInfoConfig, RpcBlockConfig, RpcProgramAccountsConfig, RpcTokenAccountsFilter,
+        RpcTransactionConfig,
+    },
+    rpc_filter::RpcFilterType,
+    rpc_request::{RpcRequest, MAX_MULTIPLE_ACCOUNTS},
+    rpc_response::{
+        OptionalContext, Response, RpcConfirmedTransactionStatusWithSignature, RpcKeyedAccount,
+        RpcResponseContext,
+    },
+};
 use solana_sdk::{
-    account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+    account::Account,
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+    signature::Signature,
 };
 use solana_transaction_status::{
     self, EncodedConfirmedBlock, EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding,
 };
-use std::{error::Error, str::FromStr, sync::Arc};
+use std::{collections::BTreeMap, future::Future, str::FromStr, sync::Arc, time::Duration};
+use tokio::task::JoinHandle;
+
+/// Response of the node with the Unix timestamp at which it arrived, which precedes the time it
+/// is served when it comes from the cache.
+#[derive(Debug)]
+pub struct Fetched<T> {
+    pub context:    RpcResponseContext,
+    pub value:      T,
+    pub fetched_at: i64,
+}
 
 pub struct RpcSolanaClient {
-    rpc_client: Arc<RpcClient>,
+    pool:         Arc<RpcPool>,
+    retry_policy: RetryPolicy,
+    /// Concurrent lookups of the same transaction, account or block share one RPC call, keyed by
+    /// method and parameter. Results are shared as JSON since the decoded types are not `Clone`,
+    /// along with the time they arrived.
+    in_flight:    Coalescer<(Value, i64)>,
+    cache:        ResponseCache,
+    cache_config: CacheConfig,
+    /// Commitment used when the caller does not request one.
+    commitment:   CommitmentConfig,
 }
 
 impl RpcSolanaClient {
-    pub fn new(rpc_url: &str) -> Self {
-        let rpc_client =
-            RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed());
+    pub fn new(
+        endpoints: &[EndpointConfig], pool_config: PoolConfig, rate_limit_config: RateLimitConfig,
+        retry_config: RetryConfig, cache_config: CacheConfig, commitment: CommitmentLevel,
+    ) -> Self {
         RpcSolanaClient {
-            rpc_client: Arc::new(rpc_client),
+            pool: Arc::new(RpcPool::new(endpoints, pool_config, rate_limit_config)),
+            retry_policy: RetryPolicy::new(retry_config),
+            in_flight: Coalescer::new(),
+            cache: ResponseCache::new(cache_config.max_entries, cache_config.max_bytes),
+            cache_config,
+            commitment: CommitmentConfig { commitment },
+        }
+    }
+
+    pub fn cache_metrics(&self) -> CacheMetrics {
+        self.cache.metrics()
+    }
+
+    /// Resolves the commitment requested by a caller, falling back to the configured default.
+    pub fn commitment(&self, requested: Option<CommitmentLevel>) -> CommitmentConfig {
+        requested.map_or(self.commitment, |commitment| CommitmentConfig {
+            commitment,
+        })
+    }
+
+    /// The configured default raised to `confirmed`, the lowest commitment transactions can be
+    /// fetched at.
+    pub fn transaction_commitment(&self) -> CommitmentConfig {
+        if self.commitment.is_at_least_confirmed() {
+            self.commitment
+        } else {
+            CommitmentConfig::confirmed()
+        }
+    }
+
+    /// Drops the cached state of an account at every commitment, called when a subscription
+    /// reports a change.
+    pub fn invalidate_account(&self, pubkey: &str) {
+        for commitment in [
+            CommitmentConfig::processed(),
+            CommitmentConfig::confirmed(),
+            CommitmentConfig::finalized(),
+        ] {
+            self.cache
+                .invalidate(&cache_key("getAccountInfo", commitment, pubkey));
         }
     }
 
-    pub async fn get_recent_blockhash(&self) -> Result<String, Box<dyn Error>> {
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
+    /// Serves `key` from the cache unless the cached response is older than `min_context_slot`,
+    /// otherwise runs `call` once for all concurrent callers asking for the same key and minimum
+    /// slot, and caches the response for the lifetime chosen by `ttl`.
+    ///
+    /// Returns the response with the Unix timestamp at which the node answered.
+    async fn fetch_cached<F, Fut>(
+        &self, key: String, min_context_slot: Option<u64>, ttl: impl FnOnce(&Value) -> CacheTtl,
+        call: F,
+    ) -> Result<(Value, i64), RetrievalError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value, RetrievalError>>,
+    {
+        let fresh = |response: &Value| {
+            min_context_slot.is_none_or(|min_context_slot| {
+                response["context"]["slot"]
+                    .as_u64()
+                    .is_some_and(|slot| slot >= min_context_slot)
+            })
+        };
+        if let Some(cached) = self.cache.get(&key).filter(|(value, _)| fresh(value)) {
+            return Ok(cached);
+        }
+
+        // A caller with a minimum slot must not be handed the response of a call made without it.
+        let in_flight_key = match min_context_slot {
+            Some(min_context_slot) => format!("{}:{}", key, min_context_slot),
+            None => key.clone(),
+        };
+        let (value, fetched_at) = self
+            .in_flight
+            .run(in_flight_key, || async {
+                Ok((call().await?, Utc::now().timestamp()))
+            })
+            .await?;
+        self.cache
+            .insert(key, value.clone(), fetched_at, ttl(&value));
+        Ok((value, fetched_at))
+    }
+
+    /// Starts the background health checks of the endpoint pool.
+    pub fn spawn_health_checks(&self) -> JoinHandle<()> {
+        Arc::clone(&self.pool).spawn_health_checks()
+    }
+
+    pub fn endpoint_status(&self) -> Vec<EndpointStatus> {
+        self.pool.status()
+    }
+
+    /// Websocket URLs of the pool, healthy endpoints first.
+    pub fn ws_urls(&self) -> Vec<String> {
+        self.pool.ws_urls()
+    }
+
+    /// Per-method call metrics, keyed by RPC method name.
+    pub fn metrics(&self) -> BTreeMap<&'static str, CallMetrics> {
+        self.retry_policy.metrics()
+    }
+
+    pub async fn get_recent_blockhash(&self) -> Result<String, RetrievalError> {
+        let (recent_blockhash, _) = self
+            .retry_policy
+            .run("getLatestBlockhash", || async {
+                let endpoint = self.pool.acquire("getLatestBlockhash").await?;
+                let result = endpoint
+                    .client
+                    .get_latest_blockhash_with_commitment(self.commitment)
+                    .await;
+                self.pool.report(&endpoint, result)
+            })
+            .await?;
         Ok(recent_blockhash.to_string())
     }
 
     pub async fn get_transaction(
-        &self, signature: &str,
-    ) -> Result<EncodedConfirmedTransactionWithStatusMeta, Box<dyn Error>> {
-        let signature = Signature::from_str(signature)?;
-        self.rpc_client
-            .get_transaction(&signature, UiTransactionEncoding::JsonParsed)
-            .await
-            .map_err(|e| {
-                eprintln!("Failed to fetch the transaction: {:?}", e);
-                "Failed to decode transaction. The transaction might be corrupted or unsupported."
-                    .into()
-            })
+        &self, signature_str: &str, commitment: CommitmentConfig,
+    ) -> Result<EncodedConfirmedTransactionWithStatusMeta, RetrievalError> {
+        let signature = Signature::from_str(signature_str)
+            .map_err(|e| RetrievalError::invalid_signature(signature_str, e))?;
+        at_least_confirmed("getTransaction", commitment)?;
+
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::JsonParsed),
+            commitment: Some(commitment),
+            max_supported_transaction_version: None,
+        };
+
+        // The node answers `null` for unknown signatures, which the client helper reports as a
+        // deserialization error.
+        let (transaction, _) = self
+            .fetch_cached(
+                cache_key("getTransaction", commitment, &signature.to_string()),
+                None,
+                |transaction| self.finality_ttl(commitment, transaction["slot"].as_u64()),
+                || {
+                    self.retry_policy.run("getTransaction", || async {
+                        let endpoint = self.pool.acquire("getTransaction").await?;
+                        let result = endpoint
+                            .client
+                            .send(
+                                RpcRequest::GetTransaction,
+                                json!([signature.to_string(), config]),
+                            )
+                            .await;
+                        self.pool.report(&endpoint, result)
+                    })
+                },
+            )
+            .await?;
+        let transaction: Option<EncodedConfirmedTransactionWithStatusMeta> =
+            serde_json::from_value(transaction).map_err(|e| {
+                RetrievalError::decode_failure(format!("transaction {}: {}", signature_str, e))
+            })?;
+
+        transaction.ok_or_else(|| {
+            RetrievalError::not_found(format!("transaction {} not found", signature_str))
+        })
+    }
+
+    pub async fn get_account(
+        &self, pubkey_str: &str, commitment: CommitmentConfig, min_context_slot: Option<u64>,
+    ) -> Result<Fetched<Account>, RetrievalError> {
+        let response = self
+            .get_optional_account(pubkey_str, commitment, min_context_slot)
+            .await?;
+        match response.value {
+            Some(account) => Ok(Fetched {
+                context:    response.context,
+                value:      account,
+                fetched_at: response.fetched_at,
+            }),
+            None => Err(RetrievalError::not_found(format!(
+                "account {} not found",
+                pubkey_str
+            ))),
+        }
     }
 
-    pub async fn get_account(&self, pubkey_str: &str) -> Result<Account, Box<dyn Error>> {
-        let pubkey = Pubkey::from_str(pubkey_str)?;
+    /// Responses older than `min_context_slot` are fetched again, cached ones included.
+    pub async fn get_optional_account(
+        &self, pubkey_str: &str, commitment: CommitmentConfig, min_context_slot: Option<u64>,
+    ) -> Result<Fetched<Option<Account>>, RetrievalError> {
+        let pubkey = Pubkey::from_str(pubkey_str)
+            .map_err(|e| RetrievalError::invalid_pubkey(pubkey_str, e))?;
 
-        let commitment_config = CommitmentConfig::processed();
+        // Base64 keeps the raw account data decodable for every owner, including token accounts
+        // which the node would otherwise return as parsed JSON.
         let config = RpcAccountInfoConfig {
-            encoding: Some(UiAccountEncoding::JsonParsed),
-            commitment: Some(commitment_config),
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(commitment),
+            min_context_slot,
             ..RpcAccountInfoConfig::default()
         };
 
-        let response = self
-            .rpc_client
-            .get_account_with_config(&pubkey, config)
-            .await
-            .map_err(|e| {
-                eprintln!("Failed to fetch the account: {:?}", e);
-                Box::new(e) as Box<dyn Error>
+        // The client helper reports every failure as "AccountNotFound", send the request directly
+        // to keep the underlying error.
+        let ttl =
+            |_: &Value| CacheTtl::Expire(Duration::from_millis(self.cache_config.account_ttl_ms));
+        let (response, fetched_at) = self
+            .fetch_cached(
+                cache_key("getAccountInfo", commitment, &pubkey.to_string()),
+                min_context_slot,
+                ttl,
+                || {
+                    self.retry_policy.run("getAccountInfo", || async {
+                        let endpoint = self.pool.acquire("getAccountInfo").await?;
+                        let result = endpoint
+                            .client
+                            .send(
+                                RpcRequest::GetAccountInfo,
+                                json!([pubkey.to_string(), config]),
+                            )
+                            .await;
+                        let response: Value = self.pool.report(&endpoint, result)?;
+                        check_context_slot(
+                            response["context"]["slot"].as_u64().unwrap_or_default(),
+                            min_context_slot,
+                        )?;
+                        Ok(response)
+                    })
+                },
+            )
+            .await?;
+        let response: Response<Option<UiAccount>> =
+            serde_json::from_value(response).map_err(|e| {
+                RetrievalError::decode_failure(format!("account {}: {}", pubkey_str, e))
             })?;
 
-        match response.value {
-            Some(account) => Ok(account),
-            None => Err("Account not found.".into()),
+        let account = match response.value {
+            Some(ui_account) => Some(ui_account.decode().ok_or_else(|| {
+                RetrievalError::decode_failure(format!("account {} data", pubkey_str))
+            })?),
+            None => None,
+        };
+        Ok(Fetched {
+            context: response.context,
+            value: account,
+            fetched_at,
+        })
+    }
+
+    /// Fetches accounts in chunks of the RPC's `getMultipleAccounts` key limit, returning them in
+    /// the order of `pubkeys` with `None` for accounts that do not exist.
+    ///
+    /// The context is the oldest one among the chunks.
+    pub async fn get_multiple_accounts(
+        &self, pubkeys: &[String], commitment: CommitmentConfig, min_context_slot: Option<u64>,
+    ) -> Result<Fetched<Vec<Option<Account>>>, RetrievalError> {
+        let pubkeys = pubkeys
+            .iter()
+            .map(|pubkey| {
+                Pubkey::from_str(pubkey).map_err(|e| RetrievalError::invalid_pubkey(pubkey, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(commitment),
+            min_context_slot,
+            ..RpcAccountInfoConfig::default()
+        };
+
+        let mut context: Option<RpcResponseContext> = None;
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let response = self
+                .retry_policy
+                .run("getMultipleAccounts", || async {
+                    let endpoint = self.pool.acquire("getMultipleAccounts").await?;
+                    let result = endpoint
+                        .client
+                        .get_multiple_accounts_with_config(chunk, config.clone())
+                        .await;
+                    let response = self.pool.report(&endpoint, result)?;
+                    check_context_slot(response.context.slot, min_context_slot)?;
+                    Ok(response)
+                })
+                .await?;
+            if context
+                .as_ref()
+                .is_none_or(|context| response.context.slot < context.slot)
+            {
+                context = Some(response.context);
+            }
+            accounts.extend(response.value);
         }
+
+        Ok(Fetched {
+            context:    context.unwrap_or(RpcResponseContext {
+                slot:        0,
+                api_version: None,
+            }),
+            value:      accounts,
+            fetched_at: Utc::now().timestamp(),
+        })
+    }
+
+    /// Returns all accounts owned by the program that match every filter, with only the
+    /// `data_slice` portion of their data when one is given.
+    pub async fn get_program_accounts(
+        &self, program_id: &str, filters: Vec<RpcFilterType>,
+        data_slice: Option<UiDataSliceConfig>, commitment: CommitmentConfig,
+        min_context_slot: Option<u64>,
+    ) -> Result<Fetched<Vec<(Pubkey, Account)>>, RetrievalError> {
+        let program_id = Pubkey::from_str(program_id)
+            .map_err(|e| RetrievalError::invalid_pubkey(program_id, e))?;
+
+        let config = RpcProgramAccountsConfig {
+            filters:        (!filters.is_empty()).then_some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice,
+                commitment: Some(commitment),
+                min_context_slot,
+            },
+            with_context:   Some(true),
+            sort_results:   None,
+        };
+
+        // The client helper drops the context, send the request directly to keep its slot.
+        let response: OptionalContext<Vec<RpcKeyedAccount>> = self
+            .retry_policy
+            .run("getProgramAccounts", || async {
+                let endpoint = self.pool.acquire("getProgramAccounts").await?;
+                let result = endpoint
+                    .client
+                    .send(
+                        RpcRequest::GetProgramAccounts,
+                        json!([program_id.to_string(), config]),
+                    )
+                    .await;
+                let response = self.pool.report(&endpoint, result)?;
+                if let OptionalContext::Context(response) = &response {
+                    check_context_slot(response.context.slot, min_context_slot)?;
+                }
+                Ok(response)
+            })
+            .await?;
+
+        match response {
+            OptionalContext::Context(response) => Ok(decode_keyed_accounts(response)),
+            OptionalContext::NoContext(_) => Err(RetrievalError::decode_failure(format!(
+                "accounts of program {} returned without context",
+                program_id
+            ))),
+        }
+    }
+
+    /// Returns the token accounts of `owner` managed by the given token program.
+    pub async fn get_token_accounts_by_owner(
+        &self, owner: &str, program_id: &Pubkey, commitment: CommitmentConfig,
+        min_context_slot: Option<u64>,
+    ) -> Result<Fetched<Vec<(Pubkey, Account)>>, RetrievalError> {
+        let owner =
+            Pubkey::from_str(owner).map_err(|e| RetrievalError::invalid_pubkey(owner, e))?;
+
+        // The client helper forces jsonParsed, request base64 so the raw layout can be processed.
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(commitment),
+            min_context_slot,
+            ..RpcAccountInfoConfig::default()
+        };
+        let response: Response<Vec<RpcKeyedAccount>> = self
+            .retry_policy
+            .run("getTokenAccountsByOwner", || async {
+                let endpoint = self.pool.acquire("getTokenAccountsByOwner").await?;
+                let result = endpoint
+                    .client
+                    .send(
+                        RpcRequest::GetTokenAccountsByOwner,
+                        json!([
+                            owner.to_string(),
+                            RpcTokenAccountsFilter::ProgramId(program_id.to_string()),
+                            config
+                        ]),
+                    )
+                    .await;
+                let response: Response<Vec<RpcKeyedAccount>> =
+                    self.pool.report(&endpoint, result)?;
+                check_context_slot(response.context.slot, min_context_slot)?;
+                Ok(response)
+            })
+            .await?;
+
+        Ok(decode_keyed_accounts(response))
     }
 
     pub async fn get_transaction_by_slot(
-        &self, slot: u64,
-    ) -> Result<EncodedConfirmedBlock, Box<dyn Error>> {
-        self.rpc_client
-            .get_block_with_encoding(slot, UiTransactionEncoding::JsonParsed)
+        &self, slot: u64, commitment: CommitmentConfig,
+    ) -> Result<EncodedConfirmedBlock, RetrievalError> {
+        at_least_confirmed("getBlock", commitment)?;
+
+        let config = RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::JsonParsed),
+            commitment: Some(commitment),
+            ..RpcBlockConfig::default()
+        };
+        let (block, _) = self
+            .fetch_cached(
+                cache_key("getBlock", commitment, &slot.to_string()),
+                None,
+                |_| self.finality_ttl(commitment, Some(slot)),
+                || {
+                    self.retry_policy.run("getBlock", || async {
+                        let endpoint = self.pool.acquire("getBlock").await?;
+                        let result = endpoint
+                            .client
+                            .send(RpcRequest::GetBlock, json!([slot, config]))
+                            .await;
+                        self.pool.report(&endpoint, result)
+                    })
+                },
+            )
+            .await?;
+
+        serde_json::from_value(block)
+            .map_err(|e| RetrievalError::decode_failure(format!("block {}: {}", slot, e)))
+    }
+
+    /// Returns signatures involving the address, newest first, starting before `before` and
+    /// stopping at `until`.
+    pub async fn get_signatures_for_address(
+        &self, address: &str, before: Option<&str>, until: Option<&str>, limit: Option<usize>,
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, RetrievalError> {
+        let parse_signature = |signature: &str| {
+            Signature::from_str(signature)
+                .map_err(|e| RetrievalError::invalid_signature(signature, e))
+        };
+        let before = before.map(parse_signature).transpose()?;
+        let until = until.map(parse_signature).transpose()?;
+        let address =
+            Pubkey::from_str(address).map_err(|e| RetrievalError::invalid_pubkey(address, e))?;
+        at_least_confirmed("getSignaturesForAddress", commitment)?;
+
+        self.retry_policy
+            .run("getSignaturesForAddress", || async {
+                let config = GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit,
+                    commitment: Some(commitment),
+                };
+                let endpoint = self.pool.acquire("getSignaturesForAddress").await?;
+                let result = endpoint
+                    .client
+                    .get_signatures_for_address_with_config(&address, config)
+                    .await;
+                self.pool.report(&endpoint, result)
+            })
             .await
-            .map_err(|e| {
-                eprintln!("Failed to fetch block: {:?}", e);
-                "Failed to decode Block.".into()
+    }
+
+    /// Responses at or below the finalized slot can no longer change.
+    fn finality_ttl(&self, commitment: CommitmentConfig, slot: Option<u64>) -> CacheTtl {
+        match slot {
+            None => CacheTtl::Skip,
+            Some(_) if commitment.is_finalized() => CacheTtl::Forever,
+            Some(slot) if slot <= self.pool.finalized_slot() => CacheTtl::Forever,
+            Some(_) => CacheTtl::Expire(Duration::from_secs(self.cache_config.confirmed_ttl_secs)),
+        }
+    }
+}
+
+fn cache_key(method: &str, commitment: CommitmentConfig, key: &str) -> String {
+    format!("{}:{:?}:{}", method, commitment.commitment, key)
+}
+
+/// Transaction history is only served from confirmed blocks onwards.
+fn at_least_confirmed(method: &str, commitment: CommitmentConfig) -> Result<(), RetrievalError> {
+    if commitment.is_at_least_confirmed() {
+        return Ok(());
+    }
+    Err(RetrievalError::invalid_input(format!(
+        "{} requires confirmed or finalized commitment",
+        method
+    )))
+}
+
+/// Rejects responses from nodes that did not honour `min_context_slot`, checked within the retried
+/// call so that another attempt can reach a node that caught up.
+fn check_context_slot(
+    context_slot: u64, min_context_slot: Option<u64>,
+) -> Result<(), RetrievalError> {
+    match min_context_slot {
+        Some(min_context_slot) if context_slot < min_context_slot => Err(
+            RetrievalError::stale_context(context_slot, min_context_slot),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Decodes the accounts of a response that just arrived.
+fn decode_keyed_accounts(
+    response: Response<Vec<RpcKeyedAccount>>,
+) -> Fetched<Vec<(Pubkey, Account)>> {
+    Fetched {
+        context:    response.context,
+        fetched_at: Utc::now().timestamp(),
+        value:      response
+            .value
+            .into_iter()
+            .filter_map(|keyed_account| {
+                let pubkey = Pubkey::from_str(&keyed_account.pubkey).ok()?;
+                let account = keyed_account.account.decode()?;
+                Some((pubkey, account))
             })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+    use axum::Json;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Client of a local node answering `getAccountInfo` from slot 10 twice, then from slot 20.
+    async fn lagging_node() -> (Arc<RpcSolanaClient>, Arc<AtomicU64>) {
+        let calls = Arc::new(AtomicU64::new(0));
+        let answered = Arc::clone(&calls);
+        let answer = move |Json(request): Json<Value>| async move {
+            let slot = match answered.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => 10,
+                _ => 20,
+            };
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": {
+                    "context": {"slot": slot},
+                    "value": {
+                        "lamports": slot,
+                        "data": ["", "base64"],
+                        "owner": "11111111111111111111111111111111",
+                        "executable": false,
+                        "rentEpoch": 0,
+                        "space": 0
+                    }
+                }
+            }))
+        };
+        let client = test_fixtures::rpc_client(&test_fixtures::serve_rpc(answer).await);
+        (client, calls)
+    }
+
+    #[tokio::test]
+    async fn test_get_account_min_context_slot() {
+        let (client, calls) = lagging_node().await;
+        let pubkey = Pubkey::new_unique().to_string();
+        let commitment = CommitmentConfig::confirmed();
+
+        // The second lookup neither joins the first call nor accepts its stale answer.
+        let (first, second) = tokio::join!(
+            client.get_optional_account(&pubkey, commitment, None),
+            client.get_optional_account(&pubkey, commitment, Some(15)),
+        );
+        let cached = client
+            .get_optional_account(&pubkey, commitment, None)
+            .await
+            .unwrap();
+
+        assert_eq!(first.unwrap().context.slot, 10);
+        let second = second.unwrap();
+        assert_eq!(second.context.slot, 20);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(cached.context.slot, 20);
+        assert_eq!(cached.fetched_at, second.fetched_at);
     }
 }
@@ -0,0 +1,541 @@
+use crate::{
+    config::config::StreamConfig,
+    data_retrieval::{
+        error::{RetrievalError, RetrievalErrorKind},
+        rpc_pool::redact_urls,
+        PubSubSolanaClient, RpcSolanaClient,
+    },
+};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use solana_pubsub_client::pubsub_client::PubsubClientSubscription;
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use tokio::sync::broadcast;
+use tracing::warn;
+use utoipa::ToSchema;
+
+/// How often a subscription thread checks whether anybody still listens to it.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Delay before subscribing again after the websocket failed or disconnected.
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Subscription updates can be streamed from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Topic {
+    Account(String),
+    /// Changes of every account owned by the program.
+    Program(String),
+    /// Logs of all transactions, or of those mentioning the address.
+    Logs(Option<String>),
+    /// The transaction reaching the commitment, notified once.
+    Signature(String),
+    Slot,
+}
+
+impl Topic {
+    /// Name of the topic as reported by the status endpoint, e.g. `account:<pubkey>`.
+    pub fn label(&self) -> String {
+        match self {
+            Topic::Account(pubkey) => format!("account:{}", pubkey),
+            Topic::Program(program_id) => format!("program:{}", program_id),
+            Topic::Logs(None) => "logs".to_string(),
+            Topic::Logs(Some(mentions)) => format!("logs:{}", mentions),
+            Topic::Signature(signature) => format!("signature:{}", signature),
+            Topic::Slot => "slot".to_string(),
+        }
+    }
+}
+
+/// State of one shared subscription.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SubscriptionStatus {
+    pub topic:         String,
+    #[schema(value_type = String)]
+    pub commitment:    CommitmentLevel,
+    /// Whether the websocket subscription is currently established.
+    pub connected:     bool,
+    pub listeners:     usize,
+    /// Unix timestamp of the last update received.
+    pub last_event_at: Option<i64>,
+    /// Last error subscribing, kept after the subscription recovers.
+    pub last_error:    Option<String>,
+    /// Whether the service itself listens, for webhooks, alerts or its logs and account feeds.
+    pub internal:      bool,
+    /// Unix timestamp at which the subscription was first requested.
+    pub opened_at:     i64,
+}
+
+/// Update received from a subscription.
+///
+/// Ids increase across all topics, so a listener can resume from the last id it has seen.
+#[derive(Debug, Clone)]
+pub struct StreamEvent {
+    pub id:   u64,
+    pub data: Value,
+}
+
+type ChannelKey = (Topic, CommitmentConfig);
+
+struct Channel {
+    sender:        broadcast::Sender<StreamEvent>,
+    /// Most recent events, oldest first, replayed to resuming listeners.
+    recent:        Mutex<VecDeque<StreamEvent>>,
+    connected:     AtomicBool,
+    /// Unix timestamp of the last event, zero before the first one.
+    last_event_at: AtomicI64,
+    last_error:    Mutex<Option<String>>,
+    /// Set once the service subscribes itself, for as long as the channel lives.
+    internal:      AtomicBool,
+    opened_at:     i64,
+}
+
+/// Shares one websocket subscription per topic and commitment between any number of listeners.
+///
+/// Each subscription runs on its own thread since the pubsub client is blocking. It is started by
+/// the first listener, re-established when the websocket disconnects, and dropped once the last
+/// listener is gone. Every account update drops the cached state of the account.
+pub struct StreamHub {
+    pub_sub:           Arc<PubSubSolanaClient>,
+    rpc_solana_client: Arc<RpcSolanaClient>,
+    config:            StreamConfig,
+    next_id:           AtomicU64,
+    channels:          Mutex<HashMap<ChannelKey, Arc<Channel>>>,
+}
+
+impl StreamHub {
+    pub fn new(
+        pub_sub: Arc<PubSubSolanaClient>, rpc_solana_client: Arc<RpcSolanaClient>,
+        config: StreamConfig,
+    ) -> Self {
+        StreamHub {
+            pub_sub,
+            rpc_solana_client,
+            config,
+            next_id: AtomicU64::new(1),
+            channels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.config.heartbeat_secs)
+    }
+
+    /// Listens to `topic`, starting its subscription if nobody listens to it yet.
+    ///
+    /// Returns the buffered events newer than `last_event_id`, followed by the receiver of the
+    /// events to come. Fails with `rate_limited` when the topic would exceed `max_channels`.
+    pub fn subscribe(
+        self: &Arc<Self>, topic: Topic, commitment: CommitmentConfig, last_event_id: Option<u64>,
+    ) -> Result<(Vec<StreamEvent>, broadcast::Receiver<StreamEvent>), RetrievalError> {
+        self.listen(topic, commitment, last_event_id, false)
+    }
+
+    /// Listens to `topic` on behalf of the service, marking the subscription as one `/ready`
+    /// depends on.
+    pub fn subscribe_internal(
+        self: &Arc<Self>, topic: Topic, commitment: CommitmentConfig,
+    ) -> Result<broadcast::Receiver<StreamEvent>, RetrievalError> {
+        self.listen(topic, commitment, None, true)
+            .map(|(_, receiver)| receiver)
+    }
+
+    fn listen(
+        self: &Arc<Self>, topic: Topic, commitment: CommitmentConfig, last_event_id: Option<u64>,
+        internal: bool,
+    ) -> Result<(Vec<StreamEvent>, broadcast::Receiver<StreamEvent>), RetrievalError> {
+        match &topic {
+            Topic::Account(address) | Topic::Program(address) | Topic::Logs(Some(address)) => {
+                Pubkey::from_str(address)
+                    .map_err(|e| RetrievalError::invalid_pubkey(address, e))?;
+            }
+            Topic::Signature(signature) => {
+                Signature::from_str(signature)
+                    .map_err(|e| RetrievalError::invalid_signature(signature, e))?;
+            }
+            Topic::Logs(None) | Topic::Slot => {}
+        }
+
+        // Slot notifications do not depend on the commitment.
+        let commitment = match topic {
+            Topic::Slot => CommitmentConfig::default(),
+            _ => commitment,
+        };
+
+        // The receiver is taken while holding the map so an idle subscription cannot be dropped
+        // in between.
+        let key = (topic, commitment);
+        let mut channels = self.channels.lock().unwrap();
+        let channel = match channels.get(&key) {
+            Some(channel) => Arc::clone(channel),
+            None if channels.len() >= self.config.max_channels => {
+                let mut e = RetrievalError::new(
+                    RetrievalErrorKind::RateLimited,
+                    format!(
+                        "at most {} subscriptions can be open at once",
+                        self.config.max_channels
+                    ),
+                );
+                e.retryable = true;
+                return Err(e);
+            }
+            None => {
+                let channel = Arc::new(Channel {
+                    sender:        broadcast::channel(self.config.buffer_size.max(1)).0,
+                    recent:        Mutex::new(VecDeque::new()),
+                    connected:     AtomicBool::new(false),
+                    last_event_at: AtomicI64::new(0),
+                    last_error:    Mutex::new(None),
+                    internal:      AtomicBool::new(false),
+                    opened_at:     chrono::Utc::now().timestamp(),
+                });
+                channels.insert(key.clone(), Arc::clone(&channel));
+                self.start(key, Arc::clone(&channel));
+                channel
+            }
+        };
+
+        if internal {
+            channel.internal.store(true, Ordering::Relaxed);
+        }
+        let recent = channel.recent.lock().unwrap();
+        let receiver = channel.sender.subscribe();
+        let backlog = match last_event_id {
+            Some(last_event_id) => recent
+                .iter()
+                .filter(|event| event.id > last_event_id)
+                .cloned()
+                .collect(),
+            None => vec![],
+        };
+        Ok((backlog, receiver))
+    }
+
+    fn start(self: &Arc<Self>, key: ChannelKey, channel: Arc<Channel>) {
+        let hub = Arc::clone(self);
+        thread::spawn(move || loop {
+            let (topic, commitment) = &key;
+            let result = match topic {
+                Topic::Account(pubkey) => hub.pub_sub.subscribe_account(pubkey, *commitment).map(
+                    |(subscription, receiver)| hub.forward(&key, &channel, subscription, receiver),
+                ),
+                Topic::Program(program_id) => hub
+                    .pub_sub
+                    .subscribe_program(program_id, *commitment)
+                    .map(|(subscription, receiver)| {
+                        hub.forward(&key, &channel, subscription, receiver)
+                    }),
+                Topic::Logs(mentions) => hub
+                    .pub_sub
+                    .subscribe_logs(mentions.as_deref(), *commitment)
+                    .map(|(subscription, receiver)| {
+                        hub.forward(&key, &channel, subscription, receiver)
+                    }),
+                Topic::Signature(signature) => hub
+                    .pub_sub
+                    .subscribe_signature(signature, *commitment)
+                    .map(|(subscription, receiver)| {
+                        hub.forward(&key, &channel, subscription, receiver)
+                    }),
+                Topic::Slot => hub
+                    .pub_sub
+                    .subscribe_slot()
+                    .map(|(subscription, receiver)| {
+                        hub.forward(&key, &channel, subscription, receiver)
+                    }),
+            };
+            channel.connected.store(false, Ordering::Relaxed);
+            match result {
+                Ok(false) => return,
+                Ok(true) => warn!("Subscription to {:?} disconnected", topic),
+                Err(e) => {
+                    let error = redact_urls(&e.to_string());
+                    warn!("Failed to subscribe to {:?}: {}", topic, error);
+                    *channel.last_error.lock().unwrap() = Some(error);
+                }
+            }
+
+            thread::sleep(RECONNECT_DELAY);
+            if !hub.retain(&key, &channel) {
+                return;
+            }
+        });
+    }
+
+    /// Publishes updates until the websocket disconnects, returning whether anybody still listens.
+    fn forward<T: Serialize + DeserializeOwned>(
+        &self, key: &ChannelKey, channel: &Channel, _subscription: PubsubClientSubscription<T>,
+        receiver: Receiver<T>,
+    ) -> bool {
+        channel.connected.store(true, Ordering::Relaxed);
+        loop {
+            match receiver.recv_timeout(IDLE_CHECK_INTERVAL) {
+                Ok(update) => match serde_json::to_value(&update) {
+                    Ok(data) => self.publish_update(&key.0, channel, data),
+                    Err(e) => warn!("Failed to encode update of {:?}: {}", key.0, e),
+                },
+                Err(RecvTimeoutError::Timeout) => {
+                    if !self.retain(key, channel) {
+                        return false;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return true,
+            }
+        }
+    }
+
+    /// Publishes an update of `topic`, first dropping the cached account it reports a change of.
+    fn publish_update(&self, topic: &Topic, channel: &Channel, data: Value) {
+        if let Topic::Account(pubkey) = topic {
+            self.rpc_solana_client.invalidate_account(pubkey);
+        }
+        self.publish(channel, data);
+    }
+
+    fn publish(&self, channel: &Channel, data: Value) {
+        let mut recent = channel.recent.lock().unwrap();
+        let event = StreamEvent {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            data,
+        };
+        recent.push_back(event.clone());
+        channel
+            .last_event_at
+            .store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+        while recent.len() > self.config.buffer_size {
+            recent.pop_front();
+        }
+        // No receivers only means the last listener just left.
+        let _ = channel.sender.send(event);
+    }
+
+    /// Subscriptions currently shared, ordered by topic.
+    pub fn status(&self) -> Vec<SubscriptionStatus> {
+        let mut status: Vec<SubscriptionStatus> = self
+            .channels
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((topic, commitment), channel)| SubscriptionStatus {
+                topic:         topic.label(),
+                commitment:    commitment.commitment,
+                connected:     channel.connected.load(Ordering::Relaxed),
+                listeners:     channel.sender.receiver_count(),
+                last_event_at: Some(channel.last_event_at.load(Ordering::Relaxed))
+                    .filter(|timestamp| *timestamp > 0),
+                last_error:    channel.last_error.lock().unwrap().clone(),
+                internal:      channel.internal.load(Ordering::Relaxed),
+                opened_at:     channel.opened_at,
+            })
+            .collect();
+        status.sort_by(|a, b| a.topic.cmp(&b.topic));
+        status
+    }
+
+    /// Keeps the channel while it has listeners, otherwise drops it and returns `false`.
+    fn retain(&self, key: &ChannelKey, channel: &Channel) -> bool {
+        let mut channels = self.channels.lock().unwrap();
+        if channel.sender.receiver_count() > 0 {
+            return true;
+        }
+        channels.remove(key);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{self, UNREACHABLE_RPC_URL};
+    use axum::Json;
+    use serde_json::json;
+    use std::sync::atomic::AtomicUsize;
+
+    fn stream_hub(buffer_size: usize) -> Arc<StreamHub> {
+        Arc::new(StreamHub::new(
+            Arc::new(PubSubSolanaClient::new(vec![])),
+            test_fixtures::rpc_client(UNREACHABLE_RPC_URL),
+            StreamConfig {
+                buffer_size,
+                ..StreamConfig::default()
+            },
+        ))
+    }
+
+    fn channel(hub: &StreamHub, topic: &Topic) -> Arc<Channel> {
+        let key = (topic.clone(), CommitmentConfig::confirmed());
+        Arc::clone(&hub.channels.lock().unwrap()[&key])
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_shares_channel() {
+        let hub = stream_hub(10);
+        let topic = Topic::Account(Pubkey::new_unique().to_string());
+
+        let (_, mut first) = hub
+            .subscribe(topic.clone(), CommitmentConfig::confirmed(), None)
+            .unwrap();
+        let (_, mut second) = hub
+            .subscribe(topic.clone(), CommitmentConfig::confirmed(), None)
+            .unwrap();
+        hub.publish(&channel(&hub, &topic), json!({"lamports": 1}));
+
+        assert_eq!(first.recv().await.unwrap().data, json!({"lamports": 1}));
+        assert_eq!(second.recv().await.unwrap().data, json!({"lamports": 1}));
+        assert_eq!(hub.channels.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_replays_events_after_last_event_id() {
+        let hub = stream_hub(2);
+        let topic = Topic::Logs(None);
+        let (_, _receiver) = hub
+            .subscribe(topic.clone(), CommitmentConfig::confirmed(), None)
+            .unwrap();
+        for slot in 0 .. 3 {
+            hub.publish(&channel(&hub, &topic), json!(slot));
+        }
+
+        let (backlog, _) = hub
+            .subscribe(topic.clone(), CommitmentConfig::confirmed(), Some(2))
+            .unwrap();
+        let (all, _) = hub
+            .subscribe(topic, CommitmentConfig::confirmed(), Some(0))
+            .unwrap();
+
+        assert_eq!(
+            backlog.iter().map(|event| event.id).collect::<Vec<_>>(),
+            vec![3]
+        );
+        // Only the two most recent events are buffered.
+        assert_eq!(
+            all.iter().map(|event| &event.data).collect::<Vec<_>>(),
+            vec![&json!(1), &json!(2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_account_update_invalidates_cached_account() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_clone = Arc::clone(&calls);
+        let answer = move |Json(request): Json<Value>| async move {
+            calls_clone.fetch_add(1, Ordering::SeqCst);
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": {
+                    "context": {"slot": 1},
+                    "value": {
+                        "lamports": 1,
+                        "data": ["", "base64"],
+                        "owner": "11111111111111111111111111111111",
+                        "executable": false,
+                        "rentEpoch": 0,
+                        "space": 0
+                    }
+                }
+            }))
+        };
+        let rpc_solana_client = test_fixtures::rpc_client(&test_fixtures::serve_rpc(answer).await);
+        let hub = Arc::new(StreamHub::new(
+            Arc::new(PubSubSolanaClient::new(vec![])),
+            Arc::clone(&rpc_solana_client),
+            StreamConfig::default(),
+        ));
+        let pubkey = Pubkey::new_unique().to_string();
+        let topic = Topic::Account(pubkey.clone());
+        let (_, _receiver) = hub
+            .subscribe(topic.clone(), CommitmentConfig::confirmed(), None)
+            .unwrap();
+        let get_account =
+            || rpc_solana_client.get_account(&pubkey, CommitmentConfig::confirmed(), None);
+
+        get_account().await.unwrap();
+        get_account().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        hub.publish_update(&topic, &channel(&hub, &topic), json!({"lamports": 2}));
+        get_account().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_status() {
+        let hub = stream_hub(10);
+        let pubkey = Pubkey::new_unique().to_string();
+        let topic = Topic::Account(pubkey.clone());
+        let (_, _first) = hub
+            .subscribe(topic.clone(), CommitmentConfig::confirmed(), None)
+            .unwrap();
+        let _second = hub
+            .subscribe_internal(Topic::Slot, CommitmentConfig::confirmed())
+            .unwrap();
+        hub.publish(&channel(&hub, &topic), json!({"lamports": 1}));
+
+        let status = hub.status();
+        assert_eq!(status.len(), 2);
+        assert_eq!(status[0].topic, format!("account:{}", pubkey));
+        assert_eq!(status[0].commitment, CommitmentLevel::Confirmed);
+        assert_eq!(status[0].listeners, 1);
+        assert!(status[0].last_event_at.is_some());
+        assert!(!status[0].internal);
+        assert_eq!(status[1].topic, "slot");
+        assert!(status[1].last_event_at.is_none());
+        assert!(status[1].internal);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_max_channels() {
+        let hub = Arc::new(StreamHub::new(
+            Arc::new(PubSubSolanaClient::new(vec![])),
+            test_fixtures::rpc_client(UNREACHABLE_RPC_URL),
+            StreamConfig {
+                max_channels: 1,
+                ..StreamConfig::default()
+            },
+        ));
+        let (_, _first) = hub
+            .subscribe(Topic::Slot, CommitmentConfig::confirmed(), None)
+            .unwrap();
+
+        let shared = hub.subscribe(Topic::Slot, CommitmentConfig::confirmed(), None);
+        let e = hub
+            .subscribe(Topic::Logs(None), CommitmentConfig::confirmed(), None)
+            .unwrap_err();
+
+        assert!(shared.is_ok());
+        assert_eq!(e.kind(), RetrievalErrorKind::RateLimited);
+        assert!(e.is_retryable());
+        assert_eq!(hub.channels.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_subscribe_invalid_pubkey() {
+        let hub = stream_hub(10);
+
+        let e = hub
+            .subscribe(
+                Topic::Logs(Some("not-a-pubkey".to_string())),
+                CommitmentConfig::confirmed(),
+                None,
+            )
+            .unwrap_err();
+
+        assert_eq!(e.kind(), RetrievalErrorKind::InvalidInput);
+        assert!(hub.channels.lock().unwrap().is_empty());
+    }
+}
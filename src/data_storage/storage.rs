@@ -1 +1,788 @@
+use crate::{
+    alerts::rule::Alert,
+    config::config::StorageConfig,
+    data_processing::{
+        processor::{AccountData, BlockData, TransactionData},
+        token::MintInfo,
+    },
+    webhooks::webhook::{DeadLetter, Delivery, Webhook},
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    ops::{Bound, RangeInclusive},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+};
+use utoipa::{IntoParams, ToSchema};
 
+/// Point-in-time copy of an account as returned by the RPC node.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct AccountSnapshot {
+    pub pubkey:      String,
+    /// Unix timestamp at which the snapshot was taken.
+    pub captured_at: i64,
+    pub account:     AccountData,
+}
+
+/// Header of a fetched block, its transactions being stored on their own.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockSummary {
+    pub slot:               u64,
+    pub blockhash:          String,
+    pub previous_blockhash: String,
+    pub parent_slot:        u64,
+    pub block_time:         Option<i64>,
+    pub block_height:       Option<u64>,
+    pub transaction_count:  usize,
+}
+
+/// Criteria of a stored transaction search, all of the given ones must hold.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TransactionFilter {
+    /// Any account of the transaction, signers and programs included.
+    pub account:          Option<String>,
+    pub signer:           Option<String>,
+    /// Program invoked by a top-level instruction.
+    pub program_id:       Option<String>,
+    /// Type of an instruction parsed by the node, e.g. `transfer`.
+    pub instruction_type: Option<String>,
+    /// Inclusive fee range in lamports.
+    pub min_fee:          Option<u64>,
+    pub max_fee:          Option<u64>,
+}
+
+impl TransactionFilter {
+    pub fn matches(&self, transaction_data: &TransactionData) -> bool {
+        self.account
+            .as_deref()
+            .is_none_or(|account| transaction_data.involves(account))
+            && self
+                .signer
+                .as_deref()
+                .is_none_or(|signer| transaction_data.is_signer(signer))
+            && self.program_id.as_deref().is_none_or(|program_id| {
+                transaction_data
+                    .program_ids()
+                    .any(|invoked| invoked == program_id)
+            })
+            && self
+                .instruction_type
+                .as_deref()
+                .is_none_or(|instruction_type| {
+                    transaction_data.has_instruction_type(instruction_type)
+                })
+            && (self.min_fee.is_none() && self.max_fee.is_none()
+                || transaction_data.fee.is_some_and(|fee| {
+                    self.min_fee.is_none_or(|min_fee| fee >= min_fee)
+                        && self.max_fee.is_none_or(|max_fee| fee <= max_fee)
+                }))
+    }
+}
+
+/// In-memory store shared by the API and the processing layer.
+#[derive(Default)]
+pub struct Storage {
+    config:            StorageConfig,
+    mints:             RwLock<HashMap<String, MintInfo>>,
+    transactions:      RwLock<HashMap<String, TransactionData>>,
+    /// Signatures of the stored transactions, oldest first, evicted from the front.
+    transaction_order: RwLock<VecDeque<String>>,
+    /// Signatures of the stored transactions by account key, signers and programs included.
+    account_index:     RwLock<HashMap<String, BTreeSet<String>>>,
+    /// Signatures of the stored transactions by program invoked.
+    program_index:     RwLock<HashMap<String, BTreeSet<String>>>,
+    /// Slots and signatures of the stored transactions, in the order range scans return them.
+    slot_index:        RwLock<BTreeSet<(u64, String)>>,
+    /// Signatures of the stored transactions without a slot.
+    unslotted_index:   RwLock<BTreeSet<String>>,
+    blocks:            RwLock<BTreeMap<u64, BlockSummary>>,
+    snapshots:         RwLock<BTreeMap<String, Vec<AccountSnapshot>>>,
+    /// Pubkeys of the stored snapshots, oldest first, evicted from the front.
+    snapshot_order:    RwLock<VecDeque<String>>,
+    webhooks:          RwLock<HashMap<String, Webhook>>,
+    /// Most recent deliveries per webhook, oldest first.
+    deliveries:        RwLock<HashMap<String, VecDeque<Delivery>>>,
+    dead_letters:      RwLock<HashMap<String, VecDeque<DeadLetter>>>,
+    /// Most recent alerts, oldest first.
+    alerts:            RwLock<VecDeque<Alert>>,
+    /// Highest slot of a stored transaction or block, zero before the first one.
+    last_slot:         AtomicU64,
+}
+
+impl Storage {
+    pub fn with_config(config: StorageConfig) -> Self {
+        Storage {
+            config,
+            ..Storage::default()
+        }
+    }
+
+    pub fn get_mint(&self, mint: &str) -> Option<MintInfo> {
+        self.mints.read().unwrap().get(mint).cloned()
+    }
+
+    pub fn insert_mint(&self, mint_info: MintInfo) {
+        self.mints
+            .write()
+            .unwrap()
+            .insert(mint_info.mint.clone(), mint_info);
+    }
+
+    pub fn get_transaction(&self, signature: &str) -> Option<TransactionData> {
+        self.transactions.read().unwrap().get(signature).cloned()
+    }
+
+    pub fn insert_transaction(&self, transaction_data: TransactionData) {
+        let Some(signature) = transaction_data.signature().map(str::to_string) else {
+            return;
+        };
+        // Keys never change for a signature, so replacing a transaction keeps its entries valid.
+        let mut account_index = self.account_index.write().unwrap();
+        for account in transaction_data.account_keys() {
+            account_index
+                .entry(account.to_string())
+                .or_default()
+                .insert(signature.clone());
+        }
+        let mut program_index = self.program_index.write().unwrap();
+        for program_id in transaction_data.program_ids() {
+            program_index
+                .entry(program_id.to_string())
+                .or_default()
+                .insert(signature.clone());
+        }
+        let mut slot_index = self.slot_index.write().unwrap();
+        let mut unslotted_index = self.unslotted_index.write().unwrap();
+        let slot = transaction_data.slot;
+        let mut transactions = self.transactions.write().unwrap();
+        let mut transaction_order = self.transaction_order.write().unwrap();
+        match transactions.insert(signature.clone(), transaction_data) {
+            // A transaction stored before its slot was known moves to the slot index.
+            Some(previous) if previous.slot != slot => match previous.slot {
+                Some(previous_slot) => {
+                    slot_index.remove(&(previous_slot, signature.clone()));
+                }
+                None => {
+                    unslotted_index.remove(&signature);
+                }
+            },
+            Some(_) => {}
+            None => transaction_order.push_back(signature.clone()),
+        }
+        match slot {
+            Some(slot) => {
+                self.last_slot.fetch_max(slot, Ordering::Relaxed);
+                slot_index.insert((slot, signature));
+            }
+            None => {
+                unslotted_index.insert(signature);
+            }
+        }
+
+        while transaction_order.len() > self.config.max_transactions {
+            let Some(evicted) = transaction_order
+                .pop_front()
+                .and_then(|signature| transactions.remove_entry(&signature))
+            else {
+                continue;
+            };
+            let (signature, transaction_data) = evicted;
+            for account in transaction_data.account_keys() {
+                remove_from_index(&mut account_index, account, &signature);
+            }
+            for program_id in transaction_data.program_ids() {
+                remove_from_index(&mut program_index, program_id, &signature);
+            }
+            match transaction_data.slot {
+                Some(slot) => slot_index.remove(&(slot, signature)),
+                None => unslotted_index.remove(&signature),
+            };
+        }
+    }
+
+    /// Stored transactions matching the filter, oldest slot first.
+    ///
+    /// Candidates come from the smallest index entry among the account, signer and program
+    /// criteria, the full filter is then applied to each.
+    pub fn search_transactions(&self, filter: &TransactionFilter) -> Vec<TransactionData> {
+        let account_index = self.account_index.read().unwrap();
+        let program_index = self.program_index.read().unwrap();
+        let transactions = self.transactions.read().unwrap();
+
+        let empty = BTreeSet::new();
+        let candidates = [
+            filter
+                .account
+                .as_ref()
+                .map(|account| account_index.get(account)),
+            filter
+                .signer
+                .as_ref()
+                .map(|signer| account_index.get(signer)),
+            filter
+                .program_id
+                .as_ref()
+                .map(|program_id| program_index.get(program_id)),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|signatures| signatures.unwrap_or(&empty))
+        .min_by_key(|signatures| signatures.len());
+
+        let mut found: Vec<TransactionData> = match candidates {
+            Some(signatures) => signatures
+                .iter()
+                .filter_map(|signature| transactions.get(signature))
+                .filter(|transaction_data| filter.matches(transaction_data))
+                .cloned()
+                .collect(),
+            None => transactions
+                .values()
+                .filter(|transaction_data| filter.matches(transaction_data))
+                .cloned()
+                .collect(),
+        };
+        found.sort_by(|a, b| {
+            a.slot
+                .cmp(&b.slot)
+                .then_with(|| a.signature().cmp(&b.signature()))
+        });
+        found
+    }
+
+    /// Up to `limit` stored transactions with a slot in `slots`, in slot then signature order,
+    /// starting after the `(slot, signature)` of `after`.
+    pub fn get_transactions_in_slots(
+        &self, slots: RangeInclusive<u64>, after: Option<&(u64, String)>, limit: usize,
+    ) -> Vec<TransactionData> {
+        let slot_index = self.slot_index.read().unwrap();
+        let transactions = self.transactions.read().unwrap();
+        let start = match after {
+            Some(key) => Bound::Excluded(key.clone()),
+            None => Bound::Included((*slots.start(), String::new())),
+        };
+        slot_index
+            .range((start, Bound::Unbounded))
+            .take_while(|(slot, _)| slot <= slots.end())
+            .filter_map(|(_, signature)| transactions.get(signature))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Up to `limit` stored transactions without a slot, in signature order, starting after the
+    /// signature `after`.
+    pub fn get_unslotted_transactions(
+        &self, after: Option<&str>, limit: usize,
+    ) -> Vec<TransactionData> {
+        let unslotted_index = self.unslotted_index.read().unwrap();
+        let transactions = self.transactions.read().unwrap();
+        let start = match after {
+            Some(signature) => Bound::Excluded(signature),
+            None => Bound::Unbounded,
+        };
+        unslotted_index
+            .range::<str, _>((start, Bound::Unbounded))
+            .filter_map(|signature| transactions.get(signature))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn insert_block(&self, slot: u64, block_data: &BlockData) {
+        let block = BlockSummary {
+            slot,
+            blockhash: block_data.blockhash.clone(),
+            previous_blockhash: block_data.previous_blockhash.clone(),
+            parent_slot: block_data.parent_slot,
+            block_time: block_data.block_time,
+            block_height: block_data.block_height,
+            transaction_count: block_data.transactions.len(),
+        };
+        let mut blocks = self.blocks.write().unwrap();
+        blocks.insert(slot, block);
+        while blocks.len() > self.config.max_blocks {
+            blocks.pop_first();
+        }
+        self.last_slot.fetch_max(slot, Ordering::Relaxed);
+    }
+
+    /// Highest slot of the stored transactions and blocks.
+    pub fn last_processed_slot(&self) -> Option<u64> {
+        Some(self.last_slot.load(Ordering::Relaxed)).filter(|slot| *slot > 0)
+    }
+
+    /// Whether every table is usable, a writer having panicked leaving its table poisoned.
+    pub fn is_healthy(&self) -> bool {
+        !(self.mints.is_poisoned()
+            || self.transactions.is_poisoned()
+            || self.transaction_order.is_poisoned()
+            || self.account_index.is_poisoned()
+            || self.program_index.is_poisoned()
+            || self.slot_index.is_poisoned()
+            || self.unslotted_index.is_poisoned()
+            || self.blocks.is_poisoned()
+            || self.snapshots.is_poisoned()
+            || self.snapshot_order.is_poisoned()
+            || self.webhooks.is_poisoned()
+            || self.deliveries.is_poisoned()
+            || self.dead_letters.is_poisoned()
+            || self.alerts.is_poisoned())
+    }
+
+    /// Up to `limit` stored blocks in `slots`, oldest first, starting after the slot `after`.
+    pub fn get_blocks_in_slots(
+        &self, slots: RangeInclusive<u64>, after: Option<u64>, limit: usize,
+    ) -> Vec<BlockSummary> {
+        let start = match after {
+            Some(slot) => Bound::Excluded(slot),
+            None => Bound::Included(*slots.start()),
+        };
+        self.blocks
+            .read()
+            .unwrap()
+            .range((start, Bound::Unbounded))
+            .take_while(|(slot, _)| *slot <= slots.end())
+            .take(limit)
+            .map(|(_, block)| block.clone())
+            .collect()
+    }
+
+    pub fn insert_account_snapshot(&self, pubkey: &str, account: AccountData) {
+        let snapshot = AccountSnapshot {
+            pubkey: pubkey.to_string(),
+            captured_at: Utc::now().timestamp(),
+            account,
+        };
+        let mut snapshots = self.snapshots.write().unwrap();
+        let mut snapshot_order = self.snapshot_order.write().unwrap();
+        snapshot_order.push_back(snapshot.pubkey.clone());
+        snapshots
+            .entry(snapshot.pubkey.clone())
+            .or_default()
+            .push(snapshot);
+
+        while snapshot_order.len() > self.config.max_snapshots {
+            let Some(pubkey) = snapshot_order.pop_front() else {
+                break;
+            };
+            if let Some(account_snapshots) = snapshots.get_mut(&pubkey) {
+                account_snapshots.remove(0);
+                if account_snapshots.is_empty() {
+                    snapshots.remove(&pubkey);
+                }
+            }
+        }
+    }
+
+    /// Snapshots of the account, oldest first.
+    pub fn get_account_snapshots(&self, pubkey: &str) -> Vec<AccountSnapshot> {
+        self.snapshots
+            .read()
+            .unwrap()
+            .get(pubkey)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Up to `limit` snapshots of all accounts, by pubkey then oldest first, starting at the
+    /// snapshot of index `after.1` of the account `after.0`.
+    pub fn get_snapshots_from(
+        &self, after: Option<(&str, usize)>, limit: usize,
+    ) -> Vec<AccountSnapshot> {
+        let snapshots = self.snapshots.read().unwrap();
+        let start = match after {
+            Some((pubkey, _)) => Bound::Included(pubkey),
+            None => Bound::Unbounded,
+        };
+        snapshots
+            .range::<str, _>((start, Bound::Unbounded))
+            .flat_map(|(pubkey, snapshots)| {
+                let skip = match after {
+                    Some((after, index)) if after == pubkey => index.min(snapshots.len()),
+                    _ => 0,
+                };
+                &snapshots[skip ..]
+            })
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_webhook(&self, id: &str) -> Option<Webhook> {
+        self.webhooks.read().unwrap().get(id).cloned()
+    }
+
+    /// Registered webhooks, oldest first.
+    pub fn get_webhooks(&self) -> Vec<Webhook> {
+        let mut webhooks: Vec<_> = self.webhooks.read().unwrap().values().cloned().collect();
+        webhooks.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        webhooks
+    }
+
+    pub fn insert_webhook(&self, webhook: Webhook) {
+        self.webhooks
+            .write()
+            .unwrap()
+            .insert(webhook.id.clone(), webhook);
+    }
+
+    /// Removes the webhook along with its deliveries and dead letters.
+    pub fn remove_webhook(&self, id: &str) -> Option<Webhook> {
+        self.deliveries.write().unwrap().remove(id);
+        self.dead_letters.write().unwrap().remove(id);
+        self.webhooks.write().unwrap().remove(id)
+    }
+
+    /// Inserts or updates the delivery, dropping the oldest beyond `history_size` per webhook.
+    pub fn record_delivery(&self, delivery: Delivery, history_size: usize) {
+        let mut deliveries = self.deliveries.write().unwrap();
+        let history = deliveries.entry(delivery.webhook_id.clone()).or_default();
+        match history
+            .iter_mut()
+            .find(|recorded| recorded.id == delivery.id)
+        {
+            Some(recorded) => *recorded = delivery,
+            None => history.push_back(delivery),
+        }
+        while history.len() > history_size {
+            history.pop_front();
+        }
+    }
+
+    pub fn get_deliveries(&self, webhook_id: &str) -> Vec<Delivery> {
+        self.deliveries
+            .read()
+            .unwrap()
+            .get(webhook_id)
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Stores the dead letter, dropping the oldest beyond `max_size` per webhook.
+    pub fn insert_dead_letter(&self, dead_letter: DeadLetter, max_size: usize) {
+        let mut dead_letters = self.dead_letters.write().unwrap();
+        let dead_letters = dead_letters
+            .entry(dead_letter.webhook_id.clone())
+            .or_default();
+        dead_letters.push_back(dead_letter);
+        while dead_letters.len() > max_size {
+            dead_letters.pop_front();
+        }
+    }
+
+    pub fn get_dead_letters(&self, webhook_id: &str) -> Vec<DeadLetter> {
+        self.dead_letters
+            .read()
+            .unwrap()
+            .get(webhook_id)
+            .map(|dead_letters| dead_letters.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Stores the alert, dropping the oldest beyond `history_size`.
+    pub fn insert_alert(&self, alert: Alert, history_size: usize) {
+        let mut alerts = self.alerts.write().unwrap();
+        alerts.push_back(alert);
+        while alerts.len() > history_size {
+            alerts.pop_front();
+        }
+    }
+
+    /// Stored alerts, oldest first.
+    pub fn get_alerts(&self) -> Vec<Alert> {
+        self.alerts.read().unwrap().iter().cloned().collect()
+    }
+}
+
+fn remove_from_index(index: &mut HashMap<String, BTreeSet<String>>, key: &str, signature: &str) {
+    if let Some(signatures) = index.get_mut(key) {
+        signatures.remove(signature);
+        if signatures.is_empty() {
+            index.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn transaction(signature: &str) -> TransactionData {
+        test_fixtures::transaction(serde_json::json!({"signatures": [signature]}))
+    }
+
+    fn transfer(signature: &str, slot: u64, signer: &str, fee: u64) -> TransactionData {
+        test_fixtures::transaction(serde_json::json!({
+            "signatures": [signature],
+            "accounts": [
+                {"pubkey": signer, "writable": true, "signer": true},
+                {"pubkey": "Receiver", "writable": true, "signer": false},
+                {"pubkey": "System", "writable": false, "signer": false}
+            ],
+            "instructions": [{"program_id": "System", "data": "", "instruction_type": "transfer"}],
+            "slot": slot,
+            "fee": fee
+        }))
+    }
+
+    #[test]
+    fn test_insert_and_get_transaction() {
+        let storage = Storage::default();
+
+        storage.insert_transaction(transaction("Signature1"));
+
+        let stored = storage.get_transaction("Signature1").unwrap();
+        assert_eq!(stored.signature(), Some("Signature1"));
+        assert!(storage.get_transaction("Signature2").is_none());
+    }
+
+    #[test]
+    fn test_search_transactions() {
+        let storage = Storage::default();
+        storage.insert_transaction(transfer("Signature3", 3, "Alice", 10000));
+        storage.insert_transaction(transfer("Signature2", 2, "Bob", 5000));
+        storage.insert_transaction(transfer("Signature1", 1, "Alice", 5000));
+        storage.insert_transaction(transaction("Signature4"));
+        let search = |filter: TransactionFilter| -> Vec<String> {
+            storage
+                .search_transactions(&filter)
+                .iter()
+                .map(|transaction_data| transaction_data.signature().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            search(TransactionFilter {
+                signer: Some("Alice".to_string()),
+                ..TransactionFilter::default()
+            }),
+            vec!["Signature1", "Signature3"]
+        );
+        assert_eq!(
+            search(TransactionFilter {
+                account: Some("Receiver".to_string()),
+                program_id: Some("System".to_string()),
+                instruction_type: Some("transfer".to_string()),
+                max_fee: Some(5000),
+                ..TransactionFilter::default()
+            }),
+            vec!["Signature1", "Signature2"]
+        );
+        // Receiver is an account of every transfer but signs none of them.
+        assert!(search(TransactionFilter {
+            signer: Some("Receiver".to_string()),
+            ..TransactionFilter::default()
+        })
+        .is_empty());
+        assert!(search(TransactionFilter {
+            program_id: Some("Unknown".to_string()),
+            ..TransactionFilter::default()
+        })
+        .is_empty());
+        assert_eq!(search(TransactionFilter::default()).len(), 4);
+    }
+
+    #[test]
+    fn test_get_transactions_in_slots() {
+        let storage = Storage::default();
+        storage.insert_transaction(transfer("Signature3", 3, "Alice", 5000));
+        storage.insert_transaction(transfer("Signature2", 2, "Alice", 5000));
+        storage.insert_transaction(transfer("Signature4", 2, "Alice", 5000));
+        storage.insert_transaction(transfer("Signature1", 1, "Alice", 5000));
+        let signatures = |transactions: Vec<TransactionData>| -> Vec<String> {
+            transactions
+                .iter()
+                .map(|transaction_data| transaction_data.signature().unwrap().to_string())
+                .collect()
+        };
+
+        let first = storage.get_transactions_in_slots(2 ..= 3, None, 2);
+        assert_eq!(signatures(first), vec!["Signature2", "Signature4"]);
+        let rest =
+            storage.get_transactions_in_slots(2 ..= 3, Some(&(2, "Signature4".to_string())), 2);
+        assert_eq!(signatures(rest), vec!["Signature3"]);
+        assert_eq!(storage.last_processed_slot(), Some(3));
+    }
+
+    #[test]
+    fn test_get_snapshots_from() {
+        let storage = Storage::default();
+        let account: AccountData = serde_json::from_value(serde_json::json!({
+            "lamports": 1,
+            "data": [],
+            "owner": "Owner",
+            "executable": false,
+            "rent_epoch": 0,
+            "context_slot": 1,
+            "fetched_at": 0
+        }))
+        .unwrap();
+        for pubkey in ["Bob", "Alice", "Alice"] {
+            storage.insert_account_snapshot(pubkey, account.clone());
+        }
+        let pubkeys = |snapshots: Vec<AccountSnapshot>| -> Vec<String> {
+            snapshots
+                .into_iter()
+                .map(|snapshot| snapshot.pubkey)
+                .collect()
+        };
+
+        assert_eq!(
+            pubkeys(storage.get_snapshots_from(None, 10)),
+            vec!["Alice", "Alice", "Bob"]
+        );
+        assert_eq!(
+            pubkeys(storage.get_snapshots_from(Some(("Alice", 1)), 10)),
+            vec!["Alice", "Bob"]
+        );
+        assert_eq!(
+            pubkeys(storage.get_snapshots_from(Some(("Alice", 2)), 1)),
+            vec!["Bob"]
+        );
+    }
+
+    #[test]
+    fn test_insert_account_snapshot_evicts_oldest() {
+        let storage = Storage::with_config(StorageConfig {
+            max_snapshots: 2,
+            ..StorageConfig::default()
+        });
+        let account = |lamports: u64| -> AccountData {
+            serde_json::from_value(serde_json::json!({
+                "lamports": lamports,
+                "data": [],
+                "owner": "Owner",
+                "executable": false,
+                "rent_epoch": 0,
+                "context_slot": 1,
+                "fetched_at": 0
+            }))
+            .unwrap()
+        };
+        storage.insert_account_snapshot("Alice", account(1));
+        storage.insert_account_snapshot("Bob", account(2));
+        storage.insert_account_snapshot("Alice", account(3));
+        storage.insert_account_snapshot("Carol", account(4));
+
+        assert!(storage.get_account_snapshots("Bob").is_empty());
+        assert!(!storage.snapshots.read().unwrap().contains_key("Bob"));
+        let alice = storage.get_account_snapshots("Alice");
+        assert_eq!(alice.len(), 1);
+        assert_eq!(alice[0].account.lamports, 3);
+        assert_eq!(storage.get_account_snapshots("Carol").len(), 1);
+    }
+
+    #[test]
+    fn test_insert_dead_letter_drops_oldest() {
+        let storage = Storage::default();
+        for delivery_id in ["1", "2", "3"] {
+            let dead_letter = DeadLetter {
+                delivery_id: delivery_id.to_string(),
+                webhook_id:  "Webhook".to_string(),
+                url:         "https://example.com/hook".to_string(),
+                payload:     transaction("Signature1"),
+                error:       "HTTP 500".to_string(),
+                failed_at:   0,
+            };
+            storage.insert_dead_letter(dead_letter, 2);
+        }
+
+        let delivery_ids: Vec<String> = storage
+            .get_dead_letters("Webhook")
+            .into_iter()
+            .map(|dead_letter| dead_letter.delivery_id)
+            .collect();
+        assert_eq!(delivery_ids, vec!["2", "3"]);
+    }
+
+    #[test]
+    fn test_get_unslotted_transactions() {
+        let storage = Storage::default();
+        for signature in ["Signature2", "Signature1", "Signature3"] {
+            storage.insert_transaction(test_fixtures::transaction(
+                serde_json::json!({"signatures": [signature], "slot": null}),
+            ));
+        }
+        storage.insert_transaction(transfer("Signature3", 3, "Alice", 5000));
+
+        let signatures = |transactions: Vec<TransactionData>| -> Vec<String> {
+            transactions
+                .iter()
+                .map(|transaction_data| transaction_data.signature().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(
+            signatures(storage.get_unslotted_transactions(None, 1)),
+            vec!["Signature1"]
+        );
+        assert_eq!(
+            signatures(storage.get_unslotted_transactions(Some("Signature1"), 10)),
+            vec!["Signature2"]
+        );
+        assert_eq!(
+            signatures(storage.get_transactions_in_slots(0 ..= 10, None, 10)),
+            vec!["Signature3"]
+        );
+    }
+
+    #[test]
+    fn test_insert_transaction_evicts_oldest() {
+        let storage = Storage::with_config(StorageConfig {
+            max_transactions: 2,
+            ..StorageConfig::default()
+        });
+        storage.insert_transaction(transfer("Signature1", 1, "Alice", 5000));
+        storage.insert_transaction(transfer("Signature2", 2, "Bob", 5000));
+        storage.insert_transaction(transfer("Signature2", 2, "Bob", 5000));
+        storage.insert_transaction(transfer("Signature3", 3, "Carol", 5000));
+
+        let signatures = |transactions: Vec<TransactionData>| -> Vec<String> {
+            transactions
+                .iter()
+                .map(|transaction_data| transaction_data.signature().unwrap().to_string())
+                .collect()
+        };
+        assert!(storage.get_transaction("Signature1").is_none());
+        assert_eq!(
+            signatures(storage.get_transactions_in_slots(0 ..= 10, None, 10)),
+            vec!["Signature2", "Signature3"]
+        );
+        assert!(storage.account_index.read().unwrap().get("Alice").is_none());
+        assert_eq!(storage.account_index.read().unwrap()["Receiver"].len(), 2);
+        assert_eq!(storage.program_index.read().unwrap()["System"].len(), 2);
+    }
+
+    #[test]
+    fn test_insert_block_evicts_lowest_slot() {
+        let storage = Storage::with_config(StorageConfig {
+            max_blocks: 2,
+            ..StorageConfig::default()
+        });
+        let block: BlockData = serde_json::from_value(serde_json::json!({
+            "previous_blockhash": "Previous",
+            "blockhash": "Blockhash",
+            "parent_slot": 0,
+            "transactions": [],
+            "block_time": null,
+            "block_height": null
+        }))
+        .unwrap();
+        for slot in [3, 1, 2] {
+            storage.insert_block(slot, &block);
+        }
+
+        let slots: Vec<u64> = storage
+            .get_blocks_in_slots(0 ..= 10, None, 10)
+            .into_iter()
+            .map(|block| block.slot)
+            .collect();
+        assert_eq!(slots, vec![2, 3]);
+    }
+}
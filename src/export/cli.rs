@@ -0,0 +1,74 @@
+use crate::export::{Dataset, ExportQuery, Format};
+use clap::{Args, Parser, Subcommand};
+use std::{error::Error, path::PathBuf};
+use tokio::io::{self, AsyncWrite, AsyncWriteExt};
+
+#[derive(Debug, Parser)]
+#[command(about = "Aggregates and serves processed Solana data")]
+pub struct Cli {
+    /// Runs the server when absent.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Exports data stored by a running aggregator to a file.
+    Export(ExportArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    #[arg(value_enum)]
+    pub dataset: Dataset,
+    #[command(flatten)]
+    pub query:   ExportQuery,
+    /// Output file, standard output when absent. Its extension picks the format when `--format`
+    /// is not given.
+    #[arg(short, long)]
+    pub output:  Option<PathBuf>,
+    /// Base URL of the running aggregator.
+    #[arg(long, default_value = "http://127.0.0.1:8000")]
+    pub url:     String,
+}
+
+/// Downloads the export from the aggregator's `/api/export` endpoint, the data being stored in
+/// the memory of the server process, and writes it as it arrives.
+pub async fn export(mut args: ExportArgs) -> Result<(), Box<dyn Error>> {
+    if args.query.format.is_none() {
+        args.query.format = args
+            .output
+            .as_ref()
+            .and_then(|output| output.extension())
+            .and_then(|extension| match extension.to_str() {
+                Some("csv") => Some(Format::Csv),
+                Some("parquet") => Some(Format::Parquet),
+                _ => None,
+            });
+    }
+
+    let url = format!(
+        "{}/api/export/{}",
+        args.url.trim_end_matches('/'),
+        args.dataset.as_str()
+    );
+    let mut response = reqwest::Client::new()
+        .get(&url)
+        .query(&args.query)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!("{} answered {}: {}", url, status, response.text().await?).into());
+    }
+
+    let mut output: Box<dyn AsyncWrite + Unpin> = match &args.output {
+        Some(path) => Box::new(tokio::fs::File::create(path).await?),
+        None => Box::new(io::stdout()),
+    };
+    while let Some(chunk) = response.chunk().await? {
+        output.write_all(&chunk).await?;
+    }
+    output.flush().await?;
+    Ok(())
+}
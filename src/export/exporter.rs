@@ -0,0 +1,562 @@
+use crate::{
+    data_processing::processor::TransactionData,
+    data_storage::{storage::AccountSnapshot, Storage},
+    export::writer::Format,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{ops::RangeInclusive, sync::Arc};
+use utoipa::{IntoParams, ToSchema};
+
+/// Stored items read from storage per batch, bounding the memory an export holds.
+const BATCH_SIZE: usize = 1000;
+
+/// Table of stored data an export produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum Dataset {
+    /// One row per transaction.
+    Transactions,
+    /// One row per top-level instruction.
+    Instructions,
+    /// One row per token balance changed by a transaction.
+    TokenTransfers,
+    /// One row per fetched block.
+    Blocks,
+    /// One row per account snapshot.
+    Snapshots,
+}
+
+/// Range and encoding of an export.
+///
+/// Items lacking the slot or time a bound looks at, e.g. transactions without a block time, do not
+/// match it.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, IntoParams, clap::Args)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// `csv`, `ndjson` or `parquet`, `ndjson` by default.
+    #[arg(long, value_enum)]
+    pub format:    Option<Format>,
+    /// Inclusive slot range, the context slot for snapshots.
+    #[arg(long)]
+    pub from_slot: Option<u64>,
+    #[arg(long)]
+    pub to_slot:   Option<u64>,
+    /// Inclusive range of Unix timestamps, the block time or the capture time for snapshots.
+    #[arg(long)]
+    pub from_time: Option<i64>,
+    #[arg(long)]
+    pub to_time:   Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Utf8,
+    Int64,
+    UInt64,
+    Boolean,
+}
+
+/// Column of an exported table, every column being nullable.
+#[derive(Debug)]
+pub struct Column {
+    pub name:        &'static str,
+    pub column_type: ColumnType,
+}
+
+const fn column(name: &'static str, column_type: ColumnType) -> Column {
+    Column { name, column_type }
+}
+
+const TRANSACTION_COLUMNS: &[Column] = &[
+    column("slot", ColumnType::UInt64),
+    column("block_time", ColumnType::Int64),
+    column("signature", ColumnType::Utf8),
+    column("blockhash", ColumnType::Utf8),
+    column("fee_payer", ColumnType::Utf8),
+    column("fee", ColumnType::UInt64),
+    column("success", ColumnType::Boolean),
+    column("error", ColumnType::Utf8),
+    column("account_count", ColumnType::UInt64),
+    column("instruction_count", ColumnType::UInt64),
+    column("token_transfer_count", ColumnType::UInt64),
+];
+
+const INSTRUCTION_COLUMNS: &[Column] = &[
+    column("slot", ColumnType::UInt64),
+    column("block_time", ColumnType::Int64),
+    column("signature", ColumnType::Utf8),
+    column("index", ColumnType::UInt64),
+    column("program_id", ColumnType::Utf8),
+    column("instruction_type", ColumnType::Utf8),
+    column("data", ColumnType::Utf8),
+];
+
+// Amounts are strings as they may exceed the range of a signed 64-bit integer.
+const TOKEN_TRANSFER_COLUMNS: &[Column] = &[
+    column("slot", ColumnType::UInt64),
+    column("block_time", ColumnType::Int64),
+    column("signature", ColumnType::Utf8),
+    column("account", ColumnType::Utf8),
+    column("mint", ColumnType::Utf8),
+    column("owner", ColumnType::Utf8),
+    column("program_id", ColumnType::Utf8),
+    column("decimals", ColumnType::UInt64),
+    column("pre_amount", ColumnType::Utf8),
+    column("post_amount", ColumnType::Utf8),
+    column("change", ColumnType::Utf8),
+    column("symbol", ColumnType::Utf8),
+];
+
+const BLOCK_COLUMNS: &[Column] = &[
+    column("slot", ColumnType::UInt64),
+    column("block_time", ColumnType::Int64),
+    column("blockhash", ColumnType::Utf8),
+    column("previous_blockhash", ColumnType::Utf8),
+    column("parent_slot", ColumnType::UInt64),
+    column("block_height", ColumnType::UInt64),
+    column("transaction_count", ColumnType::UInt64),
+];
+
+const SNAPSHOT_COLUMNS: &[Column] = &[
+    column("pubkey", ColumnType::Utf8),
+    column("captured_at", ColumnType::Int64),
+    column("context_slot", ColumnType::UInt64),
+    column("fetched_at", ColumnType::Int64),
+    column("lamports", ColumnType::UInt64),
+    column("owner", ColumnType::Utf8),
+    column("executable", ColumnType::Boolean),
+    column("rent_epoch", ColumnType::UInt64),
+    column("space", ColumnType::UInt64),
+    column("data", ColumnType::Utf8),
+];
+
+impl Dataset {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Dataset::Transactions => "transactions",
+            Dataset::Instructions => "instructions",
+            Dataset::TokenTransfers => "token_transfers",
+            Dataset::Blocks => "blocks",
+            Dataset::Snapshots => "snapshots",
+        }
+    }
+
+    pub fn columns(self) -> &'static [Column] {
+        match self {
+            Dataset::Transactions => TRANSACTION_COLUMNS,
+            Dataset::Instructions => INSTRUCTION_COLUMNS,
+            Dataset::TokenTransfers => TOKEN_TRANSFER_COLUMNS,
+            Dataset::Blocks => BLOCK_COLUMNS,
+            Dataset::Snapshots => SNAPSHOT_COLUMNS,
+        }
+    }
+}
+
+/// Row of an exported table, keyed by column name.
+pub type Row = Map<String, Value>;
+
+/// Where the next batch resumes.
+enum Cursor {
+    Start,
+    Transaction((u64, String)),
+    /// Transactions without a slot, exported after the others, resuming after this signature.
+    Unslotted(Option<String>),
+    Block(u64),
+    /// Pubkey and index of the next snapshot.
+    Snapshot(String, usize),
+    Done,
+}
+
+/// Reads the rows of a dataset from storage in batches, so an export never holds the whole range.
+pub struct Exporter {
+    storage: Arc<Storage>,
+    dataset: Dataset,
+    slots:   RangeInclusive<u64>,
+    slotted: bool,
+    times:   RangeInclusive<i64>,
+    timed:   bool,
+    cursor:  Cursor,
+}
+
+impl Exporter {
+    pub fn new(storage: Arc<Storage>, dataset: Dataset, query: &ExportQuery) -> Self {
+        Exporter {
+            storage,
+            dataset,
+            slots: query.from_slot.unwrap_or(0) ..= query.to_slot.unwrap_or(u64::MAX),
+            slotted: query.from_slot.is_some() || query.to_slot.is_some(),
+            times: query.from_time.unwrap_or(i64::MIN) ..= query.to_time.unwrap_or(i64::MAX),
+            timed: query.from_time.is_some() || query.to_time.is_some(),
+            cursor: Cursor::Start,
+        }
+    }
+
+    fn in_times(&self, time: Option<i64>) -> bool {
+        match time {
+            Some(time) => self.times.contains(&time),
+            None => !self.timed,
+        }
+    }
+
+    /// Rows of the next batch, possibly empty when the range filters all of them out, or `None`
+    /// once the range is exhausted.
+    pub fn next_batch(&mut self) -> Option<Vec<Row>> {
+        match self.dataset {
+            Dataset::Transactions | Dataset::Instructions | Dataset::TokenTransfers => {
+                let transactions = match &self.cursor {
+                    Cursor::Start | Cursor::Transaction(_) => {
+                        let after = match &self.cursor {
+                            Cursor::Transaction(key) => Some(key),
+                            _ => None,
+                        };
+                        let transactions = self.storage.get_transactions_in_slots(
+                            self.slots.clone(),
+                            after,
+                            BATCH_SIZE,
+                        );
+                        self.cursor = match transactions.last() {
+                            Some(last) if transactions.len() == BATCH_SIZE => {
+                                Cursor::Transaction((
+                                    last.slot.unwrap_or_default(),
+                                    last.signature().unwrap_or_default().to_string(),
+                                ))
+                            }
+                            // Transactions without a slot only fall in an unbounded range.
+                            _ if !self.slotted => Cursor::Unslotted(None),
+                            _ => Cursor::Done,
+                        };
+                        transactions
+                    }
+                    Cursor::Unslotted(after) => {
+                        let transactions = self
+                            .storage
+                            .get_unslotted_transactions(after.as_deref(), BATCH_SIZE);
+                        self.cursor = match transactions.last() {
+                            Some(last) if transactions.len() == BATCH_SIZE => {
+                                Cursor::Unslotted(last.signature().map(str::to_string))
+                            }
+                            _ => Cursor::Done,
+                        };
+                        transactions
+                    }
+                    _ => return None,
+                };
+                Some(
+                    transactions
+                        .iter()
+                        .filter(|transaction_data| self.in_times(transaction_data.block_time))
+                        .flat_map(|transaction_data| self.transaction_rows(transaction_data))
+                        .collect(),
+                )
+            }
+            Dataset::Blocks => {
+                let after = match self.cursor {
+                    Cursor::Start => None,
+                    Cursor::Block(slot) => Some(slot),
+                    _ => return None,
+                };
+                let blocks =
+                    self.storage
+                        .get_blocks_in_slots(self.slots.clone(), after, BATCH_SIZE);
+                self.cursor = match blocks.last() {
+                    Some(last) if blocks.len() == BATCH_SIZE => Cursor::Block(last.slot),
+                    _ => Cursor::Done,
+                };
+                Some(
+                    blocks
+                        .into_iter()
+                        .filter(|block| self.in_times(block.block_time))
+                        .map(|block| {
+                            row(json!({
+                                "slot": block.slot,
+                                "block_time": block.block_time,
+                                "blockhash": block.blockhash,
+                                "previous_blockhash": block.previous_blockhash,
+                                "parent_slot": block.parent_slot,
+                                "block_height": block.block_height,
+                                "transaction_count": block.transaction_count,
+                            }))
+                        })
+                        .collect(),
+                )
+            }
+            Dataset::Snapshots => {
+                let after = match &self.cursor {
+                    Cursor::Start => None,
+                    Cursor::Snapshot(pubkey, index) => Some((pubkey.as_str(), *index)),
+                    _ => return None,
+                };
+                let snapshots = self.storage.get_snapshots_from(after, BATCH_SIZE);
+                if snapshots.len() < BATCH_SIZE {
+                    self.cursor = Cursor::Done;
+                } else {
+                    let (mut pubkey, mut index) = match &self.cursor {
+                        Cursor::Snapshot(pubkey, index) => (pubkey.clone(), *index),
+                        _ => (String::new(), 0),
+                    };
+                    for snapshot in &snapshots {
+                        if snapshot.pubkey == pubkey {
+                            index += 1;
+                        } else {
+                            pubkey.clone_from(&snapshot.pubkey);
+                            index = 1;
+                        }
+                    }
+                    self.cursor = Cursor::Snapshot(pubkey, index);
+                }
+                Some(
+                    snapshots
+                        .into_iter()
+                        .filter(|snapshot| {
+                            self.slots.contains(&snapshot.account.context_slot)
+                                && self.in_times(Some(snapshot.captured_at))
+                        })
+                        .map(snapshot_row)
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    fn transaction_rows(&self, transaction_data: &TransactionData) -> Vec<Row> {
+        let slot = transaction_data.slot;
+        let block_time = transaction_data.block_time;
+        let signature = transaction_data.signature();
+        match self.dataset {
+            Dataset::Instructions => transaction_data
+                .instructions()
+                .iter()
+                .enumerate()
+                .map(|(index, instruction)| {
+                    row(json!({
+                        "slot": slot,
+                        "block_time": block_time,
+                        "signature": signature,
+                        "index": index,
+                        "program_id": instruction.program_id(),
+                        "instruction_type": instruction.instruction_type(),
+                        "data": instruction.data(),
+                    }))
+                })
+                .collect(),
+            Dataset::TokenTransfers => transaction_data
+                .token_balances
+                .iter()
+                .map(|balance| {
+                    let amount = |amount: &Option<String>| {
+                        amount
+                            .as_deref()
+                            .and_then(|amount| amount.parse::<i128>().ok())
+                            .unwrap_or_default()
+                    };
+                    let change = amount(&balance.post_amount) - amount(&balance.pre_amount);
+                    row(json!({
+                        "slot": slot,
+                        "block_time": block_time,
+                        "signature": signature,
+                        "account": balance.account,
+                        "mint": balance.mint,
+                        "owner": balance.owner,
+                        "program_id": balance.program_id,
+                        "decimals": balance.decimals,
+                        "pre_amount": balance.pre_amount,
+                        "post_amount": balance.post_amount,
+                        "change": change.to_string(),
+                        "symbol": balance.symbol,
+                    }))
+                })
+                .collect(),
+            _ => vec![row(json!({
+                "slot": slot,
+                "block_time": block_time,
+                "signature": signature,
+                "blockhash": transaction_data.blockhash(),
+                "fee_payer": transaction_data.accounts().first().map(|account| account.pubkey()),
+                "fee": transaction_data.fee,
+                "success": transaction_data.error.is_none(),
+                "error": transaction_data.error,
+                "account_count": transaction_data.accounts().len(),
+                "instruction_count": transaction_data.instructions().len(),
+                "token_transfer_count": transaction_data.token_balances.len(),
+            }))],
+        }
+    }
+}
+
+fn snapshot_row(snapshot: AccountSnapshot) -> Row {
+    row(json!({
+        "pubkey": snapshot.pubkey,
+        "captured_at": snapshot.captured_at,
+        "context_slot": snapshot.account.context_slot,
+        "fetched_at": snapshot.account.fetched_at,
+        "lamports": snapshot.account.lamports,
+        "owner": snapshot.account.owner,
+        "executable": snapshot.account.executable,
+        "rent_epoch": snapshot.account.rent_epoch,
+        "space": snapshot.account.data.len(),
+        "data": STANDARD.encode(&snapshot.account.data),
+    }))
+}
+
+fn row(value: Value) -> Row {
+    match value {
+        Value::Object(row) => row,
+        _ => Map::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn transaction(signature: &str, slot: u64, block_time: Option<i64>) -> TransactionData {
+        test_fixtures::transaction(json!({
+            "signatures": [signature],
+            "accounts": [
+                {"pubkey": "Payer", "writable": true, "signer": true, "lamport_change": -5000},
+                {"pubkey": "Token", "writable": false, "signer": false}
+            ],
+            "instructions": [
+                {"program_id": "Token", "data": "", "instruction_type": "transfer"},
+                {"program_id": "Memo", "data": "3yZe7d"}
+            ],
+            "token_balances": [{
+                "account": "TokenAccount",
+                "mint": "Mint",
+                "owner": "Payer",
+                "program_id": "Token",
+                "decimals": 6,
+                "pre_amount": "3000000",
+                "post_amount": "1000000",
+                "name": null,
+                "symbol": "USDC"
+            }],
+            "slot": slot,
+            "block_time": block_time,
+            "fee": 5000
+        }))
+    }
+
+    fn export(storage: &Arc<Storage>, dataset: Dataset, query: &ExportQuery) -> Vec<Row> {
+        let mut exporter = Exporter::new(Arc::clone(storage), dataset, query);
+        let mut rows = Vec::new();
+        while let Some(batch) = exporter.next_batch() {
+            rows.extend(batch);
+        }
+        rows
+    }
+
+    #[test]
+    fn test_rows_match_columns() {
+        let storage = Arc::new(Storage::default());
+        storage.insert_transaction(transaction("Signature1", 1, Some(100)));
+        let block = serde_json::from_value(json!({
+            "previous_blockhash": "Previous",
+            "blockhash": "Blockhash",
+            "parent_slot": 0,
+            "transactions": [null],
+            "block_time": 100,
+            "block_height": 1
+        }))
+        .unwrap();
+        storage.insert_block(1, &block);
+        storage.insert_account_snapshot(
+            "Account",
+            serde_json::from_value(json!({
+                "lamports": 1,
+                "data": [1, 2],
+                "owner": "Owner",
+                "executable": false,
+                "rent_epoch": 0,
+                "context_slot": 1,
+                "fetched_at": 100
+            }))
+            .unwrap(),
+        );
+
+        for dataset in [
+            Dataset::Transactions,
+            Dataset::Instructions,
+            Dataset::TokenTransfers,
+            Dataset::Blocks,
+            Dataset::Snapshots,
+        ] {
+            let rows = export(&storage, dataset, &ExportQuery::default());
+            assert!(!rows.is_empty(), "no {} rows", dataset.as_str());
+            for row in rows {
+                let mut keys: Vec<&str> = row.keys().map(String::as_str).collect();
+                keys.sort_unstable();
+                let mut columns: Vec<&str> =
+                    dataset.columns().iter().map(|column| column.name).collect();
+                columns.sort_unstable();
+                assert_eq!(keys, columns, "columns of {}", dataset.as_str());
+            }
+        }
+    }
+
+    #[test]
+    fn test_export_range() {
+        let storage = Arc::new(Storage::default());
+        for slot in 1 ..= 2 * BATCH_SIZE as u64 + 1 {
+            storage.insert_transaction(transaction(
+                &format!("Signature{}", slot),
+                slot,
+                Some(slot as i64),
+            ));
+        }
+        storage.insert_transaction(transaction("Untimed", 5, None));
+
+        let rows = export(&storage, Dataset::Transactions, &ExportQuery::default());
+        assert_eq!(rows.len(), 2 * BATCH_SIZE + 2);
+        assert_eq!(rows[0]["fee_payer"], "Payer");
+
+        let query = ExportQuery {
+            from_slot: Some(3),
+            to_slot: Some(BATCH_SIZE as u64 + 10),
+            ..ExportQuery::default()
+        };
+        assert_eq!(
+            export(&storage, Dataset::Transactions, &query).len(),
+            BATCH_SIZE + 8 + 1
+        );
+        let query = ExportQuery {
+            from_time: Some(4),
+            to_time: Some(6),
+            ..ExportQuery::default()
+        };
+        let rows = export(&storage, Dataset::Instructions, &query);
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[0]["slot"], 4);
+        assert_eq!(rows[1]["index"], 1);
+
+        let rows = export(&storage, Dataset::TokenTransfers, &query);
+        assert_eq!(rows[0]["change"], "-2000000");
+    }
+
+    #[test]
+    fn test_export_unslotted() {
+        let storage = Arc::new(Storage::default());
+        storage.insert_transaction(transaction("Slotted", 1, Some(1)));
+        for index in 0 ..= BATCH_SIZE {
+            storage.insert_transaction(test_fixtures::transaction(json!({
+                "signatures": [format!("Unslotted{}", index)],
+                "slot": null
+            })));
+        }
+
+        let rows = export(&storage, Dataset::Transactions, &ExportQuery::default());
+        assert_eq!(rows.len(), BATCH_SIZE + 2);
+        assert_eq!(rows[0]["signature"], "Slotted");
+        assert_eq!(rows[1]["slot"], Value::Null);
+
+        let query = ExportQuery {
+            from_slot: Some(0),
+            ..ExportQuery::default()
+        };
+        assert_eq!(export(&storage, Dataset::Transactions, &query).len(), 1);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod cli;
+pub mod exporter;
+pub mod writer;
+
+pub use exporter::{Dataset, ExportQuery, Exporter};
+pub use writer::{ExportWriter, Format};
@@ -0,0 +1,312 @@
+use crate::export::exporter::{Column, ColumnType, Dataset, Row};
+use arrow_array::{ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use parquet::{arrow::ArrowWriter, errors::ParquetError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    io::{self, Write},
+    mem,
+    sync::{Arc, Mutex},
+};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Csv,
+    /// Newline-delimited JSON.
+    Ndjson,
+    Parquet,
+}
+
+impl Format {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Csv => "text/csv",
+            Format::Ndjson => "application/x-ndjson",
+            Format::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Ndjson => "ndjson",
+            Format::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+}
+
+/// Buffer the Parquet writer writes to, drained after every row group.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum Encoder {
+    Csv {
+        header_written: bool,
+    },
+    Ndjson,
+    Parquet {
+        writer: Box<ArrowWriter<SharedBuffer>>,
+        buffer: SharedBuffer,
+        schema: SchemaRef,
+    },
+}
+
+/// Encodes the batches of an export one at a time, each into the bytes to append to the output.
+pub struct ExportWriter {
+    columns: &'static [Column],
+    encoder: Encoder,
+}
+
+impl ExportWriter {
+    pub fn new(format: Format, dataset: Dataset) -> Result<Self, ExportError> {
+        let columns = dataset.columns();
+        let encoder = match format {
+            Format::Csv => Encoder::Csv {
+                header_written: false,
+            },
+            Format::Ndjson => Encoder::Ndjson,
+            Format::Parquet => {
+                let schema = Arc::new(Schema::new(
+                    columns
+                        .iter()
+                        .map(|column| {
+                            let data_type = match column.column_type {
+                                ColumnType::Utf8 => DataType::Utf8,
+                                ColumnType::Int64 => DataType::Int64,
+                                ColumnType::UInt64 => DataType::UInt64,
+                                ColumnType::Boolean => DataType::Boolean,
+                            };
+                            Field::new(column.name, data_type, true)
+                        })
+                        .collect::<Vec<_>>(),
+                ));
+                let buffer = SharedBuffer::default();
+                let writer = Box::new(ArrowWriter::try_new(
+                    buffer.clone(),
+                    Arc::clone(&schema),
+                    None,
+                )?);
+                Encoder::Parquet {
+                    writer,
+                    buffer,
+                    schema,
+                }
+            }
+        };
+        Ok(ExportWriter { columns, encoder })
+    }
+
+    /// Encodes the rows, as a row group of their own for Parquet.
+    pub fn write_batch(&mut self, rows: &[Row]) -> Result<Vec<u8>, ExportError> {
+        match &mut self.encoder {
+            Encoder::Csv { header_written } => {
+                let mut writer = csv::Writer::from_writer(Vec::new());
+                if !*header_written {
+                    writer.write_record(self.columns.iter().map(|column| column.name))?;
+                    *header_written = true;
+                }
+                for row in rows {
+                    writer.write_record(self.columns.iter().map(
+                        |column| match row.get(column.name) {
+                            None | Some(Value::Null) => String::new(),
+                            Some(Value::String(value)) => value.clone(),
+                            Some(value) => value.to_string(),
+                        },
+                    ))?;
+                }
+                writer
+                    .into_inner()
+                    .map_err(|e| ExportError::Csv(e.into_error().into()))
+            }
+            Encoder::Ndjson => {
+                let mut bytes = Vec::new();
+                for row in rows {
+                    serde_json::to_writer(&mut bytes, row)?;
+                    bytes.push(b'\n');
+                }
+                Ok(bytes)
+            }
+            Encoder::Parquet {
+                writer,
+                buffer,
+                schema,
+            } => {
+                if rows.is_empty() {
+                    return Ok(Vec::new());
+                }
+                writer.write(&record_batch(schema, self.columns, rows)?)?;
+                writer.flush()?;
+                Ok(buffer.take())
+            }
+        }
+    }
+
+    /// Bytes ending the output: the CSV header of an empty export or the Parquet footer.
+    pub fn finish(mut self) -> Result<Vec<u8>, ExportError> {
+        match self.encoder {
+            Encoder::Csv {
+                header_written: false,
+            } => self.write_batch(&[]),
+            Encoder::Parquet { writer, buffer, .. } => {
+                writer.close()?;
+                Ok(buffer.take())
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+fn record_batch(
+    schema: &SchemaRef, columns: &[Column], rows: &[Row],
+) -> Result<RecordBatch, ArrowError> {
+    let arrays = columns
+        .iter()
+        .map(|column| {
+            let values = rows.iter().map(|row| row.get(column.name));
+            match column.column_type {
+                ColumnType::Utf8 => Arc::new(
+                    values
+                        .map(|value| value.and_then(Value::as_str))
+                        .collect::<StringArray>(),
+                ) as ArrayRef,
+                ColumnType::Int64 => Arc::new(
+                    values
+                        .map(|value| value.and_then(Value::as_i64))
+                        .collect::<Int64Array>(),
+                ),
+                ColumnType::UInt64 => Arc::new(
+                    values
+                        .map(|value| value.and_then(Value::as_u64))
+                        .collect::<UInt64Array>(),
+                ),
+                ColumnType::Boolean => Arc::new(
+                    values
+                        .map(|value| value.and_then(Value::as_bool))
+                        .collect::<BooleanArray>(),
+                ),
+            }
+        })
+        .collect();
+    RecordBatch::try_new(Arc::clone(schema), arrays)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde_json::json;
+
+    fn rows() -> Vec<Row> {
+        [
+            json!({"slot": 1, "block_time": 100, "blockhash": "Hash, \"quoted\"", "previous_blockhash": "Previous", "parent_slot": 0, "block_height": null, "transaction_count": 2}),
+            json!({"slot": 2, "block_time": null, "blockhash": "Hash", "previous_blockhash": "Previous", "parent_slot": 1, "block_height": 2, "transaction_count": 0}),
+        ]
+        .into_iter()
+        .map(|row| row.as_object().unwrap().clone())
+        .collect()
+    }
+
+    fn export(format: Format, batches: &[&[Row]]) -> Vec<u8> {
+        let mut writer = ExportWriter::new(format, Dataset::Blocks).unwrap();
+        let mut output = Vec::new();
+        for batch in batches {
+            output.extend(writer.write_batch(batch).unwrap());
+        }
+        output.extend(writer.finish().unwrap());
+        output
+    }
+
+    #[test]
+    fn test_csv() {
+        let rows = rows();
+        let output = String::from_utf8(export(Format::Csv, &[&rows[.. 1], &rows[1 ..]])).unwrap();
+
+        assert_eq!(
+            output,
+            "slot,block_time,blockhash,previous_blockhash,parent_slot,block_height,transaction_count\n\
+             1,100,\"Hash, \"\"quoted\"\"\",Previous,0,,2\n\
+             2,,Hash,Previous,1,2,0\n"
+        );
+        assert_eq!(
+            String::from_utf8(export(Format::Csv, &[]))
+                .unwrap()
+                .lines()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let rows = rows();
+        let output = String::from_utf8(export(Format::Ndjson, &[&rows])).unwrap();
+
+        let lines: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["parent_slot"], 1);
+    }
+
+    #[test]
+    fn test_parquet() {
+        let rows = rows();
+        let output = export(Format::Parquet, &[&rows[.. 1], &[], &rows[1 ..]]);
+
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&output).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        let batches: Vec<RecordBatch> = reader.build().unwrap().map(Result::unwrap).collect();
+        let column = |name: &str| -> Vec<ArrayRef> {
+            batches
+                .iter()
+                .map(|batch| Arc::clone(batch.column_by_name(name).unwrap()))
+                .collect()
+        };
+        let slots: Vec<Option<u64>> = column("slot")
+            .iter()
+            .flat_map(|array| array.as_any().downcast_ref::<UInt64Array>().unwrap())
+            .collect();
+        assert_eq!(slots, vec![Some(1), Some(2)]);
+        let block_times: Vec<Option<i64>> = column("block_time")
+            .iter()
+            .flat_map(|array| array.as_any().downcast_ref::<Int64Array>().unwrap())
+            .collect();
+        assert_eq!(block_times, vec![Some(100), None]);
+    }
+}
@@ -1,49 +1,117 @@
+mod alerts;
 mod api;
 mod config;
 mod data_processing;
 mod data_retrieval;
 mod data_storage;
-use crate::{api::routes, data_retrieval::PubSubSolanaClient};
+mod export;
+#[cfg(test)]
+mod test_fixtures;
+mod webhooks;
+use crate::{
+    alerts::AlertEngine,
+    api::{
+        health::{ConfigSummary, ServiceInfo},
+        routes,
+    },
+    data_retrieval::PubSubSolanaClient,
+    export::cli::{Cli, Command},
+};
 use axum::http::{
-    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    header::{HeaderName, ACCEPT, AUTHORIZATION, CONTENT_TYPE},
     Method,
 };
+use clap::Parser;
 use config::Config;
-use data_processing::Processor;
-use data_retrieval::RpcSolanaClient;
+use data_processing::{Processor, TokenRegistry};
+use data_retrieval::{
+    stream_hub::{StreamHub, Topic},
+    RpcSolanaClient,
+};
+use data_storage::Storage;
 use log::warn;
 use std::{env, error::Error, net::SocketAddr, sync::Arc};
+use tokio::sync::broadcast::error::RecvError;
 use tower_http::cors::CorsLayer;
 use tracing::{error, info, Level};
-use tracing_subscriber;
+use webhooks::{sender::WebhookSender, WebhookDispatcher};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     // Initialize logging
     tracing_subscriber::fmt().with_max_level(Level::INFO).init();
 
+    if let Some(Command::Export(args)) = Cli::parse().command {
+        return export::cli::export(args).await;
+    }
+
     // Load configuration
     let config_path = env::var("CONFIG_PATH").unwrap_or_else(|_| {
         warn!("CONFIG_PATH not set, using default 'config.toml'");
         "config.toml".to_string()
     });
     let config = Config::from_file(&config_path)?;
+    let service_info = Arc::new(ServiceInfo::new(
+        ConfigSummary::from(&config),
+        config.health.clone(),
+    ));
 
     // Initialize Solana client
-    let rpc_solana_client = Arc::new(RpcSolanaClient::new(&config.solana_rpc_url));
+    let rpc_solana_client = Arc::new(RpcSolanaClient::new(
+        &config.endpoints(),
+        config.pool.clone(),
+        config.rate_limit.clone(),
+        config.retry.clone(),
+        config.cache.clone(),
+        config.commitment,
+    ));
+    let _health_check_task = rpc_solana_client.spawn_health_checks();
     let processor = Arc::new(Processor {});
+    if config.database_url.is_some() {
+        warn!("database_url is set but only in-memory storage is supported, ignoring it");
+    }
+    let storage = Arc::new(Storage::with_config(config.storage.clone()));
+    let token_registry = Arc::new(TokenRegistry::new(
+        Arc::clone(&rpc_solana_client),
+        Arc::clone(&storage),
+    ));
 
-    let pub_sub_solana_client = Arc::new(PubSubSolanaClient::new(config.solana_ws_url));
+    let pub_sub_solana_client = Arc::new(PubSubSolanaClient::new(rpc_solana_client.ws_urls()));
+    let stream_hub = Arc::new(StreamHub::new(
+        pub_sub_solana_client,
+        Arc::clone(&rpc_solana_client),
+        config.stream.clone(),
+    ));
+    let webhook_dispatcher = Arc::new(WebhookDispatcher::new(
+        Arc::clone(&rpc_solana_client),
+        Arc::clone(&processor),
+        Arc::clone(&token_registry),
+        Arc::clone(&storage),
+        Arc::clone(&stream_hub),
+        WebhookSender::new(config.webhooks.clone(), Arc::clone(&storage)),
+    ));
+    let alert_engine = Arc::new(AlertEngine::new(
+        config.alerts.clone(),
+        Arc::clone(&rpc_solana_client),
+        Arc::clone(&processor),
+        Arc::clone(&storage),
+        Arc::clone(&stream_hub),
+    ));
+    let _alert_tasks = alert_engine.start();
 
+    let commitment = rpc_solana_client.commitment(None);
     let _recent_blockhash = rpc_solana_client.get_recent_blockhash().await?;
 
     // Process transaction
     match rpc_solana_client
-        .get_transaction(&config.transaction_signature)
+        .get_transaction(&config.transaction_signature, commitment)
         .await
     {
         Ok(encoded_transaction) => {
-            if let Some(transaction_data) = processor.process_transaction(encoded_transaction) {
+            if let Some(mut transaction_data) = processor.process_transaction(encoded_transaction) {
+                token_registry
+                    .enrich_transaction(&mut transaction_data)
+                    .await;
                 info!("Processed Transaction Data: {:?}", transaction_data);
             } else {
                 info!("Failed to process the transaction or unsupported format");
@@ -53,9 +121,19 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
 
     // Process account information
-    match rpc_solana_client.get_account(&config.account_pubkey).await {
-        Ok(account) => {
-            if let Some(account_data) = processor.process_account(account) {
+    match rpc_solana_client
+        .get_account(&config.account_pubkey, commitment, None)
+        .await
+    {
+        Ok(response) => {
+            if let Some(mut account_data) = processor.process_account(
+                response.value,
+                response.context.slot,
+                response.fetched_at,
+            ) {
+                token_registry
+                    .enrich_account(&config.account_pubkey, &mut account_data)
+                    .await;
                 info!("Processed Account Data: {:?}", account_data);
             } else {
                 info!("Failed to process Account");
@@ -64,42 +142,68 @@ async fn main() -> Result<(), Box<dyn Error>> {
         Err(e) => error!("Failed to fetch account: {}", e),
     }
 
-    // Spawn a task for the logs subscription
-    let pub_sub_solana_client_clone = Arc::clone(&pub_sub_solana_client);
+    // Spawn a task for the logs subscription, shared with the logs streams of the API
+    let stream_hub_clone = Arc::clone(&stream_hub);
     let logs_subscription_task = tokio::spawn(async move {
-        if let Ok(logs_subscription) = pub_sub_solana_client_clone.subscribe_logs() {
-            let (mut _log_subscription_client, log_subscription_receiver) = logs_subscription;
-            while let Ok(response) = log_subscription_receiver.recv() {
-                info!("logs subscription response: {:?}", response);
+        match stream_hub_clone.subscribe_internal(Topic::Logs(None), commitment) {
+            Ok(mut logs_receiver) => {
+                loop {
+                    match logs_receiver.recv().await {
+                        Ok(event) => info!("logs subscription response: {}", event.data),
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("Skipped {} logs subscription responses", skipped)
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+                error!("Logs subscription closed");
             }
-            error!("Error or disconnect in logs subscription");
-        } else {
-            error!("Error subscribing to logs");
+            Err(e) => error!("Error subscribing to logs: {}", e),
         }
     });
 
-    let pub_sub_solana_client_clone = Arc::clone(&pub_sub_solana_client);
+    // The hub drops the cached account on every update, so this task only logs them
+    let stream_hub_clone = Arc::clone(&stream_hub);
     let account_subscription_task = tokio::spawn(async move {
-        if let Ok(account_subscription) =
-            pub_sub_solana_client_clone.subscribe_account(&config.account_pubkey)
-        {
-            let (mut _account_subscription_client, account_subscription_receiver) =
-                account_subscription;
-            while let Ok(response) = account_subscription_receiver.recv() {
-                info!("account subscription response: {:?}", response);
+        let topic = Topic::Account(config.account_pubkey.clone());
+        match stream_hub_clone.subscribe_internal(topic, commitment) {
+            Ok(mut account_receiver) => {
+                loop {
+                    match account_receiver.recv().await {
+                        Ok(event) => info!("account subscription response: {}", event.data),
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("Skipped {} account subscription responses", skipped)
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+                error!("Account subscription closed");
             }
-            error!("Error or disconnect in account subscription");
-        } else {
-            error!("Error subscribing to account updates");
+            Err(e) => error!("Error subscribing to account updates: {}", e),
         }
     });
 
     let cors = CorsLayer::new()
-        .allow_methods([Method::GET])
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
         .allow_credentials(true)
-        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE]);
+        .allow_headers([
+            AUTHORIZATION,
+            ACCEPT,
+            CONTENT_TYPE,
+            HeaderName::from_static("last-event-id"),
+        ]);
 
-    let app = routes::create_router(rpc_solana_client, processor).layer(cors);
+    let app = routes::create_router(
+        rpc_solana_client,
+        processor,
+        token_registry,
+        storage,
+        stream_hub,
+        webhook_dispatcher,
+        service_info,
+        config.docs.clone(),
+    )
+    .layer(cors);
     let addr = SocketAddr::new(config.api_bind_address.parse()?, config.port.parse()?);
 
     println!("🚀 Server started successfully");
@@ -0,0 +1,140 @@
+//! Services and data shared by the unit tests.
+
+use crate::{
+    api::{
+        health::{ConfigSummary, ServiceInfo},
+        routes::create_router,
+    },
+    config::config::{
+        CacheConfig, DocsConfig, EndpointConfig, HealthConfig, PoolConfig, RateLimitConfig,
+        RetryConfig, StreamConfig, WebhookConfig,
+    },
+    data_processing::{processor::TransactionData, Processor, TokenRegistry},
+    data_retrieval::{stream_hub::StreamHub, PubSubSolanaClient, RpcSolanaClient},
+    data_storage::Storage,
+    webhooks::{sender::WebhookSender, WebhookDispatcher},
+};
+use axum::{handler::Handler, routing::post, Router};
+use serde_json::{json, Value};
+use solana_sdk::commitment_config::CommitmentLevel;
+use std::sync::Arc;
+
+/// Address of an RPC node nobody listens to, for tests that never reach it.
+pub const UNREACHABLE_RPC_URL: &str = "http://127.0.0.1:8899";
+
+/// Serves `handler` as a JSON-RPC node on a local port, returning its URL.
+pub async fn serve_rpc<H, T>(handler: H) -> String
+where
+    H: Handler<T, ()>,
+    T: 'static,
+{
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let app = Router::new().route("/", post(handler));
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    url
+}
+
+/// Client of the node at `rpc_url` with the default pool, rate limit, retry and cache settings.
+pub fn rpc_client(rpc_url: &str) -> Arc<RpcSolanaClient> {
+    let endpoint = EndpointConfig {
+        rpc_url:             rpc_url.to_string(),
+        ws_url:              None,
+        weight:              1,
+        requests_per_second: None,
+        burst:               None,
+        monthly_credits:     None,
+        batching:            None,
+    };
+    Arc::new(RpcSolanaClient::new(
+        &[endpoint],
+        PoolConfig::default(),
+        RateLimitConfig::default(),
+        RetryConfig::default(),
+        CacheConfig::default(),
+        CommitmentLevel::Confirmed,
+    ))
+}
+
+/// Stream hub without websocket endpoint, so its subscriptions never connect.
+pub fn stream_hub(rpc_solana_client: Arc<RpcSolanaClient>, config: StreamConfig) -> Arc<StreamHub> {
+    Arc::new(StreamHub::new(
+        Arc::new(PubSubSolanaClient::new(vec![])),
+        rpc_solana_client,
+        config,
+    ))
+}
+
+/// Services of the API around one storage, none of them reaching an RPC node.
+pub struct Services {
+    pub rpc_solana_client:  Arc<RpcSolanaClient>,
+    pub token_registry:     Arc<TokenRegistry>,
+    pub storage:            Arc<Storage>,
+    pub stream_hub:         Arc<StreamHub>,
+    pub webhook_dispatcher: Arc<WebhookDispatcher>,
+}
+
+impl Services {
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Services::with_stream_config(storage, StreamConfig::default())
+    }
+
+    pub fn with_stream_config(storage: Arc<Storage>, stream_config: StreamConfig) -> Self {
+        let rpc_solana_client = rpc_client(UNREACHABLE_RPC_URL);
+        let token_registry = Arc::new(TokenRegistry::new(
+            Arc::clone(&rpc_solana_client),
+            Arc::clone(&storage),
+        ));
+        let stream_hub = stream_hub(Arc::clone(&rpc_solana_client), stream_config);
+        let webhook_dispatcher = Arc::new(WebhookDispatcher::new(
+            Arc::clone(&rpc_solana_client),
+            Arc::new(Processor),
+            Arc::clone(&token_registry),
+            Arc::clone(&storage),
+            Arc::clone(&stream_hub),
+            WebhookSender::new(WebhookConfig::default(), Arc::clone(&storage)),
+        ));
+        Services {
+            rpc_solana_client,
+            token_registry,
+            storage,
+            stream_hub,
+            webhook_dispatcher,
+        }
+    }
+
+    /// The router of the whole API.
+    pub fn router(self) -> Router {
+        create_router(
+            self.rpc_solana_client,
+            Arc::new(Processor),
+            self.token_registry,
+            self.storage,
+            self.stream_hub,
+            self.webhook_dispatcher,
+            Arc::new(ServiceInfo::new(
+                ConfigSummary::default(),
+                HealthConfig::default(),
+            )),
+            DocsConfig::default(),
+        )
+    }
+}
+
+/// Successful transaction `Signature1` at slot 1 without accounts or instructions, with `fields`
+/// replacing any of its fields.
+pub fn transaction(fields: Value) -> TransactionData {
+    let mut transaction = json!({
+        "signatures": ["Signature1"],
+        "blockhash": "Blockhash",
+        "accounts": [],
+        "instructions": [],
+        "token_balances": [],
+        "slot": 1,
+        "block_time": null
+    });
+    if let (Some(transaction), Value::Object(fields)) = (transaction.as_object_mut(), fields) {
+        transaction.extend(fields);
+    }
+    serde_json::from_value(transaction).unwrap()
+}
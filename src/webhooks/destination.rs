@@ -0,0 +1,159 @@
+use hyper_014::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    Url,
+};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+
+/// Whether the address is reachable on the internet, as opposed to loopback, link-local (cloud
+/// metadata included), private, shared or otherwise reserved ranges.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+    let shared = first == 100 && second & 0xc0 == 64;
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || shared)
+}
+
+fn is_allowed(host: &str, allowed_hosts: &[String]) -> bool {
+    allowed_hosts
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(host))
+}
+
+/// Checks that the webhook URL does not name a non-public host, unless it is in `allowed_hosts`.
+///
+/// Only literal addresses and `localhost` can be refused here, the addresses a domain resolves to
+/// are checked by `PublicResolver` on every delivery.
+pub fn check_destination(url: &Url, allowed_hosts: &[String]) -> Result<(), String> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("url {} has no host", url))?
+        .trim_matches(['[', ']']);
+    if is_allowed(host, allowed_hosts) {
+        return Ok(());
+    }
+
+    let public = match host.parse::<IpAddr>() {
+        Ok(ip) => is_public(ip),
+        Err(_) => {
+            let host = host.trim_end_matches('.').to_ascii_lowercase();
+            host != "localhost" && !host.ends_with(".localhost")
+        }
+    };
+    match public {
+        true => Ok(()),
+        false => Err(format!("url {} is not a public destination", url)),
+    }
+}
+
+/// Resolver of the webhook client, dropping the non-public addresses of hosts not in
+/// `allowed_hosts`, so a domain cannot point deliveries to the internal network.
+pub struct PublicResolver {
+    pub allowed_hosts: Arc<Vec<String>>,
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let allowed_hosts = Arc::clone(&self.allowed_hosts);
+        Box::pin(async move {
+            let host = name.as_str();
+            let allowed = is_allowed(host, &allowed_hosts);
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+                .await?
+                .filter(|addr| allowed || is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_destination() {
+        let allowed_hosts = vec!["hooks.internal".to_string(), "10.0.0.5".to_string()];
+        for url in [
+            "https://example.com/hook",
+            "http://8.8.8.8/hook",
+            "http://[2001:4860:4860::8888]/hook",
+            "http://hooks.internal/hook",
+            "http://10.0.0.5:8080/hook",
+        ] {
+            assert!(
+                check_destination(&Url::parse(url).unwrap(), &allowed_hosts).is_ok(),
+                "{}",
+                url
+            );
+        }
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://10.0.0.1/hook",
+            "http://192.168.1.1/hook",
+            "http://100.64.0.1/hook",
+            "http://0.0.0.0/hook",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "http://localhost:3000/hook",
+            "http://api.localhost/hook",
+        ] {
+            assert!(
+                check_destination(&Url::parse(url).unwrap(), &allowed_hosts).is_err(),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_public_resolver_drops_private_addresses() {
+        let resolver = PublicResolver {
+            allowed_hosts: Arc::new(vec![]),
+        };
+        assert!(resolver
+            .resolve("localhost".parse().unwrap())
+            .await
+            .is_err());
+
+        let resolver = PublicResolver {
+            allowed_hosts: Arc::new(vec!["localhost".to_string()]),
+        };
+        let addrs: Vec<_> = resolver
+            .resolve("localhost".parse().unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert!(addrs.iter().all(|addr| addr.ip().is_loopback()));
+    }
+}
@@ -0,0 +1,367 @@
+use crate::{
+    data_processing::{Processor, TokenRegistry},
+    data_retrieval::{
+        error::RetrievalError,
+        stream_hub::{StreamHub, Topic},
+        RpcSolanaClient,
+    },
+    data_storage::Storage,
+    webhooks::{
+        destination::check_destination,
+        sender::WebhookSender,
+        webhook::{new_id, new_secret, Webhook, WebhookFilter},
+    },
+};
+use chrono::Utc;
+use reqwest::Url;
+use solana_client::rpc_response::{Response, RpcLogsResponse};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::broadcast::error::RecvError, task::JoinHandle};
+use tracing::{error, warn};
+
+/// Delay before subscribing again after the first failure, doubled up to `MAX_WATCH_RETRY_DELAY`.
+const WATCH_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_WATCH_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Watches the addresses of the registered webhooks and hands them the matching transactions.
+///
+/// Each watched address has one task listening to the logs mentioning it. A successful
+/// transaction is fetched, processed and enriched once, then delivered to every webhook of the
+/// address whose filter it matches. Deliveries beyond `max_concurrent_deliveries` hold the watch
+/// back until one finishes.
+pub struct WebhookDispatcher {
+    rpc_solana_client: Arc<RpcSolanaClient>,
+    processor:         Arc<Processor>,
+    token_registry:    Arc<TokenRegistry>,
+    storage:           Arc<Storage>,
+    stream_hub:        Arc<StreamHub>,
+    sender:            Arc<WebhookSender>,
+    /// Watch task of every address with at least one webhook.
+    watches:           Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(
+        rpc_solana_client: Arc<RpcSolanaClient>, processor: Arc<Processor>,
+        token_registry: Arc<TokenRegistry>, storage: Arc<Storage>, stream_hub: Arc<StreamHub>,
+        sender: WebhookSender,
+    ) -> Self {
+        WebhookDispatcher {
+            rpc_solana_client,
+            processor,
+            token_registry,
+            storage,
+            stream_hub,
+            sender: Arc::new(sender),
+            watches: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Validates and stores the webhook, then starts watching its address. Non-public destinations
+    /// are refused unless their host is in `allowed_hosts`.
+    pub fn register(
+        self: &Arc<Self>, url: &str, filter: WebhookFilter,
+    ) -> Result<Webhook, RetrievalError> {
+        let parsed_url = Url::parse(url)
+            .map_err(|e| RetrievalError::invalid_input(format!("invalid url {}: {}", url, e)))?;
+        if !matches!(parsed_url.scheme(), "http" | "https") {
+            return Err(RetrievalError::invalid_input(format!(
+                "url {} is not http or https",
+                url
+            )));
+        }
+        let config = self.sender.config();
+        check_destination(&parsed_url, &config.allowed_hosts)
+            .map_err(RetrievalError::invalid_input)?;
+        if self.storage.get_webhooks().len() >= config.max_webhooks {
+            return Err(RetrievalError::invalid_input(format!(
+                "at most {} webhooks can be registered",
+                config.max_webhooks
+            )));
+        }
+        for address in [&filter.account, &filter.program_id].into_iter().flatten() {
+            Pubkey::from_str(address).map_err(|e| RetrievalError::invalid_pubkey(address, e))?;
+        }
+        let Some(address) = filter.watched_address().map(str::to_string) else {
+            return Err(RetrievalError::invalid_input(
+                "a webhook filter needs an account or a program_id",
+            ));
+        };
+
+        let webhook = Webhook {
+            id: new_id(),
+            url: url.to_string(),
+            filter,
+            created_at: Utc::now().timestamp(),
+            secret: new_secret(),
+        };
+        self.storage.insert_webhook(webhook.clone());
+        self.watch(address);
+        Ok(webhook)
+    }
+
+    /// Removes the webhook, stopping the watch of its address when no other webhook uses it.
+    pub fn delete(&self, id: &str) -> Option<Webhook> {
+        let webhook = self.storage.remove_webhook(id)?;
+        if let Some(address) = webhook.filter.watched_address() {
+            if self.webhooks_of(address).is_empty() {
+                if let Some(watch) = self.watches.lock().unwrap().remove(address) {
+                    watch.abort();
+                }
+            }
+        }
+        Some(webhook)
+    }
+
+    fn webhooks_of(&self, address: &str) -> Vec<Webhook> {
+        self.storage
+            .get_webhooks()
+            .into_iter()
+            .filter(|webhook| webhook.filter.watched_address() == Some(address))
+            .collect()
+    }
+
+    fn watch(self: &Arc<Self>, address: String) {
+        let mut watches = self.watches.lock().unwrap();
+        if watches.contains_key(&address) {
+            return;
+        }
+
+        let commitment = self.rpc_solana_client.transaction_commitment();
+        let dispatcher = Arc::clone(self);
+        let topic = Topic::Logs(Some(address.clone()));
+        let watched_address = address.clone();
+        let watch = tokio::spawn(async move {
+            let mut delay = WATCH_RETRY_DELAY;
+            let mut receiver = loop {
+                match dispatcher
+                    .stream_hub
+                    .subscribe_internal(topic.clone(), commitment)
+                {
+                    Ok(receiver) => break receiver,
+                    Err(e) => {
+                        error!(
+                            "Error watching {} for webhooks, retrying in {:?}: {}",
+                            watched_address, delay, e
+                        );
+                        tokio::time::sleep(delay).await;
+                        delay = (delay * 2).min(MAX_WATCH_RETRY_DELAY);
+                    }
+                }
+            };
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        match serde_json::from_value::<Response<RpcLogsResponse>>(event.data) {
+                            Ok(response) if response.value.err.is_none() => {
+                                dispatcher
+                                    .dispatch(
+                                        &watched_address,
+                                        &response.value.signature,
+                                        commitment,
+                                    )
+                                    .await
+                            }
+                            Ok(_) => {}
+                            Err(e) => warn!("Unexpected logs notification: {}", e),
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => warn!(
+                        "Skipped {} transactions of {} for webhooks",
+                        skipped, watched_address
+                    ),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+        watches.insert(address, watch);
+    }
+
+    /// Processes the transaction and delivers it to the matching webhooks of the address.
+    async fn dispatch(&self, address: &str, signature: &str, commitment: CommitmentConfig) {
+        let encoded_transaction = match self
+            .rpc_solana_client
+            .get_transaction(signature, commitment)
+            .await
+        {
+            Ok(encoded_transaction) => encoded_transaction,
+            Err(e) => {
+                error!(
+                    "Error fetching transaction {} for webhooks: {}",
+                    signature, e
+                );
+                return;
+            }
+        };
+        let Some(mut transaction_data) = self.processor.process_transaction(encoded_transaction)
+        else {
+            error!(
+                "Unsupported format of transaction {} for webhooks",
+                signature
+            );
+            return;
+        };
+        self.token_registry
+            .enrich_transaction(&mut transaction_data)
+            .await;
+        self.storage.insert_transaction(transaction_data.clone());
+
+        for webhook in self.webhooks_of(address) {
+            if webhook.filter.matches(&transaction_data) {
+                self.sender
+                    .spawn_delivery(webhook, transaction_data.clone())
+                    .await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::config::{StreamConfig, WebhookConfig},
+        data_retrieval::error::RetrievalErrorKind,
+        test_fixtures::Services,
+    };
+
+    fn dispatcher(storage: Arc<Storage>) -> Arc<WebhookDispatcher> {
+        dispatcher_with_channels(storage, StreamConfig::default().max_channels)
+    }
+
+    fn dispatcher_with_channels(
+        storage: Arc<Storage>, max_channels: usize,
+    ) -> Arc<WebhookDispatcher> {
+        let stream_config = StreamConfig {
+            max_channels,
+            ..StreamConfig::default()
+        };
+        Services::with_stream_config(storage, stream_config).webhook_dispatcher
+    }
+
+    fn filter(account: Option<&str>, program_id: Option<&str>) -> WebhookFilter {
+        WebhookFilter {
+            account: account.map(str::to_string),
+            program_id: program_id.map(str::to_string),
+            ..WebhookFilter::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_and_delete_share_watch() {
+        let storage = Arc::new(Storage::default());
+        let dispatcher = dispatcher(Arc::clone(&storage));
+        let account = Pubkey::new_unique().to_string();
+
+        let first = dispatcher
+            .register("https://example.com/hook", filter(Some(&account), None))
+            .unwrap();
+        let second = dispatcher
+            .register("http://example.com/other", filter(Some(&account), None))
+            .unwrap();
+
+        assert_eq!(storage.get_webhooks().len(), 2);
+        assert_eq!(first.secret.len(), 64);
+        assert_ne!(first.secret, second.secret);
+        assert_eq!(dispatcher.watches.lock().unwrap().len(), 1);
+
+        assert!(dispatcher.delete(&first.id).is_some());
+        assert_eq!(dispatcher.watches.lock().unwrap().len(), 1);
+        assert!(dispatcher.delete(&second.id).is_some());
+        assert!(dispatcher.watches.lock().unwrap().is_empty());
+        assert!(dispatcher.delete(&second.id).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_invalid_webhooks() {
+        let storage = Arc::new(Storage::default());
+        let dispatcher = dispatcher(Arc::clone(&storage));
+        let account = Pubkey::new_unique().to_string();
+
+        for (url, filter) in [
+            ("ftp://example.com/hook", filter(Some(&account), None)),
+            ("not a url", filter(Some(&account), None)),
+            (
+                "https://example.com/hook",
+                filter(Some("not-a-pubkey"), None),
+            ),
+            ("https://example.com/hook", filter(None, None)),
+        ] {
+            let e = dispatcher.register(url, filter).unwrap_err();
+            assert_eq!(e.kind(), RetrievalErrorKind::InvalidInput);
+        }
+        assert!(storage.get_webhooks().is_empty());
+        assert!(dispatcher.watches.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_non_public_destinations() {
+        let storage = Arc::new(Storage::default());
+        let dispatcher = dispatcher(Arc::clone(&storage));
+        let account = Pubkey::new_unique().to_string();
+
+        for url in [
+            "http://169.254.169.254/latest/meta-data",
+            "http://127.0.0.1:8080/hook",
+            "http://10.0.0.1/hook",
+            "http://localhost/hook",
+        ] {
+            let e = dispatcher
+                .register(url, filter(Some(&account), None))
+                .unwrap_err();
+            assert_eq!(e.kind(), RetrievalErrorKind::InvalidInput);
+        }
+        assert!(storage.get_webhooks().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_register_limits_webhooks() {
+        let storage = Arc::new(Storage::default());
+        let services = Services::new(Arc::clone(&storage));
+        let config = WebhookConfig {
+            max_webhooks: 1,
+            allowed_hosts: vec!["127.0.0.1".to_string()],
+            ..WebhookConfig::default()
+        };
+        let dispatcher = Arc::new(WebhookDispatcher::new(
+            services.rpc_solana_client,
+            Arc::new(Processor),
+            services.token_registry,
+            Arc::clone(&storage),
+            services.stream_hub,
+            WebhookSender::new(config, Arc::clone(&storage)),
+        ));
+        let account = Pubkey::new_unique().to_string();
+
+        dispatcher
+            .register("http://127.0.0.1:8080/hook", filter(Some(&account), None))
+            .unwrap();
+        let e = dispatcher
+            .register("https://example.com/hook", filter(Some(&account), None))
+            .unwrap_err();
+
+        assert_eq!(e.kind(), RetrievalErrorKind::InvalidInput);
+        assert_eq!(storage.get_webhooks().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_watch_retries_subscription() {
+        let storage = Arc::new(Storage::default());
+        let dispatcher = dispatcher_with_channels(Arc::clone(&storage), 0);
+        let account = Pubkey::new_unique().to_string();
+
+        dispatcher
+            .register("https://example.com/hook", filter(Some(&account), None))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let watches = dispatcher.watches.lock().unwrap();
+        assert!(!watches[&account].is_finished());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod destination;
+pub mod dispatcher;
+pub mod sender;
+pub mod webhook;
+
+pub use dispatcher::WebhookDispatcher;
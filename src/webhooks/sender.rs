@@ -0,0 +1,298 @@
+use crate::{
+    config::config::WebhookConfig,
+    data_processing::processor::TransactionData,
+    data_storage::Storage,
+    webhooks::{
+        destination::PublicResolver,
+        webhook::{new_id, sign, DeadLetter, Delivery, DeliveryStatus, Webhook},
+    },
+};
+use chrono::Utc;
+use reqwest::{header::CONTENT_TYPE, redirect::Policy};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+/// POSTs payloads to webhooks, retrying with exponential backoff and recording every delivery.
+pub struct WebhookSender {
+    client:     reqwest::Client,
+    config:     WebhookConfig,
+    storage:    Arc<Storage>,
+    /// One permit per delivery allowed to be in progress.
+    deliveries: Arc<Semaphore>,
+}
+
+impl WebhookSender {
+    pub fn new(config: WebhookConfig, storage: Arc<Storage>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .redirect(Policy::none())
+            .dns_resolver(Arc::new(PublicResolver {
+                allowed_hosts: Arc::new(config.allowed_hosts.clone()),
+            }))
+            .build()
+            .expect("default TLS backend is available");
+        WebhookSender {
+            client,
+            deliveries: Arc::new(Semaphore::new(config.max_concurrent_deliveries.max(1))),
+            config,
+            storage,
+        }
+    }
+
+    pub fn config(&self) -> &WebhookConfig {
+        &self.config
+    }
+
+    /// Delivers the transaction in the background, first waiting while `max_concurrent_deliveries`
+    /// are in progress.
+    pub async fn spawn_delivery(
+        self: &Arc<Self>, webhook: Webhook, transaction_data: TransactionData,
+    ) {
+        let permit = Arc::clone(&self.deliveries)
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+        let sender = Arc::clone(self);
+        tokio::spawn(async move {
+            sender.deliver(&webhook, transaction_data).await;
+            drop(permit);
+        });
+    }
+
+    /// Delivers the transaction, moving it to the dead letters once every attempt failed.
+    pub async fn deliver(&self, webhook: &Webhook, transaction_data: TransactionData) {
+        let body = match serde_json::to_string(&transaction_data) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to encode payload of webhook {}: {}", webhook.id, e);
+                return;
+            }
+        };
+        let now = Utc::now().timestamp();
+        let mut delivery = Delivery {
+            id:          new_id(),
+            webhook_id:  webhook.id.clone(),
+            signature:   transaction_data.signature().map(str::to_string),
+            status:      DeliveryStatus::Pending,
+            attempts:    0,
+            http_status: None,
+            error:       None,
+            created_at:  now,
+            updated_at:  now,
+        };
+
+        let max_attempts = self.config.max_attempts.max(1);
+        loop {
+            delivery.attempts += 1;
+            let result = self.post(webhook, &delivery.id, &body).await;
+            delivery.updated_at = Utc::now().timestamp();
+            match result {
+                Ok(http_status) => {
+                    delivery.status = DeliveryStatus::Delivered;
+                    delivery.http_status = Some(http_status);
+                    delivery.error = None;
+                }
+                Err((http_status, error)) => {
+                    delivery.http_status = http_status;
+                    delivery.error = Some(error);
+                    if delivery.attempts >= max_attempts {
+                        delivery.status = DeliveryStatus::Failed;
+                    }
+                }
+            }
+            self.storage
+                .record_delivery(delivery.clone(), self.config.history_size);
+
+            match delivery.status {
+                DeliveryStatus::Pending => tokio::time::sleep(self.delay(delivery.attempts)).await,
+                DeliveryStatus::Delivered => return,
+                DeliveryStatus::Failed => break,
+            }
+        }
+
+        let error = delivery.error.unwrap_or_default();
+        warn!(
+            "Giving up on delivery {} to webhook {} after {} attempts: {}",
+            delivery.id, webhook.id, delivery.attempts, error
+        );
+        self.storage.insert_dead_letter(
+            DeadLetter {
+                delivery_id: delivery.id,
+                webhook_id: webhook.id.clone(),
+                url: webhook.url.clone(),
+                payload: transaction_data,
+                error,
+                failed_at: delivery.updated_at,
+            },
+            self.config.dead_letter_size,
+        );
+    }
+
+    /// Sends one attempt, returning the HTTP status on success or the failure reason.
+    async fn post(
+        &self, webhook: &Webhook, delivery_id: &str, body: &str,
+    ) -> Result<u16, (Option<u16>, String)> {
+        let timestamp = Utc::now().timestamp();
+        let response = self
+            .client
+            .post(&webhook.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header("X-Webhook-Id", &webhook.id)
+            .header("X-Webhook-Delivery", delivery_id)
+            .header("X-Webhook-Timestamp", timestamp)
+            .header(
+                "X-Webhook-Signature",
+                sign(&webhook.secret, timestamp, body),
+            )
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(|e| (None, e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(status.as_u16())
+        } else {
+            Err((
+                Some(status.as_u16()),
+                format!("webhook responded with {}", status),
+            ))
+        }
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        Duration::from_millis(self.config.base_delay_ms)
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(Duration::from_millis(self.config.max_delay_ms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_fixtures::transaction, webhooks::webhook::WebhookFilter};
+    use axum::{
+        body::Bytes,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Serves a webhook that fails the first `failures` calls, returning its URL and call counter.
+    async fn serve_webhook(failures: u32) -> (String, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let calls_clone = Arc::clone(&calls);
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| {
+                let calls = Arc::clone(&calls_clone);
+                async move {
+                    let timestamp = headers["x-webhook-timestamp"].to_str().unwrap();
+                    let expected = sign(
+                        "secret",
+                        timestamp.parse().unwrap(),
+                        std::str::from_utf8(&body).unwrap(),
+                    );
+                    assert_eq!(headers["x-webhook-signature"].to_str().unwrap(), expected);
+                    if calls.fetch_add(1, Ordering::SeqCst) < failures {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::NO_CONTENT
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}/hook", addr), calls)
+    }
+
+    fn webhook(url: String) -> Webhook {
+        Webhook {
+            id: "webhook".to_string(),
+            url,
+            filter: WebhookFilter::default(),
+            created_at: 0,
+            secret: "secret".to_string(),
+        }
+    }
+
+    fn sender(max_attempts: u32, storage: Arc<Storage>) -> WebhookSender {
+        WebhookSender::new(
+            WebhookConfig {
+                max_attempts,
+                base_delay_ms: 1,
+                max_delay_ms: 5,
+                timeout_ms: 1000,
+                history_size: 10,
+                dead_letter_size: 10,
+                max_concurrent_deliveries: 1,
+                ..WebhookConfig::default()
+            },
+            storage,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_deliver_retries_until_success() {
+        let (url, calls) = serve_webhook(2).await;
+        let storage = Arc::new(Storage::default());
+
+        sender(3, Arc::clone(&storage))
+            .deliver(&webhook(url), transaction(json!({})))
+            .await;
+
+        let deliveries = storage.get_deliveries("webhook");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+        assert_eq!(deliveries[0].attempts, 3);
+        assert_eq!(deliveries[0].http_status, Some(204));
+        assert_eq!(deliveries[0].signature.as_deref(), Some("Signature1"));
+        assert!(storage.get_dead_letters("webhook").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deliver_dead_letters_after_last_attempt() {
+        let (url, calls) = serve_webhook(u32::MAX).await;
+        let storage = Arc::new(Storage::default());
+
+        sender(2, Arc::clone(&storage))
+            .deliver(&webhook(url.clone()), transaction(json!({})))
+            .await;
+
+        let deliveries = storage.get_deliveries("webhook");
+        let dead_letters = storage.get_dead_letters("webhook");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Failed);
+        assert_eq!(deliveries[0].http_status, Some(500));
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].delivery_id, deliveries[0].id);
+        assert_eq!(dead_letters[0].url, url);
+        assert_eq!(dead_letters[0].payload.signature(), Some("Signature1"));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_delivery_waits_for_a_permit() {
+        let (url, calls) = serve_webhook(0).await;
+        let storage = Arc::new(Storage::default());
+        let sender = Arc::new(sender(1, Arc::clone(&storage)));
+
+        sender
+            .spawn_delivery(webhook(url.clone()), transaction(json!({})))
+            .await;
+        assert_eq!(sender.deliveries.available_permits(), 0);
+        sender
+            .spawn_delivery(webhook(url), transaction(json!({})))
+            .await;
+        while storage.get_deliveries("webhook").len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::data_processing::processor::TransactionData;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt::Write;
+use utoipa::ToSchema;
+
+/// Endpoint notified of the processed transactions matching its filter.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Webhook {
+    pub id:         String,
+    pub url:        String,
+    pub filter:     WebhookFilter,
+    pub created_at: i64,
+    /// Key of the `X-Webhook-Signature` HMAC, only returned when the webhook is registered.
+    #[serde(skip_serializing)]
+    pub secret:     String,
+}
+
+/// Conditions a transaction has to meet to be delivered, all of the given ones must hold.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct WebhookFilter {
+    pub account:            Option<String>,
+    pub program_id:         Option<String>,
+    /// Type of an instruction parsed by the node, e.g. `transfer`.
+    pub instruction_type:   Option<String>,
+    /// Lamports gained or lost by `account`, or by any account when no account is given.
+    pub min_lamport_change: Option<u64>,
+}
+
+impl WebhookFilter {
+    /// Address whose transactions are watched, the account if given, otherwise the program.
+    pub fn watched_address(&self) -> Option<&str> {
+        self.account.as_deref().or(self.program_id.as_deref())
+    }
+
+    pub fn matches(&self, transaction_data: &TransactionData) -> bool {
+        let lamport_change = match &self.account {
+            Some(account) => transaction_data.lamport_change(account).unsigned_abs(),
+            None => transaction_data.largest_lamport_change(),
+        };
+        self.account
+            .as_deref()
+            .is_none_or(|account| transaction_data.involves(account))
+            && self
+                .program_id
+                .as_deref()
+                .is_none_or(|program_id| transaction_data.involves(program_id))
+            && self
+                .instruction_type
+                .as_deref()
+                .is_none_or(|instruction_type| {
+                    transaction_data.has_instruction_type(instruction_type)
+                })
+            && self
+                .min_lamport_change
+                .is_none_or(|min_lamport_change| lamport_change >= min_lamport_change)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Still being attempted.
+    Pending,
+    Delivered,
+    /// Every attempt failed, the payload was moved to the dead letters.
+    Failed,
+}
+
+/// One transaction sent to a webhook, with the outcome of its last attempt.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Delivery {
+    pub id:          String,
+    pub webhook_id:  String,
+    pub signature:   Option<String>,
+    pub status:      DeliveryStatus,
+    pub attempts:    u32,
+    pub http_status: Option<u16>,
+    pub error:       Option<String>,
+    pub created_at:  i64,
+    pub updated_at:  i64,
+}
+
+/// Payload that could not be delivered, kept for inspection.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DeadLetter {
+    pub delivery_id: String,
+    pub webhook_id:  String,
+    pub url:         String,
+    pub payload:     TransactionData,
+    pub error:       String,
+    pub failed_at:   i64,
+}
+
+/// Random identifier of webhooks and deliveries.
+pub fn new_id() -> String {
+    random_hex(16)
+}
+
+pub fn new_secret() -> String {
+    random_hex(32)
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+/// Value of the `X-Webhook-Signature` header, an HMAC-SHA256 of `{timestamp}.{body}`.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    format!(
+        "sha256={}",
+        hmac_sha256(secret, &format!("{}.{}", timestamp, body))
+    )
+}
+
+fn hmac_sha256(secret: &str, message: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(message.as_bytes());
+    to_hex(&mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+    use serde_json::json;
+
+    fn transaction() -> TransactionData {
+        test_fixtures::transaction(json!({
+            "accounts": [
+                {"pubkey": "Payer", "writable": true, "signer": true, "lamport_change": -1005000},
+                {"pubkey": "Receiver", "writable": true, "signer": false, "lamport_change": 1000000},
+                {"pubkey": "11111111111111111111111111111111", "writable": false, "signer": false}
+            ],
+            "instructions": [{
+                "program_id": "11111111111111111111111111111111",
+                "data": "",
+                "instruction_type": "transfer"
+            }]
+        }))
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let filter = |filter: serde_json::Value| -> WebhookFilter {
+            serde_json::from_value(filter).unwrap()
+        };
+        let transaction_data = transaction();
+
+        assert!(filter(json!({"account": "Receiver"})).matches(&transaction_data));
+        assert!(filter(json!({
+            "account": "Receiver",
+            "program_id": "11111111111111111111111111111111",
+            "instruction_type": "transfer",
+            "min_lamport_change": 1000000
+        }))
+        .matches(&transaction_data));
+        assert!(!filter(json!({"account": "Other"})).matches(&transaction_data));
+        assert!(
+            !filter(json!({"account": "Receiver", "instruction_type": "approve"}))
+                .matches(&transaction_data)
+        );
+        assert!(
+            !filter(json!({"account": "Receiver", "min_lamport_change": 1000001}))
+                .matches(&transaction_data)
+        );
+        // Without an account the largest change of any account counts.
+        assert!(filter(json!({
+            "program_id": "11111111111111111111111111111111",
+            "min_lamport_change": 1005000
+        }))
+        .matches(&transaction_data));
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231, test case 2.
+        assert_eq!(
+            hmac_sha256("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_sign_covers_timestamp() {
+        let signature = sign("secret", 1700000000, "{}");
+
+        assert_eq!(
+            signature,
+            format!("sha256={}", hmac_sha256("secret", "1700000000.{}"))
+        );
+        assert_ne!(signature, sign("secret", 1700000001, "{}"));
+    }
+}